@@ -0,0 +1,54 @@
+//! Throughput of reads sharing one connection, from a single thread versus several threads at once.
+//!
+//! The mock server delays each reply to stand in for network and PLC cycle latency. Requests from several threads are
+//! in flight together, so their throughput scales with the number of threads rather than being bound by latency.
+
+use std::time::Duration;
+
+use ads_client::{data_types::primitives::int::PlcInt, testing::MockAdsServer};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const READS: usize = 32;
+
+fn reads(c: &mut Criterion) {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+    connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+
+    server.set_reply_delay(Duration::from_millis(1));
+
+    let mut group = c.benchmark_group("reads");
+    group.throughput(Throughput::Elements(READS as u64));
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            for _ in 0..READS {
+                connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+            }
+        })
+    });
+
+    for threads in [4, 16] {
+        group.bench_function(format!("concurrent_{threads}_threads"), |b| {
+            b.iter(|| {
+                std::thread::scope(|scope| {
+                    for _ in 0..threads {
+                        scope.spawn(|| {
+                            for _ in 0..READS / threads {
+                                connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+                            }
+                        });
+                    }
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, reads);
+criterion_main!(benches);
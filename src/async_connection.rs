@@ -0,0 +1,235 @@
+//! An async facade over PlcConnection for tokio based services.
+//!
+//! Requests run on tokio's blocking thread pool, so they never hold up the async executor while waiting on the
+//! network. Several requests can be in flight on the connection at once, each reply matched to its request by invoke
+//! ID.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crossbeam_channel::RecvTimeoutError;
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::{
+    data_types::PlcDataType,
+    error::{PlcError, PlcResult},
+    plc_connection::{PlcConnection, CONNECTION_RETRY_INTERVAL},
+    subscription::{Subscription, SubscriptionOptions},
+};
+
+// How often a forwarding thread checks whether its stream was dropped
+const STREAM_CLOSED_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Async access to a PLC, sharing its connection with any clones of the underlying PlcConnection.
+///
+/// Dropping a request future before it completes does not abort a request already sent to the PLC: a read is
+/// discarded, while a write or RPC call may still take effect. The connection is left in a consistent state either
+/// way, as the request still runs to completion and its reply is matched to it by invoke ID, alongside any other
+/// requests in flight.
+#[derive(Clone)]
+pub struct AsyncPlcConnection {
+    connection: PlcConnection,
+}
+
+impl AsyncPlcConnection {
+    pub fn new(connection: PlcConnection) -> Self {
+        Self { connection }
+    }
+
+    /// The blocking connection this facade is built on.
+    pub fn blocking(&self) -> &PlcConnection {
+        &self.connection
+    }
+
+    /// Waits until a PLC is successfully connected over ADS, retrying every 2 seconds.
+    ///
+    /// Dropping the future stops retrying after the current attempt.
+    pub async fn run_connection_loop(&self) {
+        loop {
+            match self.spawn(|connection| connection.try_connect()).await {
+                Ok(()) => {
+                    tracing::info!("PLC connection successful");
+
+                    return;
+                }
+                Err(error) => {
+                    tracing::warn!(%error, "PLC connection failed, retrying in 2 seconds");
+                }
+            }
+
+            tokio::time::sleep(CONNECTION_RETRY_INTERVAL).await;
+        }
+    }
+
+    pub async fn disconnect(&self) {
+        self.spawn(|connection| {
+            connection.disconnect();
+
+            Ok(())
+        })
+        .await
+        .ok();
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_connected()
+    }
+
+    /// Read a symbol from the PLC.
+    pub async fn read_symbol<T: PlcDataType + Send + 'static>(&self, name: &str) -> PlcResult<T> {
+        let name = name.to_string();
+
+        self.spawn(move |connection| connection.read_symbol(&name))
+            .await
+    }
+
+    /// Write a symbol to the PLC.
+    pub async fn write_symbol<T: PlcDataType + Send + 'static>(
+        &self,
+        name: &str,
+        value: T,
+    ) -> PlcResult<()> {
+        let name = name.to_string();
+
+        self.spawn(move |connection| connection.write_symbol(&name, value))
+            .await
+    }
+
+    /// Calls an RPC method on the PLC that returns a value.
+    pub async fn fetch_from_rpc_method<T: PlcDataType + Send + 'static>(
+        &self,
+        name: &str,
+    ) -> PlcResult<T> {
+        let name = name.to_string();
+
+        self.spawn(move |connection| connection.fetch_from_rpc_method(&name))
+            .await
+    }
+
+    /// Calls an RPC method on the PLC.
+    pub async fn invoke_rpc_method(&self, name: &str) -> PlcResult<()> {
+        let name = name.to_string();
+
+        self.spawn(move |connection| connection.invoke_rpc_method(&name))
+            .await
+    }
+
+    /// Calls an RPC method on the PLC with one parameter.
+    pub async fn invoke_rpc_method_with_param<P: PlcDataType + Send + 'static>(
+        &self,
+        name: &str,
+        param: P,
+    ) -> PlcResult<()> {
+        let name = name.to_string();
+
+        self.spawn(move |connection| connection.invoke_rpc_method_with_param(&name, param))
+            .await
+    }
+
+    /// Calls an RPC method on the PLC with three parameters.
+    pub async fn invoke_rpc_method_with_three_params<
+        P1: PlcDataType + Send + 'static,
+        P2: PlcDataType + Send + 'static,
+        P3: PlcDataType + Send + 'static,
+    >(
+        &self,
+        name: &str,
+        param_1: P1,
+        param_2: P2,
+        param_3: P3,
+    ) -> PlcResult<()> {
+        let name = name.to_string();
+
+        self.spawn(move |connection| {
+            connection.invoke_rpc_method_with_three_params(&name, param_1, param_2, param_3)
+        })
+        .await
+    }
+
+    /// Subscribes to notifications from a symbol, delivered as a stream of values.
+    pub async fn subscribe<T: PlcDataType + Send + 'static>(
+        &self,
+        name: &str,
+    ) -> PlcResult<SubscriptionStream<T>> {
+        self.subscribe_with_options(name, SubscriptionOptions::new())
+            .await
+    }
+
+    /// Subscribes to notifications from a symbol, filtered and delivered according to the options.
+    ///
+    /// The delivery policy applies while the stream is not being polled. The stream ends if the connection drops, and
+    /// dropping the stream deletes the notification.
+    pub async fn subscribe_with_options<T: PlcDataType + Send + 'static>(
+        &self,
+        name: &str,
+        options: SubscriptionOptions<T>,
+    ) -> PlcResult<SubscriptionStream<T>> {
+        let name = name.to_string();
+
+        let subscription = self
+            .spawn(move |connection| connection.subscribe_with_options(&name, options))
+            .await?;
+
+        Ok(SubscriptionStream::forward(subscription))
+    }
+
+    async fn spawn<R: Send + 'static>(
+        &self,
+        request: impl FnOnce(&PlcConnection) -> PlcResult<R> + Send + 'static,
+    ) -> PlcResult<R> {
+        let connection = self.connection.clone();
+
+        match tokio::task::spawn_blocking(move || request(&connection)).await {
+            Ok(result) => result,
+            Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+            // NB: only happens while the runtime shuts down
+            Err(_) => Err(PlcError::NotConnected),
+        }
+    }
+}
+
+impl From<PlcConnection> for AsyncPlcConnection {
+    fn from(connection: PlcConnection) -> Self {
+        Self::new(connection)
+    }
+}
+
+/// A stream of values from a PLC subscription.
+pub struct SubscriptionStream<T> {
+    receiver: mpsc::Receiver<T>,
+}
+
+impl<T: PlcDataType + Send + 'static> SubscriptionStream<T> {
+    // A dedicated thread hands samples over, as waiting for them would tie up a blocking pool thread for the lifetime
+    // of the subscription
+    fn forward(subscription: Subscription<T>) -> Self {
+        let (sender, receiver) = mpsc::channel(1);
+
+        std::thread::spawn(move || loop {
+            let value = match subscription.recv_timeout(STREAM_CLOSED_CHECK_INTERVAL) {
+                Ok(value) => value,
+                Err(RecvTimeoutError::Timeout) if sender.is_closed() => return,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+
+            if sender.blocking_send(value).is_err() {
+                return;
+            }
+        });
+
+        Self { receiver }
+    }
+}
+
+impl<T> Stream for SubscriptionStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.receiver.poll_recv(cx)
+    }
+}
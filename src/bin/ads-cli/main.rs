@@ -0,0 +1,545 @@
+//! Reads, writes, browses and watches symbols on a PLC from the command line, and generates Rust bindings for them.
+//!
+//! The PLC is reached through the ADS router at `ADS_ROUTER_IP` and `ADS_ROUTER_PORT`, and addressed by
+//! `PLC_AMS_NET_ID` and `PLC_AMS_PORT`. When there is no local router, e.g: on a Linux gateway with a static route on
+//! the PLC, `LOCAL_AMS_NET_ID` and `LOCAL_AMS_PORT` give the gateway's own AMS address.
+
+use std::{collections::HashMap, fmt, net::ToSocketAddrs, process::ExitCode};
+
+use ads_client::{
+    codegen::TypeTable,
+    data_types::value::{PlcType, PlcValue},
+    plc_connection::{
+        parse_ams_address_from_env, parse_socket_address_from_env, PlcConnection,
+        PlcConnectionBuilder,
+    },
+};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::json;
+
+const ROUTER_IP: &str = "ADS_ROUTER_IP";
+const ROUTER_PORT: &str = "ADS_ROUTER_PORT";
+const PLC_NET_ID: &str = "PLC_AMS_NET_ID";
+const PLC_PORT: &str = "PLC_AMS_PORT";
+const LOCAL_NET_ID: &str = "LOCAL_AMS_NET_ID";
+const LOCAL_PORT: &str = "LOCAL_AMS_PORT";
+
+fn main() -> ExitCode {
+    let matches = command().get_matches();
+
+    let output = Output {
+        json: matches.get_flag("json"),
+    };
+
+    match run(&matches, output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn command() -> Command {
+    let symbol = || {
+        Arg::new("symbol")
+            .required(true)
+            .help("The symbol's path, e.g: MAIN.nCounter")
+    };
+
+    let data_type = || {
+        Arg::new("type")
+            .long("type")
+            .help("The symbol's type, e.g: INT or STRING(20), instead of looking it up on the PLC")
+    };
+
+    Command::new("ads-cli")
+        .about("Reads, writes, browses and watches symbols on a PLC over ADS")
+        .after_help(format!(
+            "The PLC is configured through {ROUTER_IP}, {ROUTER_PORT}, {PLC_NET_ID} and {PLC_PORT}, and optionally \
+             {LOCAL_NET_ID} and {LOCAL_PORT}."
+        ))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Prints JSON instead of text"),
+        )
+        .subcommand(Command::new("info").about("Shows the PLC's runtime name, version and state"))
+        .subcommand(Command::new("state").about("Shows the PLC's ADS state"))
+        .subcommand(
+            Command::new("read")
+                .about("Reads a symbol")
+                .arg(symbol())
+                .arg(data_type()),
+        )
+        .subcommand(
+            Command::new("write")
+                .about("Writes a symbol")
+                .arg(symbol())
+                .arg(
+                    Arg::new("value")
+                        .required(true)
+                        .allow_hyphen_values(true)
+                        .help("An IEC literal, e.g: 5, 16#FF, TRUE, T#1s or 'text'"),
+                )
+                .arg(data_type()),
+        )
+        .subcommand(
+            Command::new("browse")
+                .about("Lists the PLC's symbols")
+                .arg(Arg::new("prefix").help("Only lists symbols starting with the prefix, e.g: MAIN.")),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Prints symbols whenever they change, until interrupted")
+                .arg(Arg::new("symbols").required(true).num_args(1..))
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Stops after this many values"),
+                ),
+        )
+        .subcommand(
+            Command::new("call")
+                .about("Calls an RPC method")
+                .arg(Arg::new("method").required(true).help("The method's path, e.g: MAIN.fbAxis#Move"))
+                .arg(
+                    Arg::new("args")
+                        .num_args(0..)
+                        .help("Parameters as IEC literals, e.g: REAL#1.5 or T#1s, where integers without a type are DINTs"),
+                )
+                .arg(
+                    Arg::new("returns")
+                        .long("returns")
+                        .help("The method's return type, e.g: BOOL, to print its result"),
+                ),
+        )
+        .subcommand(
+            Command::new("codegen")
+                .about("Generates Rust bindings for the PLC's data types and symbols")
+                .arg(
+                    Arg::new("prefix")
+                        .help("Only generates symbols starting with the prefix and the types they use, e.g: MAIN."),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("Writes the bindings to a file instead of printing them"),
+                ),
+        )
+}
+
+fn run(matches: &ArgMatches, output: Output) -> Result<()> {
+    let connection = connect()?;
+
+    match matches.subcommand() {
+        Some(("info", _)) => info(&connection, output),
+        Some(("state", _)) => state(&connection, output),
+        Some(("read", arguments)) => read(&connection, arguments, output),
+        Some(("write", arguments)) => write(&connection, arguments, output),
+        Some(("browse", arguments)) => browse(&connection, arguments, output),
+        Some(("watch", arguments)) => watch(&connection, arguments, output),
+        Some(("call", arguments)) => call(&connection, arguments, output),
+        Some(("codegen", arguments)) => codegen(&connection, arguments, output),
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+fn connect() -> Result<PlcConnection> {
+    let ads_router_address = parse_socket_address_from_env(ROUTER_IP, ROUTER_PORT)?
+        .to_socket_addrs()
+        .context(format!("Could not resolve {ROUTER_IP}."))?
+        .next()
+        .context(format!("Could not resolve {ROUTER_IP}."))?;
+
+    let plc_ams_address = parse_ams_address_from_env(PLC_NET_ID, PLC_PORT)?;
+
+    let local_ams_address = match std::env::var_os(LOCAL_NET_ID) {
+        Some(_) => Some(parse_ams_address_from_env(LOCAL_NET_ID, LOCAL_PORT)?),
+        None => None,
+    };
+
+    let connection = PlcConnectionBuilder::new(ads_router_address, plc_ams_address)
+        .with_local_ams_address(local_ams_address)
+        .build();
+
+    connection
+        .try_connect()
+        .context(format!("Could not connect to the PLC at {plc_ams_address}"))?;
+
+    Ok(connection)
+}
+
+fn info(connection: &PlcConnection, output: Output) -> Result<()> {
+    let device_info = connection.device_info()?;
+    let ads_state = connection.ads_state()?;
+
+    let version = format!(
+        "{}.{}.{}",
+        device_info.major, device_info.minor, device_info.version
+    );
+
+    output.print(
+        format!("{} {version}\nState: {ads_state:?}", device_info.name),
+        json!({
+            "name": device_info.name,
+            "version": version,
+            "state": format!("{ads_state:?}"),
+        }),
+    );
+
+    Ok(())
+}
+
+fn state(connection: &PlcConnection, output: Output) -> Result<()> {
+    let ads_state = connection.ads_state()?;
+
+    output.print(
+        format!("{ads_state:?}"),
+        json!({ "state": format!("{ads_state:?}") }),
+    );
+
+    Ok(())
+}
+
+fn read(connection: &PlcConnection, arguments: &ArgMatches, output: Output) -> Result<()> {
+    let name = string(arguments, "symbol");
+    let symbol = SymbolType::resolve(connection, name, arguments.get_one("type"))?;
+
+    let data = connection.read_symbol_raw(name, symbol.size)?;
+    let value = symbol.decode(&data)?;
+
+    output.print(
+        value.to_string(),
+        json!({ "symbol": name, "type": symbol.name, "value": value.to_json() }),
+    );
+
+    Ok(())
+}
+
+fn write(connection: &PlcConnection, arguments: &ArgMatches, output: Output) -> Result<()> {
+    let name = string(arguments, "symbol");
+    let symbol = SymbolType::resolve(connection, name, arguments.get_one("type"))?;
+
+    let Some(plc_type) = symbol.plc_type else {
+        bail!(
+            "Cannot write {name}, as values of type {} are not supported",
+            symbol.name
+        );
+    };
+
+    let literal = string(arguments, "value");
+
+    let value = match plc_type {
+        // NB: text without quotes is taken as is, to save quoting it twice in the shell
+        PlcType::String(_) if !literal.starts_with('\'') => PlcValue::String(literal.to_string()),
+        plc_type => plc_type.parse_literal(literal)?,
+    };
+
+    connection.write_symbol_raw(name, &plc_type.encode(&value)?)?;
+
+    output.print(
+        format!("{name} = {value}"),
+        json!({ "symbol": name, "type": symbol.name, "value": to_json(&value) }),
+    );
+
+    Ok(())
+}
+
+fn browse(connection: &PlcConnection, arguments: &ArgMatches, output: Output) -> Result<()> {
+    let prefix = arguments
+        .get_one::<String>("prefix")
+        .map(|prefix| prefix.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let (mut symbols, _) = connection.upload_symbol_info()?;
+
+    // NB: symbol names on the PLC are case-insensitive
+    symbols.retain(|symbol| symbol.name.to_ascii_lowercase().starts_with(&prefix));
+    symbols.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if output.json {
+        let symbols: Vec<_> = symbols
+            .iter()
+            .map(|symbol| {
+                json!({
+                    "name": symbol.name,
+                    "type": symbol.typ,
+                    "size": symbol.size,
+                    "index_group": symbol.ix_group,
+                    "index_offset": symbol.ix_offset,
+                })
+            })
+            .collect();
+
+        println!("{}", serde_json::Value::from(symbols));
+
+        return Ok(());
+    }
+
+    let name_width = symbols
+        .iter()
+        .map(|symbol| symbol.name.len())
+        .max()
+        .unwrap_or(0);
+    let type_width = symbols
+        .iter()
+        .map(|symbol| symbol.typ.len())
+        .max()
+        .unwrap_or(0);
+
+    for symbol in &symbols {
+        println!(
+            "{:name_width$}  {:type_width$}  {} bytes",
+            symbol.name, symbol.typ, symbol.size
+        );
+    }
+
+    Ok(())
+}
+
+fn watch(connection: &PlcConnection, arguments: &ArgMatches, output: Output) -> Result<()> {
+    let names: Vec<&String> = arguments.get_many("symbols").unwrap_or_default().collect();
+    let count = arguments.get_one::<usize>("count").copied();
+
+    let receiver = connection.notification_receiver()?;
+
+    let mut watched = HashMap::new();
+
+    for name in names {
+        let symbol = SymbolType::resolve(connection, name, None)?;
+        let handle = connection.subscribe_raw(name, symbol.size)?;
+
+        watched.insert(handle, (name, symbol));
+    }
+
+    let mut printed = 0;
+
+    while count.is_none_or(|count| printed < count) {
+        let notification = receiver
+            .recv()
+            .map_err(|_| anyhow!("Lost the connection to the PLC"))?;
+
+        for sample in notification.samples() {
+            let Some((name, symbol)) = watched.get(&sample.handle) else {
+                continue;
+            };
+
+            let value = symbol.decode(sample.data)?;
+            let time = chrono::Local::now();
+
+            output.print(
+                format!("{} {name} = {value}", time.format("%H:%M:%S%.3f")),
+                json!({ "time": time.to_rfc3339(), "symbol": name, "value": value.to_json() }),
+            );
+
+            printed += 1;
+
+            if count.is_some_and(|count| printed >= count) {
+                break;
+            }
+        }
+    }
+
+    for handle in watched.keys() {
+        connection.unsubscribe(*handle);
+    }
+
+    Ok(())
+}
+
+fn call(connection: &PlcConnection, arguments: &ArgMatches, output: Output) -> Result<()> {
+    let name = string(arguments, "method");
+
+    let params = arguments
+        .get_many::<String>("args")
+        .unwrap_or_default()
+        .map(|argument| {
+            let value: PlcValue = argument.parse()?;
+
+            Ok(value.plc_type().encode(&value)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let params: Vec<&[u8]> = params.iter().map(Vec::as_slice).collect();
+
+    let Some(returns) = arguments.get_one::<String>("returns") else {
+        connection.invoke_rpc_method_with_params(name, &params)?;
+
+        output.print(String::new(), json!({ "method": name, "result": null }));
+
+        return Ok(());
+    };
+
+    let plc_type = parse_type(returns)?;
+
+    let data = connection.call_rpc_method_raw(name, &params, plc_type.size())?;
+    let value = plc_type.decode(&data)?;
+
+    output.print(
+        value.to_string(),
+        json!({ "method": name, "result": to_json(&value) }),
+    );
+
+    Ok(())
+}
+
+/// A symbol's type, either elementary or only known by its name and size.
+struct SymbolType {
+    name: String,
+    size: usize,
+    plc_type: Option<PlcType>,
+}
+
+impl SymbolType {
+    /// Takes the type given on the command line, or otherwise looks it up on the PLC.
+    fn resolve(connection: &PlcConnection, name: &str, given: Option<&String>) -> Result<Self> {
+        if let Some(given) = given {
+            let plc_type = parse_type(given)?;
+
+            return Ok(Self {
+                name: plc_type.to_string(),
+                size: plc_type.size(),
+                plc_type: Some(plc_type),
+            });
+        }
+
+        let symbol = connection.symbol_info(name)?;
+
+        Ok(Self {
+            plc_type: PlcType::from_name(&symbol.typ),
+            name: symbol.typ,
+            size: symbol.size,
+        })
+    }
+
+    /// Decodes a value, or keeps its bytes if the type is not an elementary one.
+    fn decode(&self, data: &[u8]) -> Result<Reading> {
+        match self.plc_type {
+            Some(plc_type) => Ok(Reading::Value(plc_type.decode(data)?)),
+            None => Ok(Reading::Bytes(data.to_vec())),
+        }
+    }
+}
+
+/// A value read from the PLC, or its bytes if its type is not an elementary one, e.g: a structure.
+enum Reading {
+    Value(PlcValue),
+    Bytes(Vec<u8>),
+}
+
+impl Reading {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Reading::Value(value) => to_json(value),
+            Reading::Bytes(_) => self.to_string().into(),
+        }
+    }
+}
+
+impl fmt::Display for Reading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reading::Value(value) => write!(f, "{value}"),
+            Reading::Bytes(data) => {
+                for byte in data {
+                    write!(f, "{byte:02x}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Numbers, booleans and text as their JSON equivalents, and other values as their IEC literal.
+fn to_json(value: &PlcValue) -> serde_json::Value {
+    match value {
+        PlcValue::Bool(value) => (*value).into(),
+        PlcValue::SInt(value) => (*value).into(),
+        PlcValue::USInt(value) | PlcValue::Byte(value) => (*value).into(),
+        PlcValue::Int(value) => (*value).into(),
+        PlcValue::UInt(value) | PlcValue::Word(value) => (*value).into(),
+        PlcValue::DInt(value) => (*value).into(),
+        PlcValue::UDInt(value) | PlcValue::DWord(value) => (*value).into(),
+        PlcValue::LInt(value) => (*value).into(),
+        PlcValue::ULInt(value) | PlcValue::LWord(value) => (*value).into(),
+        PlcValue::Real(value) => (*value).into(),
+        PlcValue::LReal(value) => (*value).into(),
+        PlcValue::String(text) => text.as_str().into(),
+        value => value.to_string().into(),
+    }
+}
+
+fn parse_type(name: &str) -> Result<PlcType> {
+    PlcType::from_name(name).ok_or_else(|| anyhow!("{name} is not a supported type"))
+}
+
+fn codegen(connection: &PlcConnection, arguments: &ArgMatches, output: Output) -> Result<()> {
+    let prefix = arguments
+        .get_one::<String>("prefix")
+        .map(|prefix| prefix.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let mut type_table = TypeTable::from_plc(connection)?;
+
+    // NB: symbol names on the PLC are case-insensitive
+    type_table.retain_symbols(|symbol| symbol.name.to_ascii_lowercase().starts_with(&prefix));
+    type_table.symbols.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let plc_ams_address = parse_ams_address_from_env(PLC_NET_ID, PLC_PORT)?;
+    let source = type_table.to_rust(&format!("the PLC at {plc_ams_address}"));
+
+    let Some(path) = arguments.get_one::<String>("output") else {
+        print!("{source}");
+
+        return Ok(());
+    };
+
+    std::fs::write(path, &source).context(format!("Could not write {path}"))?;
+
+    output.print(
+        format!(
+            "Wrote {} data types and {} symbols to {path}",
+            type_table.data_types.len(),
+            type_table.symbols.len()
+        ),
+        json!({
+            "output": path,
+            "data_types": type_table.data_types.len(),
+            "symbols": type_table.symbols.len(),
+        }),
+    );
+
+    Ok(())
+}
+
+fn string<'a>(arguments: &'a ArgMatches, id: &str) -> &'a str {
+    arguments
+        .get_one::<String>(id)
+        .expect("a required argument")
+}
+
+/// Prints results as text for people or JSON for scripts.
+#[derive(Clone, Copy)]
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn print(self, text: String, json: serde_json::Value) {
+        if self.json {
+            println!("{json}");
+        } else if !text.is_empty() {
+            println!("{text}");
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! Captures the AMS/TCP frames sent to and received from the PLC in pcap format, for inspection in Wireshark.
+//!
+//! The frames are wrapped in synthetic IP and TCP headers, starting each connection with a handshake. The PLC's side
+//! always uses port 48898, so the ADS dissector recognises the traffic even when going through e.g: a tunnel.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{recording::Direction, transport::FrameTap};
+
+/// The registered AMS/TCP port, which the Wireshark ADS dissector is bound to.
+pub const ADS_TCP_PORT: u16 = 48898;
+
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+const LINKTYPE_RAW: u32 = 101;
+const SNAPLEN: u32 = 262_144;
+
+const TCP_SYN: u8 = 0x02;
+const TCP_PSH_ACK: u8 = 0x18;
+const TCP_ACK: u8 = 0x10;
+const TCP_SYN_ACK: u8 = 0x12;
+
+// Keeps every segment within an IPv4 packet's 16-bit length
+const MAX_SEGMENT_SIZE: usize = 65_000;
+
+/// Writes the frames of every connection a `PlcConnection` makes to a pcap capture, see
+/// `PlcConnectionBuilder::with_capture`.
+pub struct PacketCapture {
+    state: Mutex<CaptureState>,
+}
+
+struct CaptureState {
+    writer: Box<dyn Write + Send>,
+    flow: Option<Flow>,
+}
+
+/// The synthetic TCP connection frames are currently written to.
+struct Flow {
+    client: SocketAddr,
+    server: SocketAddr,
+    client_sequence: u32,
+    server_sequence: u32,
+}
+
+impl PacketCapture {
+    /// Captures to a new file, replacing any existing one.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Captures to any writer, e.g: an in-memory buffer.
+    pub fn new(writer: impl Write + Send + 'static) -> io::Result<Self> {
+        let mut writer: Box<dyn Write + Send> = Box::new(writer);
+
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        // Time zone and timestamp accuracy, always zero
+        header.extend_from_slice(&[0; 8]);
+        header.extend_from_slice(&SNAPLEN.to_le_bytes());
+        header.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.flush()?;
+
+        Ok(Self {
+            state: Mutex::new(CaptureState { writer, flow: None }),
+        })
+    }
+}
+
+impl FrameTap for PacketCapture {
+    fn connected(&self, local: SocketAddr, peer: SocketAddr) {
+        let mut state = self.state.lock().unwrap();
+
+        let mut flow = Flow {
+            client: local,
+            server: SocketAddr::new(peer.ip(), ADS_TCP_PORT),
+            client_sequence: 0,
+            server_sequence: 0,
+        };
+
+        // The handshake's SYNs each take up a sequence number
+        let syn = flow.segment(Direction::Sent, TCP_SYN, &[]);
+        flow.client_sequence = 1;
+
+        let syn_ack = flow.segment(Direction::Received, TCP_SYN_ACK, &[]);
+        flow.server_sequence = 1;
+
+        let ack = flow.segment(Direction::Sent, TCP_ACK, &[]);
+
+        let packets = [syn, syn_ack, ack];
+
+        state.flow = Some(flow);
+
+        state.write(&packets);
+    }
+
+    fn frame(&self, direction: Direction, frame: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+
+        let Some(flow) = &mut state.flow else {
+            return;
+        };
+
+        let packets: Vec<Vec<u8>> = frame
+            .chunks(MAX_SEGMENT_SIZE)
+            .map(|segment| {
+                let packet = flow.segment(direction, TCP_PSH_ACK, segment);
+
+                let sequence = match direction {
+                    Direction::Sent => &mut flow.client_sequence,
+                    Direction::Received => &mut flow.server_sequence,
+                };
+
+                *sequence = sequence.wrapping_add(segment.len() as u32);
+
+                packet
+            })
+            .collect();
+
+        state.write(&packets);
+    }
+}
+
+impl CaptureState {
+    fn write(&mut self, packets: &[Vec<u8>]) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let result = packets.iter().try_for_each(|packet| {
+            let mut record = Vec::with_capacity(16 + packet.len());
+            record.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+            record.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+            record.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            record.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            record.extend_from_slice(packet);
+
+            self.writer.write_all(&record)
+        });
+
+        // NB: flushed every time, so a capture of a crashing application is still complete
+        if let Err(error) = result.and_then(|_| self.writer.flush()) {
+            tracing::warn!(%error, "Failed to write packet capture");
+        }
+    }
+}
+
+impl Flow {
+    /// An IP packet carrying a TCP segment in the given direction.
+    fn segment(&self, direction: Direction, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let (source, destination, sequence, acknowledgement) = match direction {
+            Direction::Sent => (
+                self.client,
+                self.server,
+                self.client_sequence,
+                self.server_sequence,
+            ),
+            Direction::Received => (
+                self.server,
+                self.client,
+                self.server_sequence,
+                self.client_sequence,
+            ),
+        };
+
+        let mut tcp = Vec::with_capacity(20 + payload.len());
+        tcp.extend_from_slice(&source.port().to_be_bytes());
+        tcp.extend_from_slice(&destination.port().to_be_bytes());
+        tcp.extend_from_slice(&sequence.to_be_bytes());
+        tcp.extend_from_slice(&acknowledgement.to_be_bytes());
+        tcp.push(5 << 4);
+        tcp.push(flags);
+        tcp.extend_from_slice(&u16::MAX.to_be_bytes());
+        // Checksum, filled in below, and urgent pointer
+        tcp.extend_from_slice(&[0; 4]);
+        tcp.extend_from_slice(payload);
+
+        ip_packet(source.ip(), destination.ip(), tcp)
+    }
+}
+
+/// Wraps a TCP segment in an IPv4 or IPv6 header, filling in the segment's checksum.
+fn ip_packet(source: IpAddr, destination: IpAddr, mut tcp: Vec<u8>) -> Vec<u8> {
+    let tcp_length = tcp.len();
+
+    match (source, destination) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => {
+            let mut pseudo_header = Vec::with_capacity(12);
+            pseudo_header.extend_from_slice(&source.octets());
+            pseudo_header.extend_from_slice(&destination.octets());
+            pseudo_header.extend_from_slice(&[0, 6]);
+            pseudo_header.extend_from_slice(&(tcp_length as u16).to_be_bytes());
+
+            let tcp_checksum = checksum(&[&pseudo_header, &tcp]);
+            tcp[16..18].copy_from_slice(&tcp_checksum.to_be_bytes());
+
+            let mut packet = Vec::with_capacity(20 + tcp_length);
+            packet.extend_from_slice(&[0x45, 0]);
+            packet.extend_from_slice(&((20 + tcp_length) as u16).to_be_bytes());
+            // Identification, then don't fragment
+            packet.extend_from_slice(&[0, 0, 0x40, 0]);
+            // Time to live and protocol, then the header checksum filled in below
+            packet.extend_from_slice(&[64, 6, 0, 0]);
+            packet.extend_from_slice(&source.octets());
+            packet.extend_from_slice(&destination.octets());
+
+            let header_checksum = checksum(&[&packet]);
+            packet[10..12].copy_from_slice(&header_checksum.to_be_bytes());
+
+            packet.extend_from_slice(&tcp);
+            packet
+        }
+        (source, destination) => {
+            let source = to_ipv6(source);
+            let destination = to_ipv6(destination);
+
+            let mut pseudo_header = Vec::with_capacity(40);
+            pseudo_header.extend_from_slice(&source.octets());
+            pseudo_header.extend_from_slice(&destination.octets());
+            pseudo_header.extend_from_slice(&(tcp_length as u32).to_be_bytes());
+            pseudo_header.extend_from_slice(&[0, 0, 0, 6]);
+
+            let tcp_checksum = checksum(&[&pseudo_header, &tcp]);
+            tcp[16..18].copy_from_slice(&tcp_checksum.to_be_bytes());
+
+            let mut packet = Vec::with_capacity(40 + tcp_length);
+            packet.extend_from_slice(&[0x60, 0, 0, 0]);
+            packet.extend_from_slice(&(tcp_length as u16).to_be_bytes());
+            // Next header and hop limit
+            packet.extend_from_slice(&[6, 64]);
+            packet.extend_from_slice(&source.octets());
+            packet.extend_from_slice(&destination.octets());
+            packet.extend_from_slice(&tcp);
+            packet
+        }
+    }
+}
+
+fn to_ipv6(address: IpAddr) -> std::net::Ipv6Addr {
+    match address {
+        IpAddr::V4(address) => address.to_ipv6_mapped(),
+        IpAddr::V6(address) => address,
+    }
+}
+
+/// The internet checksum of the concatenated chunks, each of which but the last must have an even length.
+fn checksum(chunks: &[&[u8]]) -> u16 {
+    let mut sum: u32 = chunks
+        .iter()
+        .flat_map(|chunk| chunk.chunks(2))
+        .map(|word| u32::from(u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)])))
+        .sum();
+
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(data)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// The IP packets in a capture.
+    fn packets(capture: &[u8]) -> Vec<&[u8]> {
+        let mut packets = Vec::new();
+        let mut offset = 24;
+
+        while offset < capture.len() {
+            let length =
+                u32::from_le_bytes(capture[offset + 8..offset + 12].try_into().unwrap()) as usize;
+
+            packets.push(&capture[offset + 16..offset + 16 + length]);
+            offset += 16 + length;
+        }
+
+        packets
+    }
+
+    #[test]
+    fn connections_start_with_a_handshake() {
+        let buffer = Buffer::default();
+        let capture = PacketCapture::new(buffer.clone()).unwrap();
+
+        capture.connected(
+            "10.0.0.2:50000".parse().unwrap(),
+            "10.0.0.1:1234".parse().unwrap(),
+        );
+
+        let data = buffer.0.lock().unwrap();
+
+        assert_eq!(data[..4], PCAP_MAGIC.to_le_bytes());
+        assert_eq!(data[20..24], LINKTYPE_RAW.to_le_bytes());
+
+        let packets = packets(&data);
+        let flags: Vec<u8> = packets.iter().map(|packet| packet[20 + 13]).collect();
+
+        assert_eq!(flags, [TCP_SYN, TCP_SYN_ACK, TCP_ACK]);
+
+        // The PLC's side is always on the ADS port
+        assert_eq!(packets[0][22..24], ADS_TCP_PORT.to_be_bytes());
+        assert_eq!(packets[1][20..22], ADS_TCP_PORT.to_be_bytes());
+    }
+
+    #[test]
+    fn frames_follow_the_sequence_numbers() {
+        let buffer = Buffer::default();
+        let capture = PacketCapture::new(buffer.clone()).unwrap();
+
+        capture.connected(
+            "127.0.0.1:50000".parse().unwrap(),
+            "127.0.0.1:48898".parse().unwrap(),
+        );
+        capture.frame(Direction::Sent, &[1; 10]);
+        capture.frame(Direction::Received, &[2; 7]);
+        capture.frame(Direction::Sent, &[3; 4]);
+
+        let data = buffer.0.lock().unwrap();
+        let packets = packets(&data);
+        let sequence = |packet: &[u8]| u32::from_be_bytes(packet[24..28].try_into().unwrap());
+        let acknowledgement =
+            |packet: &[u8]| u32::from_be_bytes(packet[28..32].try_into().unwrap());
+
+        assert_eq!(sequence(packets[3]), 1);
+        assert_eq!(sequence(packets[4]), 1);
+        assert_eq!(acknowledgement(packets[4]), 11);
+        assert_eq!(sequence(packets[5]), 11);
+        assert_eq!(acknowledgement(packets[5]), 8);
+        assert_eq!(packets[5][40..], [3; 4]);
+    }
+
+    #[test]
+    fn checksums_verify() {
+        let packet = Flow {
+            client: "192.168.1.2:50000".parse().unwrap(),
+            server: "192.168.1.1:48898".parse().unwrap(),
+            client_sequence: 1,
+            server_sequence: 1,
+        }
+        .segment(Direction::Sent, TCP_PSH_ACK, &[1, 2, 3]);
+
+        // Summing a header including its checksum gives zero
+        assert_eq!(checksum(&[&packet[..20]]), 0);
+
+        let mut pseudo_header = packet[12..20].to_vec();
+        pseudo_header.extend_from_slice(&[0, 6, 0, 23]);
+
+        assert_eq!(checksum(&[&pseudo_header, &packet[20..]]), 0);
+    }
+}
@@ -0,0 +1,411 @@
+//! Coalescing of concurrent single symbol reads and writes into ADS sum commands.
+//!
+//! The first request to arrive opens a batch and waits out the coalescing window, collecting every request that arrives
+//! meanwhile. It then sends the whole batch as one sum command, or several if there are more requests than one sum
+//! command takes, and hands each waiting caller its own result.
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::error::{PlcError, PlcResult};
+
+/// Sum commands are limited to 500 sub-commands by TwinCAT.
+pub(crate) const MAX_BATCH_SIZE: usize = 500;
+
+/// Counters describing how requests were coalesced, accumulated over the lifetime of a connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CoalescingStats {
+    /// Batches sent, whether as a sum command or a single request.
+    pub batches: u64,
+    /// Requests that went through the coalescing layer.
+    pub requests: u64,
+    /// Requests that shared a sum command with at least one other request.
+    pub coalesced_requests: u64,
+    /// The most requests sent in one batch.
+    pub largest_batch: u64,
+    /// Batches sent as individual requests because the PLC rejected the sum command.
+    pub fallbacks: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct CoalescingMetrics {
+    batches: AtomicU64,
+    requests: AtomicU64,
+    coalesced_requests: AtomicU64,
+    largest_batch: AtomicU64,
+    fallbacks: AtomicU64,
+}
+
+impl CoalescingMetrics {
+    fn record_batch(&self, size: usize) {
+        let size = size as u64;
+
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.requests.fetch_add(size, Ordering::Relaxed);
+        self.largest_batch.fetch_max(size, Ordering::Relaxed);
+
+        if size > 1 {
+            self.coalesced_requests.fetch_add(size, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_fallback(&self) {
+        self.fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> CoalescingStats {
+        CoalescingStats {
+            batches: self.batches.load(Ordering::Relaxed),
+            requests: self.requests.load(Ordering::Relaxed),
+            coalesced_requests: self.coalesced_requests.load(Ordering::Relaxed),
+            largest_batch: self.largest_batch.load(Ordering::Relaxed),
+            fallbacks: self.fallbacks.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub(crate) struct ReadRequest {
+    pub(crate) symbol: String,
+    pub(crate) handle: u32,
+    pub(crate) length: usize,
+}
+
+pub(crate) struct WriteRequest {
+    pub(crate) symbol: String,
+    pub(crate) handle: u32,
+    pub(crate) data: Vec<u8>,
+}
+
+/// A request in a batch, together with the caller waiting for its result.
+pub(crate) struct Pending<R, T> {
+    pub(crate) request: R,
+    waiter: Sender<PlcResult<T>>,
+}
+
+impl<R, T> Pending<R, T> {
+    pub(crate) fn new(request: R) -> (Self, Receiver<PlcResult<T>>) {
+        let (waiter, receiver) = crossbeam_channel::bounded(1);
+
+        (Self { request, waiter }, receiver)
+    }
+
+    pub(crate) fn complete(self, result: PlcResult<T>) {
+        // The caller only stops waiting if its thread panicked
+        self.waiter.send(result).ok();
+    }
+}
+
+type Batch<R, T> = Vec<Pending<R, T>>;
+
+pub(crate) type ReadBatch = Batch<ReadRequest, Vec<u8>>;
+pub(crate) type WriteBatch = Batch<WriteRequest, ()>;
+
+pub(crate) struct Coalescer {
+    window: Duration,
+    metrics: std::sync::Arc<CoalescingMetrics>,
+    reads: Lane<ReadRequest, Vec<u8>>,
+    writes: Lane<WriteRequest, ()>,
+}
+
+impl Coalescer {
+    pub(crate) fn new(window: Duration, metrics: std::sync::Arc<CoalescingMetrics>) -> Self {
+        Self {
+            window,
+            metrics,
+            reads: Lane::default(),
+            writes: Lane::default(),
+        }
+    }
+
+    pub(crate) fn metrics(&self) -> &CoalescingMetrics {
+        &self.metrics
+    }
+
+    /// Reads through the current batch, calling send with each of its sum commands if this caller opened it.
+    pub(crate) fn read(
+        &self,
+        request: ReadRequest,
+        send: impl FnMut(ReadBatch),
+    ) -> PlcResult<Vec<u8>> {
+        let (receiver, batches) = self.reads.join(request, self.window);
+
+        self.finish(receiver, batches, send)
+    }
+
+    /// Writes through the current batch, calling send with each of its sum commands if this caller opened it.
+    pub(crate) fn write(
+        &self,
+        request: WriteRequest,
+        send: impl FnMut(WriteBatch),
+    ) -> PlcResult<()> {
+        let (receiver, batches) = self.writes.join(request, self.window);
+
+        self.finish(receiver, batches, send)
+    }
+
+    fn finish<R, T>(
+        &self,
+        receiver: Receiver<PlcResult<T>>,
+        batches: Vec<Batch<R, T>>,
+        mut send: impl FnMut(Batch<R, T>),
+    ) -> PlcResult<T> {
+        for batch in batches {
+            self.metrics.record_batch(batch.len());
+
+            send(batch);
+        }
+
+        receiver.recv().unwrap_or(Err(PlcError::NotConnected))
+    }
+}
+
+struct Lane<R, T> {
+    state: Mutex<LaneState<R, T>>,
+    full: Condvar,
+}
+
+impl<R, T> Default for Lane<R, T> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(LaneState {
+                batch: Vec::new(),
+                has_leader: false,
+            }),
+            full: Condvar::new(),
+        }
+    }
+}
+
+struct LaneState<R, T> {
+    batch: Batch<R, T>,
+    // Whether a caller is already waiting out the window to send the current batch
+    has_leader: bool,
+}
+
+impl<R, T> Lane<R, T> {
+    /// Adds a request to the current batch, returning it split into sum commands if this caller opened it and should
+    /// send them, or no sum commands otherwise.
+    fn join(&self, request: R, window: Duration) -> (Receiver<PlcResult<T>>, Vec<Batch<R, T>>) {
+        let (pending, receiver) = Pending::new(request);
+
+        let mut state = self.state.lock().unwrap();
+
+        state.batch.push(pending);
+
+        if state.has_leader {
+            if state.batch.len() >= MAX_BATCH_SIZE {
+                self.full.notify_one();
+            }
+
+            return (receiver, Vec::new());
+        }
+
+        state.has_leader = true;
+
+        let deadline = Instant::now() + window;
+
+        while state.batch.len() < MAX_BATCH_SIZE {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            if timeout.is_zero() {
+                break;
+            }
+
+            state = self.full.wait_timeout(state, timeout).unwrap().0;
+        }
+
+        let mut requests = std::mem::take(&mut state.batch).into_iter().peekable();
+        let mut batches = Vec::new();
+
+        // NB: requests beyond a full sum command go out right after it, as nothing else may arrive to send them
+        while requests.peek().is_some() {
+            batches.push(requests.by_ref().take(MAX_BATCH_SIZE).collect());
+        }
+
+        state.has_leader = false;
+
+        (receiver, batches)
+    }
+}
+
+/// Encodes the sub-commands of an ADS sum read, returning the request and the expected reply length.
+pub(crate) fn sum_read_request(batch: &ReadBatch) -> (Vec<u8>, usize) {
+    let mut request = Vec::with_capacity(batch.len() * 12);
+    let mut reply_length = batch.len() * 4;
+
+    for pending in batch {
+        request.extend_from_slice(&ads::index::RW_SYMVAL_BYHANDLE.to_le_bytes());
+        request.extend_from_slice(&pending.request.handle.to_le_bytes());
+        request.extend_from_slice(&(pending.request.length as u32).to_le_bytes());
+
+        reply_length += pending.request.length;
+    }
+
+    (request, reply_length)
+}
+
+/// Splits the reply of an ADS sum read into each sub-command's data, or its ADS return code if it failed.
+pub(crate) fn split_sum_read_reply(
+    reply: &[u8],
+    lengths: impl ExactSizeIterator<Item = usize>,
+) -> Vec<Result<&[u8], u32>> {
+    let count = lengths.len();
+    let mut offset = count * 4;
+
+    lengths
+        .enumerate()
+        .map(|(index, length)| {
+            let code = read_u32(reply, index * 4);
+            let data = &reply[offset..offset + length];
+
+            // NB: failed sub-commands still take up their space in the reply
+            offset += length;
+
+            match code {
+                0 => Ok(data),
+                code => Err(code),
+            }
+        })
+        .collect()
+}
+
+/// Encodes the sub-commands of an ADS sum write, followed by all the data to write.
+pub(crate) fn sum_write_request(batch: &WriteBatch) -> Vec<u8> {
+    let data_length: usize = batch.iter().map(|pending| pending.request.data.len()).sum();
+
+    let mut request = Vec::with_capacity(batch.len() * 12 + data_length);
+
+    for pending in batch {
+        request.extend_from_slice(&ads::index::RW_SYMVAL_BYHANDLE.to_le_bytes());
+        request.extend_from_slice(&pending.request.handle.to_le_bytes());
+        request.extend_from_slice(&(pending.request.data.len() as u32).to_le_bytes());
+    }
+
+    for pending in batch {
+        request.extend_from_slice(&pending.request.data);
+    }
+
+    request
+}
+
+/// Splits the reply of an ADS sum write into each sub-command's ADS return code.
+pub(crate) fn split_sum_write_reply(reply: &[u8], count: usize) -> Vec<u32> {
+    (0..count).map(|index| read_u32(reply, index * 4)).collect()
+}
+
+/// Copies an error, so every caller in a failed batch can be given one.
+pub(crate) fn copy_ads_error(error: &ads::Error) -> ads::Error {
+    match error {
+        ads::Error::Io(context, error) => {
+            ads::Error::Io(context, io::Error::new(error.kind(), error.to_string()))
+        }
+        ads::Error::Ads(action, description, code) => ads::Error::Ads(action, description, *code),
+        ads::Error::Reply(action, description, value) => {
+            ads::Error::Reply(action, description, *value)
+        }
+        ads::Error::Overflow(error) => ads::Error::Overflow(*error),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().expect("four bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+
+    fn read(symbol: &str, handle: u32, length: usize) -> Pending<ReadRequest, Vec<u8>> {
+        Pending::new(ReadRequest {
+            symbol: symbol.to_string(),
+            handle,
+            length,
+        })
+        .0
+    }
+
+    #[test]
+    fn sum_reads_encode_each_sub_command() {
+        let batch = vec![read("MAIN.nA", 1, 2), read("MAIN.fB", 2, 8)];
+
+        let (request, reply_length) = sum_read_request(&batch);
+
+        assert_eq!(request.len(), 24);
+        assert_eq!(read_u32(&request, 0), ads::index::RW_SYMVAL_BYHANDLE);
+        assert_eq!(read_u32(&request, 16), 2);
+        assert_eq!(read_u32(&request, 20), 8);
+        assert_eq!(reply_length, 2 * 4 + 2 + 8);
+    }
+
+    #[test]
+    fn sum_read_replies_split_per_sub_command() {
+        let mut reply = Vec::new();
+        reply.extend_from_slice(&0u32.to_le_bytes());
+        reply.extend_from_slice(&0x710u32.to_le_bytes());
+        reply.extend_from_slice(&0u32.to_le_bytes());
+        reply.extend_from_slice(&[1, 2]);
+        reply.extend_from_slice(&[0, 0, 0, 0]);
+        reply.extend_from_slice(&[3]);
+
+        let results = split_sum_read_reply(&reply, [2, 4, 1].into_iter());
+
+        assert_eq!(results, vec![Ok(&[1, 2][..]), Err(0x710), Ok(&[3][..])]);
+    }
+
+    #[test]
+    fn concurrent_requests_share_a_batch() {
+        let lane = Arc::new(Lane::<u32, ()>::default());
+        let barrier = Arc::new(Barrier::new(4));
+
+        let threads: Vec<_> = (0..4)
+            .map(|request| {
+                let lane = lane.clone();
+                let barrier = barrier.clone();
+
+                std::thread::spawn(move || {
+                    barrier.wait();
+
+                    let (_, batches) = lane.join(request, Duration::from_millis(200));
+
+                    batches.iter().map(Vec::len).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let batches: Vec<_> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        assert_eq!(batches.iter().flatten().collect::<Vec<_>>(), vec![&4]);
+    }
+
+    #[test]
+    fn stats_count_coalesced_requests() {
+        let metrics = CoalescingMetrics::default();
+
+        metrics.record_batch(1);
+        metrics.record_batch(3);
+
+        assert_eq!(
+            metrics.stats(),
+            CoalescingStats {
+                batches: 2,
+                requests: 4,
+                coalesced_requests: 3,
+                largest_batch: 3,
+                fallbacks: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Generates Rust bindings for a PLC project: a `PlcDataType` struct per DUT with its padding, a newtype per enum, a
+//! path constant per symbol and a struct of typed wrappers per function block with RPC methods.
+//!
+//! The types come from the TwinCAT `.tmc` file of the project, see [`TypeTable::from_tmc`]. A build script turns it into
+//! a module with [`build_from_tmc`]:
+//!
+//! ```no_run
+//! // build.rs, with ads-client as a build dependency with the `codegen` feature
+//! fn main() -> anyhow::Result<()> {
+//!     ads_client::codegen::build_from_tmc("plc/Plc.tmc", "plc.rs")?;
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ```ignore
+//! // src/lib.rs, with ads-client and zerocopy (with its `derive` feature) as dependencies
+//! mod plc {
+//!     include!(concat!(env!("OUT_DIR"), "/plc.rs"));
+//! }
+//! ```
+//!
+//! Without the TMC file, [`TypeTable::from_plc`] uploads the types from the running PLC instead, as `ads-cli codegen`
+//! does. The upload has no enum values or methods, so its enums have no constants and there are no RPC wrappers.
+
+mod online;
+mod rust;
+mod tmc;
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ads::symbol::{Symbol, TypeMap};
+use anyhow::{Context, Result};
+
+use crate::{error::PlcResult, plc_connection::PlcConnection};
+
+/// The data types and symbols of a PLC project.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypeTable {
+    pub data_types: Vec<TypeInfo>,
+    pub symbols: Vec<SymbolInfo>,
+}
+
+/// A data type declared in the PLC project, e.g: a structure, function block, enum or alias.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeInfo {
+    pub name: String,
+    /// The size in bytes.
+    pub size: usize,
+    pub kind: TypeKind,
+    /// The RPC methods, for a function block.
+    pub methods: Vec<MethodInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeKind {
+    Struct {
+        fields: Vec<FieldInfo>,
+    },
+    Enum {
+        base_type: String,
+        values: Vec<(String, i128)>,
+    },
+    Alias {
+        base_type: String,
+    },
+    Array {
+        element_type: String,
+        /// The number of elements in each dimension, outermost first.
+        dimensions: Vec<usize>,
+    },
+}
+
+/// A field of a structure or function block. Fields that are not whole bytes, e.g: `BIT`s, are left out, and their
+/// bytes kept as padding.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldInfo {
+    pub name: String,
+    /// The type of the field, or of its elements if it is an array.
+    pub type_name: String,
+    /// The offset from the start of the structure, in bytes.
+    pub offset: usize,
+    /// The size in bytes, of the whole array if it is one.
+    pub size: usize,
+    /// The number of elements in each dimension, outermost first, if the field is an array.
+    pub dimensions: Vec<usize>,
+}
+
+/// A method of a function block that can be called over ADS, i.e: with the `TcRpcEnable` attribute.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MethodInfo {
+    pub name: String,
+    pub parameters: Vec<ParameterInfo>,
+    /// The return type and its size in bytes, unless the method returns nothing.
+    pub return_type: Option<(String, usize)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterInfo {
+    pub name: String,
+    pub type_name: String,
+    /// The size in bytes.
+    pub size: usize,
+}
+
+/// A symbol on the PLC, e.g: `MAIN.nCounter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolInfo {
+    pub name: String,
+    pub type_name: String,
+    /// The size in bytes.
+    pub size: usize,
+}
+
+impl TypeTable {
+    /// Reads the data types and symbols of a TwinCAT `.tmc` file.
+    pub fn from_tmc(xml: &str) -> Result<Self> {
+        tmc::parse(xml)
+    }
+
+    /// Uploads the data types and symbols of a running PLC.
+    pub fn from_plc(connection: &PlcConnection) -> PlcResult<Self> {
+        let (symbols, types) = connection.upload_symbol_info()?;
+
+        Ok(Self::from_symbol_info(&symbols, &types))
+    }
+
+    /// The data types and symbols of an uploaded symbol table, see [`PlcConnection::upload_symbol_info`].
+    pub fn from_symbol_info(symbols: &[Symbol], types: &TypeMap) -> Self {
+        online::convert(symbols, types)
+    }
+
+    /// The Rust bindings, as the source of a module. `source` names where the types came from in the module's header.
+    pub fn to_rust(&self, source: &str) -> String {
+        rust::generate(self, source)
+    }
+
+    pub fn data_type(&self, name: &str) -> Option<&TypeInfo> {
+        // NB: type names on the PLC are case-insensitive
+        self.data_types
+            .iter()
+            .find(|data_type| data_type.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Keeps the symbols `keep` returns true for, and only the data types they use, e.g: to generate bindings for part
+    /// of a PLC whose libraries bring hundreds of types.
+    pub fn retain_symbols(&mut self, keep: impl FnMut(&SymbolInfo) -> bool) {
+        self.symbols.retain(keep);
+
+        let mut used = HashSet::new();
+        let mut pending: Vec<_> = self
+            .symbols
+            .iter()
+            .map(|symbol| symbol.type_name.clone())
+            .collect();
+
+        while let Some(type_name) = pending.pop() {
+            let Some(data_type) = self.data_type(&type_name) else {
+                continue;
+            };
+
+            if !used.insert(data_type.name.to_ascii_uppercase()) {
+                continue;
+            }
+
+            match &data_type.kind {
+                TypeKind::Struct { fields } => {
+                    pending.extend(fields.iter().map(|field| field.type_name.clone()))
+                }
+                TypeKind::Enum { base_type, .. } | TypeKind::Alias { base_type } => {
+                    pending.push(base_type.clone())
+                }
+                TypeKind::Array { element_type, .. } => pending.push(element_type.clone()),
+            }
+
+            for method in &data_type.methods {
+                pending.extend(
+                    method
+                        .parameters
+                        .iter()
+                        .map(|parameter| parameter.type_name.clone()),
+                );
+                pending.extend(method.return_type.iter().map(|(name, _)| name.clone()));
+            }
+        }
+
+        self.data_types
+            .retain(|data_type| used.contains(&data_type.name.to_ascii_uppercase()));
+    }
+}
+
+/// Generates bindings from a TMC file into `file_name` in `OUT_DIR`, for a build script, and has cargo rerun it when
+/// the TMC file changes. Returns the path of the generated file.
+pub fn build_from_tmc(tmc_path: impl AsRef<Path>, file_name: &str) -> Result<PathBuf> {
+    let tmc_path = tmc_path.as_ref();
+
+    println!("cargo:rerun-if-changed={}", tmc_path.display());
+
+    let xml =
+        fs::read_to_string(tmc_path).context(format!("Could not read {}.", tmc_path.display()))?;
+
+    let type_table =
+        TypeTable::from_tmc(&xml).context(format!("Could not parse {}.", tmc_path.display()))?;
+
+    let source = tmc_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let out_dir = std::env::var_os("OUT_DIR")
+        .context("OUT_DIR is not set, as it is outside a build script.")?;
+    let output_path = Path::new(&out_dir).join(file_name);
+
+    fs::write(&output_path, type_table.to_rust(&source))
+        .context(format!("Could not write {}.", output_path.display()))?;
+
+    Ok(output_path)
+}
@@ -0,0 +1,246 @@
+//! Reads the data types and symbols a running PLC uploads over ADS.
+
+use ads::symbol::{Field, Symbol, Type, TypeMap};
+
+use super::{FieldInfo, SymbolInfo, TypeInfo, TypeKind, TypeTable};
+
+// Type flags
+const FLAG_BIT_VALUES: u32 = 0x20;
+const FLAG_ENUM_INFOS: u32 = 0x2000;
+
+const ADST_STRING: u32 = 30;
+
+// The elementary type of each ADS base type, which is all the upload has of an alias's or enum's base type
+const BASE_TYPES: [(u32, &str); 11] = [
+    (2, "INT"),
+    (3, "DINT"),
+    (4, "REAL"),
+    (5, "LREAL"),
+    (16, "SINT"),
+    (17, "USINT"),
+    (18, "UINT"),
+    (19, "UDINT"),
+    (20, "LINT"),
+    (21, "ULINT"),
+    (33, "BOOL"),
+];
+
+pub(super) fn convert(symbols: &[Symbol], types: &TypeMap) -> TypeTable {
+    let mut data_types: Vec<_> = types.values().filter_map(data_type).collect();
+
+    // NB: the uploaded types are in no particular order, and the generated source should not change between uploads
+    data_types.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let symbols = symbols
+        .iter()
+        .map(|symbol| SymbolInfo {
+            name: symbol.name.clone(),
+            type_name: symbol.typ.clone(),
+            size: symbol.size,
+        })
+        .collect();
+
+    TypeTable {
+        data_types,
+        symbols,
+    }
+}
+
+/// A structure, array, enum or alias, or `None` for e.g: a `BIT` or an alias of a structure.
+fn data_type(data_type: &Type) -> Option<TypeInfo> {
+    if data_type.flags & FLAG_BIT_VALUES != 0 {
+        return None;
+    }
+
+    let kind = if !data_type.fields.is_empty() {
+        TypeKind::Struct {
+            fields: data_type.fields.iter().filter_map(field).collect(),
+        }
+    } else if !data_type.array.is_empty() {
+        TypeKind::Array {
+            element_type: element_type(&data_type.name)?.to_string(),
+            dimensions: dimensions(&data_type.array),
+        }
+    } else {
+        let base_type = base_type(data_type.base_type, data_type.size)?;
+
+        // NB: elementary types are in the upload too, and are not aliases of themselves
+        if base_type.eq_ignore_ascii_case(&data_type.name) {
+            return None;
+        }
+
+        if data_type.flags & FLAG_ENUM_INFOS != 0 {
+            TypeKind::Enum {
+                base_type,
+                values: Vec::new(),
+            }
+        } else {
+            TypeKind::Alias { base_type }
+        }
+    };
+
+    Some(TypeInfo {
+        name: data_type.name.clone(),
+        size: data_type.size,
+        kind,
+        methods: Vec::new(),
+    })
+}
+
+/// A field that is whole bytes and inside the structure, or `None` for e.g: a `BIT` or a field declared `AT %M*`.
+fn field(field: &Field) -> Option<FieldInfo> {
+    let offset = field.offset?;
+
+    if field.flags & FLAG_BIT_VALUES != 0 || field.typ.eq_ignore_ascii_case("BIT") {
+        return None;
+    }
+
+    // NB: an array field of a named array type has no element type in its name, and is looked up as that type
+    let (type_name, dimensions) = match element_type(&field.typ) {
+        Some(element_type) if !field.array.is_empty() => {
+            (element_type.to_string(), dimensions(&field.array))
+        }
+        _ => (field.typ.clone(), Vec::new()),
+    };
+
+    Some(FieldInfo {
+        name: field.name.clone(),
+        type_name,
+        offset: offset as usize,
+        size: field.size,
+        dimensions,
+    })
+}
+
+/// The element type of an array type's name, e.g: `INT` for `ARRAY [0..2] OF INT`.
+fn element_type(type_name: &str) -> Option<&str> {
+    let (array, element_type) = type_name.split_once(" OF ")?;
+
+    array
+        .trim()
+        .to_ascii_uppercase()
+        .starts_with("ARRAY")
+        .then(|| element_type.trim())
+}
+
+fn dimensions(bounds: &[(i32, i32)]) -> Vec<usize> {
+    bounds
+        .iter()
+        .map(|(lower, upper)| (upper - lower + 1).max(0) as usize)
+        .collect()
+}
+
+fn base_type(ads_type: u32, size: usize) -> Option<String> {
+    if ads_type == ADST_STRING {
+        return Some(format!("STRING({})", size.saturating_sub(1)));
+    }
+
+    BASE_TYPES
+        .into_iter()
+        .find(|(base_type, _)| *base_type == ads_type)
+        .map(|(_, name)| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plc_type(name: &str, size: usize, base_type: u32, flags: u32) -> Type {
+        Type {
+            name: name.to_string(),
+            size,
+            array: Vec::new(),
+            fields: Vec::new(),
+            base_type,
+            flags,
+        }
+    }
+
+    fn plc_field(name: &str, typ: &str, offset: u32, size: usize) -> Field {
+        Field {
+            name: name.to_string(),
+            typ: typ.to_string(),
+            offset: Some(offset),
+            size,
+            array: Vec::new(),
+            base_type: 65,
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn uploaded_types_become_a_type_table() {
+        let mut status = plc_type("ST_Status", 12, 65, 1);
+        status.fields = vec![
+            plc_field("bReady", "BOOL", 0, 1),
+            Field {
+                array: vec![(1, 3)],
+                ..plc_field("aValues", "ARRAY [1..3] OF INT", 2, 6)
+            },
+            Field {
+                flags: FLAG_BIT_VALUES,
+                ..plc_field("bFlag", "BIT", 64, 1)
+            },
+            Field {
+                offset: None,
+                ..plc_field("nInput", "INT", 0, 2)
+            },
+            plc_field("eColor", "E_Color", 8, 2),
+        ];
+
+        let types: TypeMap = [
+            status,
+            plc_type("E_Color", 2, 2, 1 | FLAG_ENUM_INFOS),
+            plc_type("T_Speed", 4, 4, 1),
+            plc_type("INT", 2, 2, 1),
+            plc_type("BIT", 1, 33, 1 | FLAG_BIT_VALUES),
+        ]
+        .into_iter()
+        .map(|data_type| (data_type.name.clone(), data_type))
+        .collect();
+
+        let type_table = convert(&[], &types);
+
+        let names: Vec<_> = type_table
+            .data_types
+            .iter()
+            .map(|data_type| data_type.name.as_str())
+            .collect();
+        assert_eq!(names, ["E_Color", "ST_Status", "T_Speed"]);
+
+        assert_eq!(
+            type_table.data_types[0].kind,
+            TypeKind::Enum {
+                base_type: "INT".to_string(),
+                values: Vec::new(),
+            }
+        );
+        assert_eq!(
+            type_table.data_types[2].kind,
+            TypeKind::Alias {
+                base_type: "REAL".to_string(),
+            }
+        );
+
+        let TypeKind::Struct { fields } = &type_table.data_types[1].kind else {
+            panic!("a struct");
+        };
+
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[1].type_name, "INT");
+        assert_eq!(fields[1].dimensions, [3]);
+        assert_eq!(fields[2].name, "eColor");
+    }
+
+    #[test]
+    fn array_types_are_read_from_their_name() {
+        assert_eq!(element_type("ARRAY [0..2] OF ST_Status"), Some("ST_Status"));
+        assert_eq!(
+            element_type("ARRAY [0..1] OF ARRAY [0..2] OF INT"),
+            Some("ARRAY [0..2] OF INT")
+        );
+        assert_eq!(element_type("POINTER TO INT"), None);
+
+        assert_eq!(dimensions(&[(0, 2), (-1, 1)]), [3, 3]);
+    }
+}
@@ -0,0 +1,760 @@
+//! Writes a type table as the source of a Rust module.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Write,
+};
+
+use super::{FieldInfo, MethodInfo, TypeInfo, TypeKind, TypeTable};
+
+// Elementary types, with the Rust type and the module of `data_types::primitives` it is in, if any, and its size and
+// alignment in bytes
+const ELEMENTARY: [(&str, &str, Option<&str>, usize, usize); 24] = [
+    ("BOOL", "PlcBool", Some("bool"), 1, 1),
+    ("BYTE", "u8", None, 1, 1),
+    ("USINT", "u8", None, 1, 1),
+    ("SINT", "i8", None, 1, 1),
+    ("INT", "PlcInt", Some("int"), 2, 2),
+    ("UINT", "PlcUInt", Some("uint"), 2, 2),
+    ("WORD", "PlcWord", Some("word"), 2, 2),
+    ("DINT", "PlcDInt", Some("dint"), 4, 4),
+    ("UDINT", "PlcUDInt", Some("udint"), 4, 4),
+    ("DWORD", "u32", None, 4, 4),
+    ("LINT", "i64", None, 8, 8),
+    ("ULINT", "u64", None, 8, 8),
+    ("LWORD", "u64", None, 8, 8),
+    ("REAL", "PlcReal", Some("real"), 4, 4),
+    ("LREAL", "PlcLReal", Some("lreal"), 8, 8),
+    ("TIME", "PlcTime", Some("time"), 4, 4),
+    ("LTIME", "u64", None, 8, 8),
+    ("DATE", "u32", None, 4, 4),
+    ("DT", "u32", None, 4, 4),
+    ("DATE_AND_TIME", "u32", None, 4, 4),
+    ("TOD", "u32", None, 4, 4),
+    ("TIME_OF_DAY", "u32", None, 4, 4),
+    ("STRING(16)", "PlcString16", Some("string16"), 17, 1),
+    ("TIMESTRUCT", "PlcTimeStruct", Some("time_struct"), 16, 2),
+];
+
+// The integer an enum of each base type is stored as
+const ENUM_BASES: [(&str, &str, i128, i128); 12] = [
+    ("SINT", "i8", i8::MIN as i128, i8::MAX as i128),
+    ("USINT", "u8", 0, u8::MAX as i128),
+    ("BYTE", "u8", 0, u8::MAX as i128),
+    ("INT", "i16", i16::MIN as i128, i16::MAX as i128),
+    ("UINT", "u16", 0, u16::MAX as i128),
+    ("WORD", "u16", 0, u16::MAX as i128),
+    ("DINT", "i32", i32::MIN as i128, i32::MAX as i128),
+    ("UDINT", "u32", 0, u32::MAX as i128),
+    ("DWORD", "u32", 0, u32::MAX as i128),
+    ("LINT", "i64", i64::MIN as i128, i64::MAX as i128),
+    ("ULINT", "u64", 0, u64::MAX as i128),
+    ("LWORD", "u64", 0, u64::MAX as i128),
+];
+
+const KEYWORDS: [&str; 51] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+pub(super) fn generate(type_table: &TypeTable, source: &str) -> String {
+    let mut generator = Generator {
+        type_table,
+        layouts: HashMap::new(),
+        imports: BTreeSet::new(),
+    };
+
+    let mut items = Vec::new();
+
+    for data_type in &type_table.data_types {
+        if is_identifier(&data_type.name) && elementary(&data_type.name).is_none() {
+            items.extend(generator.data_type(data_type));
+        }
+    }
+
+    for data_type in &type_table.data_types {
+        if is_identifier(&data_type.name) && !data_type.methods.is_empty() {
+            items.push(generator.rpc_methods(data_type));
+        }
+    }
+
+    if !type_table.symbols.is_empty() {
+        items.push(generator.symbols());
+    }
+
+    let mut module = format!("// Generated by ads-client from {source}. Do not edit.\n");
+
+    if !generator.imports.is_empty() {
+        writeln!(module, "\nuse ads_client::data_types::primitives::{{").unwrap();
+
+        for import in &generator.imports {
+            writeln!(module, "    {import},").unwrap();
+        }
+
+        writeln!(module, "}};").unwrap();
+    }
+
+    for item in items {
+        module.push('\n');
+        module.push_str(&item);
+    }
+
+    module
+}
+
+/// A Rust type for a PLC type.
+#[derive(Clone)]
+struct RustType {
+    name: String,
+    size: usize,
+    align: usize,
+}
+
+impl RustType {
+    fn bytes(size: usize) -> Self {
+        Self {
+            name: format!("[u8; {size}]"),
+            size,
+            align: 1,
+        }
+    }
+}
+
+/// The members of a structure, with padding wherever the PLC's layout has a gap.
+#[derive(Clone)]
+struct Layout {
+    members: Vec<Member>,
+    align: usize,
+}
+
+#[derive(Clone)]
+enum Member {
+    Field {
+        name: String,
+        field: FieldInfo,
+        rust_type: RustType,
+    },
+    Padding {
+        offset: usize,
+        size: usize,
+    },
+}
+
+struct Generator<'a> {
+    type_table: &'a TypeTable,
+    /// Layouts by the structure's name in upper case, `None` while it is being laid out.
+    layouts: HashMap<String, Option<Layout>>,
+    imports: BTreeSet<String>,
+}
+
+impl Generator<'_> {
+    fn data_type(&mut self, data_type: &TypeInfo) -> Option<String> {
+        let name = type_name(&data_type.name);
+
+        let mut item = String::new();
+
+        match &data_type.kind {
+            TypeKind::Struct { .. } => {
+                let layout = self.layout(data_type)?;
+
+                writeln!(item, "/// `{}`, {} bytes.", data_type.name, data_type.size).unwrap();
+                writeln!(
+                    item,
+                    "#[derive(Clone, Debug, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]"
+                )
+                .unwrap();
+                writeln!(item, "#[repr(C)]").unwrap();
+                writeln!(item, "pub struct {name} {{").unwrap();
+
+                for member in &layout.members {
+                    match member {
+                        Member::Field {
+                            name,
+                            field,
+                            rust_type,
+                        } => {
+                            writeln!(item, "    /// `{}`: {}", field.name, describe(field))
+                                .unwrap();
+                            writeln!(item, "    pub {name}: {},", rust_type.name).unwrap();
+                        }
+                        Member::Padding { offset, size } => {
+                            writeln!(item, "    _padding_{offset}: [u8; {size}],").unwrap();
+                        }
+                    }
+                }
+
+                writeln!(item, "}}").unwrap();
+                writeln!(item).unwrap();
+                writeln!(item, "impl Default for {name} {{").unwrap();
+                writeln!(item, "    fn default() -> Self {{").unwrap();
+                writeln!(item, "        zerocopy::FromZeroes::new_zeroed()").unwrap();
+                writeln!(item, "    }}").unwrap();
+                writeln!(item, "}}").unwrap();
+                writeln!(item).unwrap();
+                writeln!(
+                    item,
+                    "impl ads_client::data_types::PlcDataType for {name} {{}}"
+                )
+                .unwrap();
+            }
+            TypeKind::Enum { base_type, values } => {
+                let (_, integer, min, max) = enum_base(base_type)?;
+
+                writeln!(item, "/// `{}`, an enum of {base_type}.", data_type.name).unwrap();
+                writeln!(
+                    item,
+                    "#[derive(\n    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, zerocopy::AsBytes, zerocopy::FromBytes,\n    \
+                     zerocopy::FromZeroes,\n)]"
+                )
+                .unwrap();
+                writeln!(item, "#[repr(transparent)]").unwrap();
+                writeln!(item, "pub struct {name}(pub {integer});").unwrap();
+                writeln!(item).unwrap();
+                writeln!(item, "impl {name} {{").unwrap();
+
+                let mut names = HashSet::new();
+
+                for (text, value) in values {
+                    let constant = constant_name(text);
+
+                    if (min..=max).contains(value) && names.insert(constant.clone()) {
+                        writeln!(item, "    pub const {constant}: Self = Self({value});").unwrap();
+                    }
+                }
+
+                writeln!(item, "}}").unwrap();
+                writeln!(item).unwrap();
+                writeln!(
+                    item,
+                    "impl ads_client::data_types::PlcDataType for {name} {{}}"
+                )
+                .unwrap();
+            }
+            TypeKind::Alias { base_type } => {
+                let rust_type = self.resolve(base_type, data_type.size);
+
+                writeln!(item, "/// `{}`, a {base_type}.", data_type.name).unwrap();
+                writeln!(item, "pub type {name} = {};", rust_type.name).unwrap();
+            }
+            // NB: array types are written out where they are used
+            TypeKind::Array { .. } => return None,
+        }
+
+        Some(item)
+    }
+
+    fn rpc_methods(&mut self, data_type: &TypeInfo) -> String {
+        let name = format!("{}Rpc", type_name(&data_type.name));
+
+        let mut item = String::new();
+
+        writeln!(
+            item,
+            "/// The RPC methods of a `{}`, called on the instance a scope points at.",
+            data_type.name
+        )
+        .unwrap();
+        writeln!(item, "#[derive(Clone)]").unwrap();
+        writeln!(item, "pub struct {name} {{").unwrap();
+        writeln!(item, "    scope: ads_client::scope::Scope,").unwrap();
+        writeln!(item, "}}").unwrap();
+        writeln!(item).unwrap();
+        writeln!(item, "impl {name} {{").unwrap();
+        writeln!(
+            item,
+            "    /// The methods of the instance at the path, e.g: one of the `symbols`."
+        )
+        .unwrap();
+        writeln!(
+            item,
+            "    pub fn new(connection: &ads_client::plc_connection::PlcConnection, path: &str) -> Self {{"
+        )
+        .unwrap();
+        writeln!(item, "        Self::in_scope(connection.scope(path))").unwrap();
+        writeln!(item, "    }}").unwrap();
+        writeln!(item).unwrap();
+        writeln!(
+            item,
+            "    pub fn in_scope(scope: ads_client::scope::Scope) -> Self {{"
+        )
+        .unwrap();
+        writeln!(item, "        Self {{ scope }}").unwrap();
+        writeln!(item, "    }}").unwrap();
+
+        let mut names = HashSet::new();
+
+        for method in &data_type.methods {
+            let method_name = field_name(&method.name);
+
+            if names.insert(method_name.clone()) {
+                item.push('\n');
+                item.push_str(&self.rpc_method(&method_name, method));
+            }
+        }
+
+        writeln!(item, "}}").unwrap();
+
+        item
+    }
+
+    fn rpc_method(&mut self, method_name: &str, method: &MethodInfo) -> String {
+        let mut parameters = Vec::new();
+        let mut names = HashSet::new();
+
+        for parameter in &method.parameters {
+            let mut name = field_name(&parameter.name);
+
+            while !names.insert(name.clone()) {
+                name.push('_');
+            }
+
+            let rust_type = self.resolve(&parameter.type_name, parameter.size);
+
+            parameters.push((name, rust_type, parameter));
+        }
+
+        let signature: Vec<_> = parameters
+            .iter()
+            .map(|(_, _, parameter)| format!("{}: {}", parameter.name, parameter.type_name))
+            .collect();
+
+        let arguments: Vec<_> = parameters
+            .iter()
+            .map(|(name, rust_type, _)| format!("{name}: {}", rust_type.name))
+            .collect();
+
+        let params: Vec<_> = parameters
+            .iter()
+            .map(|(name, _, _)| format!("zerocopy::AsBytes::as_bytes(&{name})"))
+            .collect();
+
+        let mut item = String::new();
+
+        let return_type = method
+            .return_type
+            .as_ref()
+            .map(|(type_name, size)| (type_name, self.resolve(type_name, *size)));
+
+        match &return_type {
+            Some((type_name, _)) => writeln!(
+                item,
+                "    /// `{}({}): {type_name}`",
+                method.name,
+                signature.join(", ")
+            ),
+            None => writeln!(item, "    /// `{}({})`", method.name, signature.join(", ")),
+        }
+        .unwrap();
+
+        let result = return_type
+            .as_ref()
+            .map(|(_, rust_type)| rust_type.name.clone())
+            .unwrap_or_else(|| "()".to_string());
+
+        writeln!(
+            item,
+            "    pub fn {method_name}(&self{}) -> ads_client::error::PlcResult<{result}> {{",
+            arguments
+                .iter()
+                .map(|argument| format!(", {argument}"))
+                .collect::<String>()
+        )
+        .unwrap();
+
+        let call = match return_type {
+            Some(_) => {
+                "ads_client::plc_interface::__private::call_rpc_method(\n            &self.scope,"
+            }
+            None => "self.scope.invoke_rpc_method_with_params(",
+        };
+
+        writeln!(item, "        {call}").unwrap();
+        writeln!(item, "            \"#{}\",", method.name).unwrap();
+        writeln!(item, "            &[{}],", params.join(", ")).unwrap();
+        writeln!(item, "        )").unwrap();
+        writeln!(item, "    }}").unwrap();
+
+        item
+    }
+
+    fn symbols(&mut self) -> String {
+        let mut item = String::new();
+
+        writeln!(item, "/// The paths of the PLC's symbols.").unwrap();
+        writeln!(item, "pub mod symbols {{").unwrap();
+
+        let mut names = HashSet::new();
+
+        for symbol in &self.type_table.symbols {
+            let constant = symbol
+                .name
+                .split('.')
+                .map(constant_name)
+                .collect::<Vec<_>>()
+                .join("_");
+
+            if !names.insert(constant.clone()) {
+                continue;
+            }
+
+            if names.len() > 1 {
+                writeln!(item).unwrap();
+            }
+
+            writeln!(item, "    /// {}", symbol.type_name).unwrap();
+            writeln!(item, "    pub const {constant}: &str = {:?};", symbol.name).unwrap();
+        }
+
+        writeln!(item, "}}").unwrap();
+
+        item
+    }
+
+    /// The Rust type for a PLC type of a size, which is its bytes if there is no Rust type of that size.
+    fn resolve(&mut self, plc_type: &str, size: usize) -> RustType {
+        let rust_type = self.resolve_known(plc_type, size);
+
+        match rust_type {
+            Some(rust_type) if rust_type.size == size => rust_type,
+            _ => RustType::bytes(size),
+        }
+    }
+
+    fn resolve_known(&mut self, plc_type: &str, size: usize) -> Option<RustType> {
+        if let Some((_, name, module, size, align)) = elementary(plc_type) {
+            if let Some(module) = module {
+                self.imports.insert(format!("{module}::{name}"));
+            }
+
+            return Some(RustType {
+                name: name.to_string(),
+                size,
+                align,
+            });
+        }
+
+        let data_type = self.type_table.data_type(plc_type)?;
+
+        if !is_identifier(&data_type.name) && !matches!(data_type.kind, TypeKind::Array { .. }) {
+            return None;
+        }
+
+        match &data_type.kind {
+            TypeKind::Struct { .. } => Some(RustType {
+                name: type_name(&data_type.name),
+                size: data_type.size,
+                align: self.layout(data_type)?.align,
+            }),
+            TypeKind::Enum { base_type, .. } => {
+                enum_base(base_type)?;
+
+                Some(RustType {
+                    name: type_name(&data_type.name),
+                    size: data_type.size,
+                    align: elementary(base_type)?.4,
+                })
+            }
+            TypeKind::Alias { base_type } => {
+                let rust_type = self.resolve(base_type, size);
+
+                Some(RustType {
+                    name: type_name(&data_type.name),
+                    ..rust_type
+                })
+            }
+            TypeKind::Array {
+                element_type,
+                dimensions,
+            } => Some(self.array(element_type, dimensions, data_type.size)),
+        }
+    }
+
+    /// An array of a PLC type, or its bytes if the elements are not of a Rust type.
+    fn array(&mut self, element_type: &str, dimensions: &[usize], size: usize) -> RustType {
+        let count: usize = dimensions.iter().product();
+
+        if count == 0 || !size.is_multiple_of(count) {
+            return RustType::bytes(size);
+        }
+
+        let mut rust_type = self.resolve(element_type, size / count);
+
+        for dimension in dimensions.iter().rev() {
+            rust_type.name = format!("[{}; {dimension}]", rust_type.name);
+        }
+
+        rust_type.size = size;
+
+        rust_type
+    }
+
+    fn layout(&mut self, data_type: &TypeInfo) -> Option<Layout> {
+        let key = data_type.name.to_ascii_uppercase();
+
+        if let Some(layout) = self.layouts.get(&key) {
+            return layout.clone();
+        }
+
+        let TypeKind::Struct { fields } = &data_type.kind else {
+            return None;
+        };
+
+        // NB: guards against a structure containing itself, which the PLC does not allow
+        self.layouts.insert(key.clone(), None);
+
+        let layout = self.lay_out(data_type.size, fields);
+
+        self.layouts.insert(key, Some(layout.clone()));
+
+        Some(layout)
+    }
+
+    fn lay_out(&mut self, size: usize, fields: &[FieldInfo]) -> Layout {
+        // Rust pads a structure to a multiple of its alignment, so fields aligned more than the size allows are kept
+        // as bytes, as are fields the PLC's pack mode does not align as Rust would
+        let max_align = [8, 4, 2, 1]
+            .into_iter()
+            .find(|align| size.is_multiple_of(*align))
+            .expect("1 divides any size");
+
+        let mut fields: Vec<&FieldInfo> = fields.iter().collect();
+        fields.sort_by_key(|field| field.offset);
+
+        let mut members = Vec::new();
+        let mut names = HashSet::new();
+        let mut offset = 0;
+        let mut align = 1;
+
+        for field in fields {
+            // NB: overlapping fields, e.g: of a union, are left to the bytes of the first
+            if field.offset < offset || field.offset + field.size > size || field.size == 0 {
+                continue;
+            }
+
+            let mut rust_type = if field.dimensions.is_empty() {
+                self.resolve(&field.type_name, field.size)
+            } else {
+                self.array(&field.type_name, &field.dimensions, field.size)
+            };
+
+            if rust_type.align > max_align || !field.offset.is_multiple_of(rust_type.align) {
+                rust_type = RustType::bytes(field.size);
+            }
+
+            if field.offset > offset {
+                members.push(Member::Padding {
+                    offset,
+                    size: field.offset - offset,
+                });
+            }
+
+            let mut name = field_name(&field.name);
+
+            while !names.insert(name.clone()) {
+                name.push('_');
+            }
+
+            align = align.max(rust_type.align);
+            offset = field.offset + field.size;
+
+            members.push(Member::Field {
+                name,
+                field: field.clone(),
+                rust_type,
+            });
+        }
+
+        if size > offset {
+            members.push(Member::Padding {
+                offset,
+                size: size - offset,
+            });
+        }
+
+        Layout { members, align }
+    }
+}
+
+fn elementary(
+    plc_type: &str,
+) -> Option<(
+    &'static str,
+    &'static str,
+    Option<&'static str>,
+    usize,
+    usize,
+)> {
+    let plc_type = plc_type.replace(' ', "").to_ascii_uppercase();
+
+    ELEMENTARY.into_iter().find(|(name, ..)| *name == plc_type)
+}
+
+fn enum_base(base_type: &str) -> Option<(&'static str, &'static str, i128, i128)> {
+    ENUM_BASES
+        .into_iter()
+        .find(|(name, ..)| name.eq_ignore_ascii_case(base_type.trim()))
+}
+
+fn describe(field: &FieldInfo) -> String {
+    match field.dimensions.as_slice() {
+        [] => field.type_name.clone(),
+        dimensions => format!(
+            "ARRAY OF {}, {} elements",
+            field.type_name,
+            dimensions
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(" x ")
+        ),
+    }
+}
+
+/// Whether a PLC name can be made a Rust one, e.g: `ST_Status` or `Tc2_Standard.TON`, unlike `ARRAY [0..9] OF INT`.
+fn is_identifier(name: &str) -> bool {
+    let mut characters = name.chars();
+
+    characters
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && characters.all(|character| {
+            character.is_ascii_alphanumeric() || character == '_' || character == '.'
+        })
+}
+
+/// The words of a PLC name, split at underscores, dots and where the case changes, e.g: `sIPAddress` is `s`, `IP` and
+/// `Address`.
+fn words(name: &str) -> Vec<String> {
+    let characters: Vec<char> = name.chars().collect();
+
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (index, &character) in characters.iter().enumerate() {
+        if !character.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+
+            continue;
+        }
+
+        let previous = index.checked_sub(1).map(|index| characters[index]);
+        let next = characters.get(index + 1);
+
+        let boundary = character.is_ascii_uppercase()
+            && previous.is_some_and(|previous| {
+                previous.is_ascii_lowercase()
+                    || previous.is_ascii_digit()
+                    || (previous.is_ascii_uppercase() && next.is_some_and(char::is_ascii_lowercase))
+            });
+
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        word.push(character);
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// A field, method or parameter name, e.g: `bReady` is `b_ready`.
+pub(super) fn field_name(name: &str) -> String {
+    let name = words(name)
+        .iter()
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if name.is_empty() || name.starts_with(|character: char| character.is_ascii_digit()) {
+        format!("_{name}")
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("{name}_")
+    } else {
+        name
+    }
+}
+
+/// A constant name, e.g: `nCounter` is `N_COUNTER`.
+pub(super) fn constant_name(name: &str) -> String {
+    field_name(name).to_ascii_uppercase()
+}
+
+/// A type name, e.g: `ST_Status` is `StStatus` and `FB_IOLink` is `FbIoLink`.
+pub(super) fn type_name(name: &str) -> String {
+    let name: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut characters = word.chars();
+            let first = characters.next().expect("words are not empty");
+
+            first.to_ascii_uppercase().to_string() + &characters.as_str().to_ascii_lowercase()
+        })
+        .collect();
+
+    if name.starts_with(|character: char| character.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plc_names_become_rust_names() {
+        assert_eq!(type_name("ST_Status"), "StStatus");
+        assert_eq!(type_name("FB_IOLink"), "FbIoLink");
+        assert_eq!(type_name("Tc2_Standard.TON"), "Tc2StandardTon");
+        assert_eq!(field_name("bReady"), "b_ready");
+        assert_eq!(field_name("sIPAddress"), "s_ip_address");
+        assert_eq!(field_name("Type"), "type_");
+        assert_eq!(field_name("ARRAY"), "array");
+        assert_eq!(constant_name("fbConveyor"), "FB_CONVEYOR");
+    }
+
+    #[test]
+    fn fields_the_pack_mode_misaligns_are_kept_as_bytes() {
+        let type_table = TypeTable {
+            data_types: vec![TypeInfo {
+                name: "ST_Packed".to_string(),
+                size: 5,
+                kind: TypeKind::Struct {
+                    fields: vec![
+                        FieldInfo {
+                            name: "bA".to_string(),
+                            type_name: "BOOL".to_string(),
+                            offset: 0,
+                            size: 1,
+                            dimensions: vec![],
+                        },
+                        FieldInfo {
+                            name: "fB".to_string(),
+                            type_name: "REAL".to_string(),
+                            offset: 1,
+                            size: 4,
+                            dimensions: vec![],
+                        },
+                    ],
+                },
+                methods: vec![],
+            }],
+            symbols: vec![],
+        };
+
+        let source = generate(&type_table, "test");
+
+        assert!(source.contains("    pub b_a: PlcBool,\n"));
+        assert!(source.contains("    pub f_b: [u8; 4],\n"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Reads the data types and symbols of a TwinCAT `.tmc` file.
+
+use anyhow::{anyhow, Context, Result};
+use roxmltree::{Document, Node};
+
+use super::{FieldInfo, MethodInfo, ParameterInfo, SymbolInfo, TypeInfo, TypeKind, TypeTable};
+
+const RPC_ENABLE_PROPERTY: &str = "TcRpcEnable";
+
+pub(super) fn parse(xml: &str) -> Result<TypeTable> {
+    let document = Document::parse(xml).context("The TMC file is not valid XML.")?;
+
+    let mut type_table = TypeTable::default();
+
+    for node in document
+        .descendants()
+        .filter(|node| node.has_tag_name("DataType"))
+    {
+        if let Some(data_type) = data_type(node)? {
+            type_table.data_types.push(data_type);
+        }
+    }
+
+    for node in document
+        .descendants()
+        .filter(|node| node.has_tag_name("Symbol"))
+    {
+        let symbol = SymbolInfo {
+            name: text(node, "Name")?,
+            type_name: text(node, "BaseType")?,
+            size: bytes(node, "BitSize")?,
+        };
+
+        // NB: the same symbol can be listed in more than one data area
+        if !type_table
+            .symbols
+            .iter()
+            .any(|known| known.name == symbol.name)
+        {
+            type_table.symbols.push(symbol);
+        }
+    }
+
+    Ok(type_table)
+}
+
+fn data_type(node: Node) -> Result<Option<TypeInfo>> {
+    let name = text(node, "Name")?;
+    let size = bytes(node, "BitSize")?;
+
+    let context = || format!("Could not read data type {name}.");
+
+    let kind = if children(node, "EnumInfo").next().is_some() {
+        let values = children(node, "EnumInfo")
+            .map(|value| {
+                let text_value = optional_text(value, "Enum").unwrap_or_default();
+
+                Ok((
+                    text(value, "Text")?,
+                    text_value
+                        .parse()
+                        .map_err(|_| anyhow!("{text_value:?} is not an enum value."))?,
+                ))
+            })
+            .collect::<Result<_>>()
+            .with_context(context)?;
+
+        TypeKind::Enum {
+            base_type: optional_text(node, "BaseType").unwrap_or_else(|| "INT".to_string()),
+            values,
+        }
+    } else if children(node, "SubItem").next().is_some() {
+        let fields = children(node, "SubItem")
+            .map(field)
+            .filter_map(Result::transpose)
+            .collect::<Result<_>>()
+            .with_context(context)?;
+
+        TypeKind::Struct { fields }
+    } else if let Some(base_type) = optional_text(node, "BaseType") {
+        let dimensions = dimensions(node).with_context(context)?;
+
+        if dimensions.is_empty() {
+            TypeKind::Alias { base_type }
+        } else {
+            TypeKind::Array {
+                element_type: base_type,
+                dimensions,
+            }
+        }
+    } else {
+        return Ok(None);
+    };
+
+    let methods = children(node, "Method")
+        .filter(|method| has_property(*method, RPC_ENABLE_PROPERTY))
+        .map(method)
+        .filter_map(Result::transpose)
+        .collect::<Result<_>>()
+        .with_context(context)?;
+
+    Ok(Some(TypeInfo {
+        name,
+        size,
+        kind,
+        methods,
+    }))
+}
+
+/// A field that is whole bytes, or `None` for e.g: a `BIT`.
+fn field(node: Node) -> Result<Option<FieldInfo>> {
+    let bit_offset = number(node, "BitOffs")?;
+    let bit_size = number(node, "BitSize")?;
+
+    if !bit_offset.is_multiple_of(8) || !bit_size.is_multiple_of(8) {
+        return Ok(None);
+    }
+
+    Ok(Some(FieldInfo {
+        name: text(node, "Name")?,
+        type_name: text(node, "Type")?,
+        offset: bit_offset / 8,
+        size: bit_size / 8,
+        dimensions: dimensions(node)?,
+    }))
+}
+
+/// An RPC method, or `None` if it has outputs, which are not supported.
+fn method(node: Node) -> Result<Option<MethodInfo>> {
+    let mut parameters = Vec::new();
+
+    for parameter in children(node, "Parameter") {
+        if parameter.attribute("InOut").is_some()
+            || has_property(parameter, "VAR_OUTPUT")
+            || has_property(parameter, "VAR_IN_OUT")
+        {
+            return Ok(None);
+        }
+
+        parameters.push(ParameterInfo {
+            name: text(parameter, "Name")?,
+            type_name: text(parameter, "Type")?,
+            size: bytes(parameter, "BitSize")?,
+        });
+    }
+
+    let return_type = match optional_text(node, "ReturnType") {
+        Some(return_type) => Some((return_type, bytes(node, "ReturnBitSize")?)),
+        None => None,
+    };
+
+    Ok(Some(MethodInfo {
+        name: text(node, "Name")?,
+        parameters,
+        return_type,
+    }))
+}
+
+fn dimensions(node: Node) -> Result<Vec<usize>> {
+    children(node, "ArrayInfo")
+        .map(|array_info| number(array_info, "Elements"))
+        .collect()
+}
+
+fn has_property(node: Node, name: &str) -> bool {
+    children(node, "Properties")
+        .flat_map(|properties| children(properties, "Property"))
+        .any(|property| optional_text(property, "Name").as_deref() == Some(name))
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn optional_text(node: Node, name: &'static str) -> Option<String> {
+    children(node, name)
+        .next()
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+}
+
+fn text(node: Node, name: &'static str) -> Result<String> {
+    optional_text(node, name).ok_or_else(|| {
+        anyhow!(
+            "<{}> on line {} has no <{name}>.",
+            node.tag_name().name(),
+            node.document().text_pos_at(node.range().start).row
+        )
+    })
+}
+
+fn number(node: Node, name: &'static str) -> Result<usize> {
+    let text = text(node, name)?;
+
+    text.parse()
+        .map_err(|_| anyhow!("<{name}> {text:?} is not a number."))
+}
+
+/// A size in bits, as bytes.
+fn bytes(node: Node, name: &'static str) -> Result<usize> {
+    Ok(number(node, name)?.div_ceil(8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_fields_are_left_out() {
+        let type_table = parse(
+            "<DataTypes><DataType><Name>ST_Flags</Name><BitSize>16</BitSize>\
+             <SubItem><Name>bA</Name><Type>BIT</Type><BitSize>1</BitSize><BitOffs>0</BitOffs></SubItem>\
+             <SubItem><Name>nB</Name><Type>BYTE</Type><BitSize>8</BitSize><BitOffs>8</BitOffs></SubItem>\
+             </DataType></DataTypes>",
+        )
+        .unwrap();
+
+        let TypeKind::Struct { fields } = &type_table.data_types[0].kind else {
+            panic!("a struct");
+        };
+
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "nB");
+        assert_eq!(fields[0].offset, 1);
+    }
+
+    #[test]
+    fn missing_elements_are_reported_with_their_line() {
+        let error = parse("<DataTypes>\n<DataType><BitSize>8</BitSize></DataType></DataTypes>")
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "<DataType> on line 2 has no <Name>.");
+    }
+}
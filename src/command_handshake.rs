@@ -0,0 +1,251 @@
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{RecvTimeoutError, TryRecvError};
+
+use crate::{
+    data_types::primitives::{bool::PlcBool, udint::PlcUDInt},
+    error::{PlcError, PlcResult},
+    plc_connection::PlcConnection,
+    subscription::{DeliveryPolicy, Subscription},
+};
+
+// Matches the cycle time notifications are checked at
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The symbols a PLC command is driven through.
+///
+/// The PLC is expected to start the command on the rising edge of the execute flag, then set either the done flag,
+/// or the error flag together with the error ID. Both flags must clear once the execute flag is reset.
+#[derive(Clone, Debug)]
+pub struct CommandHandshake {
+    execute: String,
+    done: String,
+    error: String,
+    error_id: String,
+    timeout: Duration,
+}
+
+impl CommandHandshake {
+    pub fn new(
+        execute: impl Into<String>,
+        done: impl Into<String>,
+        error: impl Into<String>,
+        error_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            execute: execute.into(),
+            done: done.into(),
+            error: error.into(),
+            error_id: error_id.into(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Uses the conventional bExecute, bDone, bError and nErrorId members of a function block instance.
+    pub fn for_function_block(path: &str) -> Self {
+        Self::new(
+            format!("{path}.bExecute"),
+            format!("{path}.bDone"),
+            format!("{path}.bError"),
+            format!("{path}.nErrorId"),
+        )
+    }
+
+    /// How long the PLC may take to finish the command, defaults to 5 seconds.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+}
+
+/// How the PLC finished a command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandOutcome {
+    Done,
+    Error { error_id: u32 },
+}
+
+impl PlcConnection {
+    /// Runs a command on the PLC: writes its parameters, sets the execute flag, waits for the done or error flag, then
+    /// resets the execute flag.
+    ///
+    /// The execute flag is reset even if waiting fails. Parameters are written by the given closure.
+    pub fn run_command(
+        &self,
+        handshake: &CommandHandshake,
+        write_parameters: impl FnOnce(&PlcConnection) -> PlcResult<()>,
+    ) -> PlcResult<CommandOutcome> {
+        let deadline = Instant::now() + handshake.timeout;
+
+        // Flags left over from the previous command clear one PLC cycle after its execute flag was reset
+        self.wait_for_flags_to_clear(handshake, deadline)?;
+
+        let mut flags = CommandFlags::subscribe(self, handshake)?;
+
+        write_parameters(self)?;
+
+        let _execute = ExecuteGuard::set(self, &handshake.execute)?;
+
+        loop {
+            let (done, error) = flags.next(deadline)?;
+
+            if error {
+                let error_id: PlcUDInt = self.read_symbol(&handshake.error_id)?;
+
+                return Ok(CommandOutcome::Error {
+                    error_id: error_id.into(),
+                });
+            }
+
+            if done {
+                return Ok(CommandOutcome::Done);
+            }
+        }
+    }
+
+    fn wait_for_flags_to_clear(
+        &self,
+        handshake: &CommandHandshake,
+        deadline: Instant,
+    ) -> PlcResult<()> {
+        loop {
+            let (done, error) = self.read_flags(handshake)?;
+
+            if !done && !error {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Err(PlcError::Timeout);
+            }
+
+            std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    fn read_flags(&self, handshake: &CommandHandshake) -> PlcResult<(bool, bool)> {
+        let read_flag = |name: &str| -> PlcResult<bool> {
+            let flag: PlcBool = self.read_symbol(name)?;
+
+            Ok(flag.into())
+        };
+
+        Ok((read_flag(&handshake.done)?, read_flag(&handshake.error)?))
+    }
+}
+
+/// Tracks the done and error flags, through notifications if possible, otherwise by polling.
+// NB: lives on the stack for the duration of a single command, so the size difference does not matter
+#[allow(clippy::large_enum_variant)]
+enum CommandFlags<'a> {
+    Notified {
+        done: Subscription<PlcBool>,
+        error: Subscription<PlcBool>,
+        latest: (bool, bool),
+    },
+    Polled {
+        connection: &'a PlcConnection,
+        handshake: &'a CommandHandshake,
+        polled: bool,
+    },
+}
+
+impl<'a> CommandFlags<'a> {
+    fn subscribe(
+        connection: &'a PlcConnection,
+        handshake: &'a CommandHandshake,
+    ) -> PlcResult<Self> {
+        let subscribe = |name: &str| {
+            connection.subscribe_with_policy::<PlcBool>(name, DeliveryPolicy::CoalesceLatest)
+        };
+
+        match (subscribe(&handshake.done), subscribe(&handshake.error)) {
+            (Ok(done), Ok(error)) => Ok(Self::Notified {
+                done,
+                error,
+                latest: (false, false),
+            }),
+            _ if !connection.is_connected() => Err(PlcError::NotConnected),
+            _ => Ok(Self::Polled {
+                connection,
+                handshake,
+                polled: false,
+            }),
+        }
+    }
+
+    /// Returns the latest (done, error) flags, blocking for up to one poll interval.
+    fn next(&mut self, deadline: Instant) -> PlcResult<(bool, bool)> {
+        let now = Instant::now();
+
+        if now >= deadline {
+            return Err(PlcError::Timeout);
+        }
+
+        let timeout = POLL_INTERVAL.min(deadline - now);
+
+        match self {
+            CommandFlags::Notified {
+                done,
+                error,
+                latest,
+            } => {
+                match done.recv_timeout(timeout) {
+                    Ok(value) => latest.0 = value.into(),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return Err(PlcError::NotConnected),
+                }
+
+                match error.try_recv() {
+                    Ok(value) => latest.1 = value.into(),
+                    Err(TryRecvError::Empty) => {}
+                    Err(TryRecvError::Disconnected) => return Err(PlcError::NotConnected),
+                }
+
+                Ok(*latest)
+            }
+            CommandFlags::Polled {
+                connection,
+                handshake,
+                polled,
+            } => {
+                if *polled {
+                    std::thread::sleep(timeout);
+                }
+
+                *polled = true;
+
+                connection.read_flags(handshake)
+            }
+        }
+    }
+}
+
+/// Sets the execute flag, resetting it again when dropped.
+struct ExecuteGuard<'a> {
+    connection: &'a PlcConnection,
+    name: &'a str,
+}
+
+impl<'a> ExecuteGuard<'a> {
+    fn set(connection: &'a PlcConnection, name: &'a str) -> PlcResult<Self> {
+        // Reset on drop even if setting failed, as the write may still have reached the PLC
+        let guard = Self { connection, name };
+
+        connection.write_symbol(name, PlcBool::from(true))?;
+
+        Ok(guard)
+    }
+}
+
+impl Drop for ExecuteGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(error) = self
+            .connection
+            .write_symbol(self.name, PlcBool::from(false))
+        {
+            tracing::warn!(%error, flag = self.name, "Could not reset PLC command flag");
+        }
+    }
+}
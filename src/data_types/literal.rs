@@ -0,0 +1,788 @@
+//! IEC 61131-3 literals for the PLC's elementary data types, e.g: `TRUE`, `16#FF`, `1.5E3`, `T#1h2m`,
+//! `DT#2024-01-01-12:00:00` and `'text'`.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use super::{
+    primitives::{
+        bool::PlcBool, dint::PlcDInt, int::PlcInt, lreal::PlcLReal, real::PlcReal,
+        string16::PlcString16, time::PlcTime, udint::PlcUDInt, uint::PlcUInt, word::PlcWord,
+    },
+    value::{PlcType, PlcValue},
+};
+
+// Duration units from largest to smallest, with their length in nanoseconds
+const DURATION_UNITS: [(&str, u128); 7] = [
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// A literal that could not be parsed, with the reason why.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid {expected} literal {literal:?}: {reason}")]
+pub struct LiteralError {
+    pub literal: String,
+    /// The type the literal was parsed as, or `IEC` if it was to be inferred.
+    pub expected: String,
+    pub reason: String,
+}
+
+impl LiteralError {
+    fn new(literal: &str, expected: impl ToString, reason: impl ToString) -> Self {
+        Self {
+            literal: literal.to_string(),
+            expected: expected.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl PlcType {
+    /// Parses a literal of this type, with or without its type prefix, e.g: `5`, `16#FF` or `INT#5` for an INT.
+    pub fn parse_literal(self, literal: &str) -> Result<PlcValue, LiteralError> {
+        let error = |reason: String| LiteralError::new(literal, self, reason);
+
+        let text = literal.trim();
+
+        let body = match text.split_once('#') {
+            Some((prefix, body)) => match type_for_prefix(prefix) {
+                Some(prefixed) if prefixed.is_kind_of(self) => body,
+                Some(prefixed) => return Err(error(format!("is a {prefixed} literal"))),
+                // A based number, e.g: 16#FF
+                None => text,
+            },
+            None => text,
+        };
+
+        macro_rules! integer {
+            ($variant:ident, $type:ty) => {{
+                let value = parse_integer(body).map_err(error)?;
+
+                PlcValue::$variant(<$type>::try_from(value).map_err(|_| {
+                    error(format!("out of range {} to {}", <$type>::MIN, <$type>::MAX))
+                })?)
+            }};
+        }
+
+        let value = match self {
+            PlcType::Bool => match body.to_ascii_uppercase().as_str() {
+                "TRUE" | "1" => PlcValue::Bool(true),
+                "FALSE" | "0" => PlcValue::Bool(false),
+                _ => return Err(error("expected TRUE or FALSE".to_string())),
+            },
+            PlcType::SInt => integer!(SInt, i8),
+            PlcType::USInt => integer!(USInt, u8),
+            PlcType::Byte => integer!(Byte, u8),
+            PlcType::Int => integer!(Int, i16),
+            PlcType::UInt => integer!(UInt, u16),
+            PlcType::Word => integer!(Word, u16),
+            PlcType::DInt => integer!(DInt, i32),
+            PlcType::UDInt => integer!(UDInt, u32),
+            PlcType::DWord => integer!(DWord, u32),
+            PlcType::LInt => integer!(LInt, i64),
+            PlcType::ULInt => integer!(ULInt, u64),
+            PlcType::LWord => integer!(LWord, u64),
+            PlcType::Real => PlcValue::Real(parse_real(body).map_err(error)?),
+            PlcType::LReal => PlcValue::LReal(parse_real(body).map_err(error)?),
+            PlcType::Time => {
+                let duration = parse_duration(body).map_err(error)?;
+
+                if duration.subsec_nanos() % 1_000_000 != 0 {
+                    return Err(error("finer than the 1ms resolution of a TIME".to_string()));
+                }
+
+                if duration.as_millis() > u32::MAX.into() {
+                    return Err(error("longer than T#49d17h2m47s295ms".to_string()));
+                }
+
+                PlcValue::Time(duration)
+            }
+            PlcType::LTime => PlcValue::LTime(parse_duration(body).map_err(error)?),
+            PlcType::Date => PlcValue::Date(
+                NaiveDate::parse_from_str(body, "%Y-%m-%d")
+                    .map_err(|_| error("expected a date such as 2024-01-31".to_string()))?,
+            ),
+            PlcType::DateAndTime => {
+                PlcValue::DateAndTime(parse_date_and_time(body).map_err(error)?)
+            }
+            PlcType::TimeOfDay => PlcValue::TimeOfDay(
+                NaiveTime::parse_from_str(body, "%H:%M:%S%.f")
+                    .map_err(|_| error("expected a time of day such as 12:00:00.5".to_string()))?,
+            ),
+            PlcType::String(length) => {
+                let text = parse_string(body).map_err(error)?;
+
+                if text.chars().count() > length {
+                    return Err(error(format!("longer than {length} characters")));
+                }
+
+                PlcValue::String(text)
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+impl PlcValue {
+    /// The value as a literal with its type, where the literal alone does not give it, e.g: `INT#5` or
+    /// `WORD#16#00FF`. Strings lose their declared length.
+    pub fn to_typed_literal(&self) -> String {
+        match self {
+            PlcValue::SInt(_)
+            | PlcValue::USInt(_)
+            | PlcValue::Byte(_)
+            | PlcValue::Int(_)
+            | PlcValue::UInt(_)
+            | PlcValue::Word(_)
+            | PlcValue::DInt(_)
+            | PlcValue::UDInt(_)
+            | PlcValue::DWord(_)
+            | PlcValue::LInt(_)
+            | PlcValue::ULInt(_)
+            | PlcValue::LWord(_)
+            | PlcValue::Real(_)
+            | PlcValue::LReal(_) => format!("{}#{self}", self.plc_type()),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for PlcValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlcValue::Bool(true) => f.write_str("TRUE"),
+            PlcValue::Bool(false) => f.write_str("FALSE"),
+            PlcValue::SInt(value) => write!(f, "{value}"),
+            PlcValue::USInt(value) => write!(f, "{value}"),
+            PlcValue::Byte(value) => write!(f, "16#{value:02X}"),
+            PlcValue::Int(value) => write!(f, "{value}"),
+            PlcValue::UInt(value) => write!(f, "{value}"),
+            PlcValue::Word(value) => write!(f, "16#{value:04X}"),
+            PlcValue::DInt(value) => write!(f, "{value}"),
+            PlcValue::UDInt(value) => write!(f, "{value}"),
+            PlcValue::DWord(value) => write!(f, "16#{value:08X}"),
+            PlcValue::LInt(value) => write!(f, "{value}"),
+            PlcValue::ULInt(value) => write!(f, "{value}"),
+            PlcValue::LWord(value) => write!(f, "16#{value:016X}"),
+            PlcValue::Real(value) => f.write_str(&format_real(format!("{value:?}"))),
+            PlcValue::LReal(value) => f.write_str(&format_real(format!("{value:?}"))),
+            PlcValue::Time(duration) => write!(f, "T#{}", format_duration(*duration, "ms")),
+            PlcValue::LTime(duration) => write!(f, "LTIME#{}", format_duration(*duration, "ns")),
+            PlcValue::Date(date) => write!(f, "D#{}", date.format("%Y-%m-%d")),
+            PlcValue::DateAndTime(date_time) => {
+                write!(f, "DT#{}", date_time.format("%Y-%m-%d-%H:%M:%S%.f"))
+            }
+            PlcValue::TimeOfDay(time) => write!(f, "TOD#{}", time.format("%H:%M:%S%.f")),
+            PlcValue::String(text) => f.write_str(&format_string(text)),
+        }
+    }
+}
+
+/// Parses a typed literal, e.g: `INT#5`, or infers the type of an untyped one: `TRUE` is a BOOL, `'text'` a STRING,
+/// integers a DINT or wider if needed, and other numbers an LREAL.
+impl FromStr for PlcValue {
+    type Err = LiteralError;
+
+    fn from_str(literal: &str) -> Result<Self, Self::Err> {
+        let text = literal.trim();
+
+        if let Some(plc_type) = text
+            .split_once('#')
+            .and_then(|(prefix, _)| type_for_prefix(prefix))
+        {
+            return plc_type.parse_literal(literal);
+        }
+
+        if text.eq_ignore_ascii_case("TRUE") || text.eq_ignore_ascii_case("FALSE") {
+            return PlcType::Bool.parse_literal(literal);
+        }
+
+        if text.starts_with('\'') {
+            return parse_string(text)
+                .map(PlcValue::String)
+                .map_err(|reason| LiteralError::new(literal, "STRING", reason));
+        }
+
+        let integer = parse_integer(text);
+
+        if let Ok(value) = integer {
+            return i32::try_from(value)
+                .map(PlcValue::DInt)
+                .or_else(|_| i64::try_from(value).map(PlcValue::LInt))
+                .or_else(|_| u64::try_from(value).map(PlcValue::ULInt))
+                .map_err(|_| LiteralError::new(literal, "ULINT", "out of range"));
+        }
+
+        if text.contains('#') {
+            return Err(LiteralError::new(literal, "IEC", integer.unwrap_err()));
+        }
+
+        parse_real(text).map(PlcValue::LReal).map_err(|_| {
+            LiteralError::new(
+                literal,
+                "IEC",
+                "expected TRUE, FALSE, a number, 'text' or a literal with a type such as T#1s",
+            )
+        })
+    }
+}
+
+/// The type a literal's prefix gives, e.g: `INT` in `INT#5` or `T` in `T#1s`.
+fn type_for_prefix(prefix: &str) -> Option<PlcType> {
+    match prefix.trim().to_ascii_uppercase().as_str() {
+        "T" => Some(PlcType::Time),
+        "LT" => Some(PlcType::LTime),
+        "D" => Some(PlcType::Date),
+        prefix => PlcType::from_name(prefix),
+    }
+}
+
+/// Parses a decimal integer with an optional sign, or an unsigned one in base 2, 8 or 16, e.g: `16#FF`. Digits can
+/// be separated by single underscores, e.g: `1_000`.
+fn parse_integer(text: &str) -> Result<i128, String> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let (radix, digits) = match unsigned.split_once('#') {
+        Some(("2", digits)) => (2, digits),
+        Some(("8", digits)) => (8, digits),
+        Some(("16", digits)) => (16, digits),
+        Some((base, _)) => return Err(format!("{base}# is not a base, expected 2#, 8# or 16#")),
+        None => (10, unsigned),
+    };
+
+    if negative && radix != 10 {
+        return Err("only decimal numbers can be negative".to_string());
+    }
+
+    if digits.is_empty()
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+        || !digits
+            .chars()
+            .all(|digit| digit == '_' || digit.is_digit(radix))
+    {
+        return Err(format!(
+            "expected base {radix} digits, optionally separated by single underscores"
+        ));
+    }
+
+    let digits: String = digits.chars().filter(|&digit| digit != '_').collect();
+
+    let value = u128::from_str_radix(&digits, radix)
+        .ok()
+        .and_then(|value| i128::try_from(value).ok())
+        .ok_or("out of range")?;
+
+    Ok(if negative { -value } else { value })
+}
+
+/// Parses a REAL or LREAL, e.g: `1.5`, `-2`, `1_000.0` or `1.5E-3`.
+fn parse_real<T: FromStr + IsInfinite>(text: &str) -> Result<T, String> {
+    let digits: String = text.chars().filter(|&digit| digit != '_').collect();
+
+    let value: T = digits
+        .parse()
+        .map_err(|_| "expected a number such as 1.5 or 1.5E3".to_string())?;
+
+    // NB: IEC 61131-3 has no literal for infinity or NaN, but the values are accepted as Rust formats them
+    if value.is_infinite() && !digits.to_ascii_lowercase().contains("inf") {
+        return Err("out of range".to_string());
+    }
+
+    Ok(value)
+}
+
+trait IsInfinite {
+    fn is_infinite(&self) -> bool;
+}
+
+impl IsInfinite for f32 {
+    fn is_infinite(&self) -> bool {
+        f32::is_infinite(*self)
+    }
+}
+
+impl IsInfinite for f64 {
+    fn is_infinite(&self) -> bool {
+        f64::is_infinite(*self)
+    }
+}
+
+/// Formats a real as Rust's debug format does, which always round-trips, but with an IEC exponent, e.g: `1.0E20`.
+fn format_real(mut text: String) -> String {
+    if let Some(exponent) = text.find('e') {
+        if !text[..exponent].contains('.') {
+            text.insert_str(exponent, ".0");
+        }
+    }
+
+    text.replace('e', "E")
+}
+
+/// Parses a duration from its units, largest first, e.g: `1d2h3m4s5ms6us7ns`. The last can have a fraction, e.g:
+/// `1.5s`.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    if text.starts_with('-') {
+        return Err("negative durations are not supported".to_string());
+    }
+
+    let text = text.to_ascii_lowercase();
+
+    let mut rest = text.as_str();
+    let mut nanoseconds: u128 = 0;
+    let mut previous_unit = None;
+
+    if rest.is_empty() {
+        return Err("expected a duration such as 1h2m3s".to_string());
+    }
+
+    while !rest.is_empty() {
+        rest = rest.strip_prefix('_').unwrap_or(rest);
+
+        let number_length = rest
+            .find(|character: char| !character.is_ascii_digit() && character != '.')
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_length);
+
+        let unit_length = after
+            .find(|character: char| !character.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_length);
+
+        let Some(index) = DURATION_UNITS.iter().position(|(name, _)| *name == unit) else {
+            return Err(format!(
+                "unknown unit {unit:?}, expected d, h, m, s, ms, us or ns"
+            ));
+        };
+
+        if previous_unit.is_some_and(|previous| index <= previous) {
+            return Err("units must go from largest to smallest, each at most once".to_string());
+        }
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+
+        if !fraction.is_empty() && !after.is_empty() {
+            return Err("only the last unit can have a fraction".to_string());
+        }
+
+        let unit_nanoseconds = DURATION_UNITS[index].1;
+
+        let whole: u128 = whole
+            .parse()
+            .map_err(|_| format!("expected a number before {unit:?}"))?;
+
+        let fraction_nanoseconds = match fraction {
+            "" => 0,
+            fraction if fraction.len() <= 18 && fraction.chars().all(|c| c.is_ascii_digit()) => {
+                let scale = 10u128.pow(fraction.len() as u32);
+                let scaled = fraction.parse::<u128>().expect("digits") * unit_nanoseconds;
+
+                if !scaled.is_multiple_of(scale) {
+                    return Err("finer than a nanosecond".to_string());
+                }
+
+                scaled / scale
+            }
+            _ => return Err(format!("expected a number before {unit:?}")),
+        };
+
+        nanoseconds = whole
+            .checked_mul(unit_nanoseconds)
+            .and_then(|whole| whole.checked_add(fraction_nanoseconds))
+            .and_then(|value| value.checked_add(nanoseconds))
+            .ok_or("out of range")?;
+
+        previous_unit = Some(index);
+        rest = after;
+    }
+
+    u64::try_from(nanoseconds)
+        .map(Duration::from_nanos)
+        .map_err(|_| "out of range".to_string())
+}
+
+/// Formats a duration in its units, largest first, e.g: `1h2m`, or zero of the smallest unit.
+fn format_duration(duration: Duration, zero_unit: &str) -> String {
+    let mut nanoseconds = duration.as_nanos();
+    let mut text = String::new();
+
+    for (unit, unit_nanoseconds) in DURATION_UNITS {
+        let count = nanoseconds / unit_nanoseconds;
+
+        if count > 0 {
+            text.push_str(&format!("{count}{unit}"));
+
+            nanoseconds %= unit_nanoseconds;
+        }
+    }
+
+    if text.is_empty() {
+        text = format!("0{zero_unit}");
+    }
+
+    text
+}
+
+/// Parses a date and time of day separated by a dash, e.g: `2024-01-31-12:00:00`.
+fn parse_date_and_time(text: &str) -> Result<NaiveDateTime, String> {
+    let invalid = || "expected a date and time such as 2024-01-31-12:00:00".to_string();
+
+    let separator = text.match_indices('-').nth(2).ok_or_else(invalid)?.0;
+
+    let date = NaiveDate::parse_from_str(&text[..separator], "%Y-%m-%d").map_err(|_| invalid())?;
+    let time =
+        NaiveTime::parse_from_str(&text[separator + 1..], "%H:%M:%S%.f").map_err(|_| invalid())?;
+
+    Ok(date.and_time(time))
+}
+
+/// Parses text in single quotes, with `$` escapes: `$$`, `$'`, `$L` or `$N` for a new line, `$P`, `$R`, `$T` and
+/// `$hh` for a character by its code.
+fn parse_string(text: &str) -> Result<String, String> {
+    let quoted = text
+        .strip_prefix('\'')
+        .and_then(|text| text.strip_suffix('\''))
+        .filter(|_| text.len() >= 2)
+        .ok_or("expected text in single quotes, e.g: 'text'")?;
+
+    let mut parsed = String::new();
+    let mut characters = quoted.chars();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '\'' => return Err("quotes in text must be escaped as $'".to_string()),
+            '$' => {
+                let escaped = match characters.next() {
+                    Some('$') => '$',
+                    Some('\'') => '\'',
+                    Some('L' | 'l' | 'N' | 'n') => '\n',
+                    Some('P' | 'p') => '\x0c',
+                    Some('R' | 'r') => '\r',
+                    Some('T' | 't') => '\t',
+                    Some(high) if high.is_ascii_hexdigit() => {
+                        let code = characters
+                            .next()
+                            .filter(char::is_ascii_hexdigit)
+                            .map(|low| format!("{high}{low}"))
+                            .ok_or("expected two hex digits after $")?;
+
+                        u8::from_str_radix(&code, 16).expect("hex digits") as char
+                    }
+                    Some(other) => return Err(format!("unknown escape ${other}")),
+                    None => return Err("expected an escape after $".to_string()),
+                };
+
+                parsed.push(escaped);
+            }
+            character => parsed.push(character),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Formats text in single quotes, escaping quotes, dollars and control characters.
+fn format_string(text: &str) -> String {
+    let mut literal = String::from("'");
+
+    for character in text.chars() {
+        match character {
+            '$' => literal.push_str("$$"),
+            '\'' => literal.push_str("$'"),
+            '\n' => literal.push_str("$N"),
+            '\x0c' => literal.push_str("$P"),
+            '\r' => literal.push_str("$R"),
+            '\t' => literal.push_str("$T"),
+            character if character.is_control() && u32::from(character) <= 0xff => {
+                literal.push_str(&format!("${:02X}", u32::from(character)));
+            }
+            character => literal.push(character),
+        }
+    }
+
+    literal.push('\'');
+
+    literal
+}
+
+/// Displays primitives as IEC literals, and parses them back from one.
+macro_rules! literal_text {
+    ($($primitive:ty => $plc_type:expr),* $(,)?) => {$(
+        impl fmt::Display for $primitive {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                PlcValue::from(self.clone()).fmt(f)
+            }
+        }
+
+        impl FromStr for $primitive {
+            type Err = LiteralError;
+
+            fn from_str(literal: &str) -> Result<Self, Self::Err> {
+                let value = $plc_type.parse_literal(literal)?;
+
+                Self::try_from(value)
+                    .map_err(|error| LiteralError::new(literal, $plc_type, error))
+            }
+        }
+    )*};
+}
+
+literal_text!(
+    PlcBool => PlcType::Bool,
+    PlcDInt => PlcType::DInt,
+    PlcInt => PlcType::Int,
+    PlcLReal => PlcType::LReal,
+    PlcReal => PlcType::Real,
+    PlcString16 => PlcType::String(16),
+    PlcTime => PlcType::Time,
+    PlcUDInt => PlcType::UDInt,
+    PlcUInt => PlcType::UInt,
+    PlcWord => PlcType::Word,
+);
+
+#[cfg(test)]
+mod tests {
+    use zerocopy::AsBytes;
+
+    use super::*;
+
+    fn parse(plc_type: PlcType, literal: &str) -> PlcValue {
+        plc_type.parse_literal(literal).unwrap()
+    }
+
+    fn reason(plc_type: PlcType, literal: &str) -> String {
+        plc_type.parse_literal(literal).unwrap_err().reason
+    }
+
+    #[test]
+    fn numbers_parse_in_any_base() {
+        assert_eq!(parse(PlcType::Int, "-300"), PlcValue::Int(-300));
+        assert_eq!(parse(PlcType::Int, "INT#5"), PlcValue::Int(5));
+        assert_eq!(parse(PlcType::Byte, "16#FF"), PlcValue::Byte(0xff));
+        assert_eq!(parse(PlcType::Word, "WORD#16#00_FF"), PlcValue::Word(0xff));
+        assert_eq!(parse(PlcType::USInt, "2#1010"), PlcValue::USInt(10));
+        assert_eq!(parse(PlcType::UDInt, "8#777"), PlcValue::UDInt(0o777));
+        assert_eq!(parse(PlcType::DInt, "1_000_000"), PlcValue::DInt(1_000_000));
+        assert_eq!(parse(PlcType::Real, "1.5E3"), PlcValue::Real(1500.0));
+        assert_eq!(parse(PlcType::LReal, "-2"), PlcValue::LReal(-2.0));
+        assert_eq!(parse(PlcType::Bool, "true"), PlcValue::Bool(true));
+    }
+
+    #[test]
+    fn times_and_dates_parse() {
+        assert_eq!(
+            parse(PlcType::Time, "T#1h2m"),
+            PlcValue::Time(Duration::from_secs(3720))
+        );
+        assert_eq!(
+            parse(PlcType::Time, "TIME#1.5s"),
+            PlcValue::Time(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse(PlcType::LTime, "LTIME#1ms_5us"),
+            PlcValue::LTime(Duration::from_micros(1005))
+        );
+        assert_eq!(
+            parse(PlcType::Date, "D#2024-01-31"),
+            PlcValue::Date(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())
+        );
+        assert_eq!(
+            parse(PlcType::DateAndTime, "DT#2024-01-01-12:00:00"),
+            PlcValue::DateAndTime(
+                NaiveDate::from_ymd_opt(2024, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(12, 0, 0)
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            parse(PlcType::TimeOfDay, "TOD#08:30:00.25"),
+            PlcValue::TimeOfDay(NaiveTime::from_hms_milli_opt(8, 30, 0, 250).unwrap())
+        );
+    }
+
+    #[test]
+    fn strings_parse_with_escapes() {
+        assert_eq!(
+            parse(PlcType::String(80), "'It$'s $$5$N$41'"),
+            PlcValue::String("It's $5\nA".to_string())
+        );
+        assert_eq!(
+            parse(PlcType::String(80), "STRING#''"),
+            PlcValue::String(String::new())
+        );
+    }
+
+    #[test]
+    fn values_round_trip_through_their_literals() {
+        for value in [
+            PlcValue::Bool(false),
+            PlcValue::SInt(-128),
+            PlcValue::Byte(0x0f),
+            PlcValue::Word(0xff00),
+            PlcValue::UDInt(u32::MAX),
+            PlcValue::LWord(u64::MAX),
+            PlcValue::LInt(i64::MIN),
+            PlcValue::Real(0.1),
+            PlcValue::Real(1e20),
+            PlcValue::LReal(-1.5e-7),
+            PlcValue::Time(Duration::ZERO),
+            PlcValue::Time(Duration::from_millis(90_061_001)),
+            PlcValue::LTime(Duration::from_nanos(3_600_000_000_001)),
+            PlcValue::Date(NaiveDate::from_ymd_opt(1999, 12, 31).unwrap()),
+            PlcValue::DateAndTime(
+                NaiveDate::from_ymd_opt(2024, 2, 29)
+                    .unwrap()
+                    .and_hms_milli_opt(23, 59, 59, 500)
+                    .unwrap(),
+            ),
+            PlcValue::TimeOfDay(NaiveTime::from_hms_opt(0, 0, 1).unwrap()),
+            PlcValue::String("'$\t\u{1}ü'".to_string()),
+        ] {
+            let literal = value.to_string();
+
+            assert_eq!(parse(value.plc_type(), &literal), value, "{literal}");
+            assert_eq!(value.to_typed_literal().parse::<PlcValue>().unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn values_format_as_iec_literals() {
+        assert_eq!(PlcValue::Bool(true).to_string(), "TRUE");
+        assert_eq!(PlcValue::Word(0xff).to_string(), "16#00FF");
+        assert_eq!(PlcValue::Word(0xff).to_typed_literal(), "WORD#16#00FF");
+        assert_eq!(PlcValue::Int(-5).to_typed_literal(), "INT#-5");
+        assert_eq!(PlcValue::Real(1.0).to_string(), "1.0");
+        assert_eq!(PlcValue::LReal(1e20).to_string(), "1.0E20");
+        assert_eq!(
+            PlcValue::Time(Duration::from_secs(3720)).to_string(),
+            "T#1h2m"
+        );
+        assert_eq!(PlcValue::Time(Duration::ZERO).to_string(), "T#0ms");
+        assert_eq!(
+            PlcValue::DateAndTime(
+                NaiveDate::from_ymd_opt(2024, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(12, 0, 0)
+                    .unwrap()
+            )
+            .to_string(),
+            "DT#2024-01-01-12:00:00"
+        );
+        assert_eq!(PlcValue::String("It's".to_string()).to_string(), "'It$'s'");
+    }
+
+    #[test]
+    fn untyped_literals_are_inferred() {
+        assert_eq!("TRUE".parse(), Ok(PlcValue::Bool(true)));
+        assert_eq!("5".parse(), Ok(PlcValue::DInt(5)));
+        assert_eq!("16#FF".parse(), Ok(PlcValue::DInt(0xff)));
+        assert_eq!("5000000000".parse(), Ok(PlcValue::LInt(5_000_000_000)));
+        assert_eq!("1.5".parse(), Ok(PlcValue::LReal(1.5)));
+        assert_eq!("'text'".parse(), Ok(PlcValue::String("text".to_string())));
+        assert_eq!("T#1s".parse(), Ok(PlcValue::Time(Duration::from_secs(1))));
+        assert_eq!("USINT#7".parse(), Ok(PlcValue::USInt(7)));
+    }
+
+    #[test]
+    fn invalid_literals_explain_why() {
+        assert_eq!(
+            reason(PlcType::Int, "70000"),
+            "out of range -32768 to 32767"
+        );
+        assert_eq!(reason(PlcType::Int, "REAL#1.5"), "is a REAL literal");
+        assert_eq!(
+            reason(PlcType::Byte, "16#FG"),
+            "expected base 16 digits, optionally separated by single underscores"
+        );
+        assert_eq!(
+            reason(PlcType::Byte, "3#12"),
+            "3# is not a base, expected 2#, 8# or 16#"
+        );
+        assert_eq!(reason(PlcType::Bool, "yes"), "expected TRUE or FALSE");
+        assert_eq!(reason(PlcType::Real, "1e39"), "out of range");
+        assert_eq!(
+            reason(PlcType::Time, "T#1x"),
+            "unknown unit \"x\", expected d, h, m, s, ms, us or ns"
+        );
+        assert_eq!(
+            reason(PlcType::Time, "T#1s2m"),
+            "units must go from largest to smallest, each at most once"
+        );
+        assert_eq!(
+            reason(PlcType::Time, "T#1.5s2ms"),
+            "only the last unit can have a fraction"
+        );
+        assert_eq!(
+            reason(PlcType::Time, "T#1.5ms"),
+            "finer than the 1ms resolution of a TIME"
+        );
+        assert_eq!(
+            reason(PlcType::Time, "T#50d"),
+            "longer than T#49d17h2m47s295ms"
+        );
+        assert_eq!(
+            reason(PlcType::Date, "D#2024-02-30"),
+            "expected a date such as 2024-01-31"
+        );
+        assert_eq!(
+            reason(PlcType::String(80), "text"),
+            "expected text in single quotes, e.g: 'text'"
+        );
+        assert_eq!(
+            reason(PlcType::String(80), "'it's'"),
+            "quotes in text must be escaped as $'"
+        );
+        assert_eq!(
+            reason(PlcType::String(3), "'text'"),
+            "longer than 3 characters"
+        );
+
+        assert_eq!(
+            PlcType::Int.parse_literal("70000").unwrap_err().to_string(),
+            "Invalid INT literal \"70000\": out of range -32768 to 32767"
+        );
+        assert_eq!(
+            "banana".parse::<PlcValue>().unwrap_err().to_string(),
+            "Invalid IEC literal \"banana\": expected TRUE, FALSE, a number, 'text' or a literal with a type such \
+             as T#1s"
+        );
+    }
+
+    #[test]
+    fn primitives_display_and_parse_iec_literals() {
+        assert_eq!(PlcBool::from(true).to_string(), "TRUE");
+        assert_eq!(PlcInt::from(-5).to_string(), "-5");
+        assert_eq!(PlcWord::from(0x1234).to_string(), "16#1234");
+        assert_eq!(PlcTime::from(3_723_004).to_string(), "T#1h2m3s4ms");
+        assert_eq!(
+            PlcString16::try_from("Running".to_string())
+                .unwrap()
+                .to_string(),
+            "'Running'"
+        );
+
+        assert_eq!(i16::from("INT#-5".parse::<PlcInt>().unwrap()), -5);
+        assert_eq!(u32::from("T#1h2m".parse::<PlcTime>().unwrap()), 3_720_000);
+        assert!(bool::from("TRUE".parse::<PlcBool>().unwrap()));
+        assert!("'Longer than sixteen'".parse::<PlcString16>().is_err());
+
+        // Latin-1 characters are one byte each on the PLC
+        let umlauts = "'üüüüüüüüüüüüüüüü'".parse::<PlcString16>().unwrap();
+        assert_eq!(umlauts.as_bytes(), [[0xfc; 16].as_slice(), &[0]].concat());
+        assert_eq!(umlauts.to_string(), "'üüüüüüüüüüüüüüüü'");
+        assert!("T#1h".parse::<PlcInt>().is_err());
+    }
+}
@@ -1,4 +1,6 @@
+pub mod literal;
 pub mod primitives;
+pub mod value;
 
 use std::fmt::Debug;
 
@@ -13,3 +15,8 @@ pub trait PlcDataType:
         Self::read_from(bytes)
     }
 }
+
+/// A data type with a numeric value, e.g: for applying deadbands.
+pub trait PlcNumeric: PlcDataType {
+    fn to_f64(&self) -> f64;
+}
@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 #[derive(Clone, Debug, Default, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
 #[repr(C)]
@@ -6,6 +6,18 @@ pub struct PlcDInt(i32);
 
 impl PlcDataType for PlcDInt {}
 
+impl PlcNumeric for PlcDInt {
+    fn to_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
+
+impl From<i32> for PlcDInt {
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
 impl From<PlcDInt> for i32 {
     fn from(value: PlcDInt) -> Self {
         value.0
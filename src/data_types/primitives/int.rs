@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 #[derive(Clone, Debug, Default, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
 #[repr(C)]
@@ -6,6 +6,12 @@ pub struct PlcInt(i16);
 
 impl PlcDataType for PlcInt {}
 
+impl PlcNumeric for PlcInt {
+    fn to_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
+
 impl From<i16> for PlcInt {
     fn from(value: i16) -> Self {
         Self(value)
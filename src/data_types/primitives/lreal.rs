@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 #[derive(Clone, Debug, Default, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
 #[repr(C)]
@@ -6,6 +6,12 @@ pub struct PlcLReal(f64);
 
 impl PlcDataType for PlcLReal {}
 
+impl PlcNumeric for PlcLReal {
+    fn to_f64(&self) -> f64 {
+        self.0
+    }
+}
+
 impl From<f64> for PlcLReal {
     fn from(value: f64) -> Self {
         Self(value)
@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 #[derive(Clone, Debug, Default, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
 #[repr(C)]
@@ -6,6 +6,12 @@ pub struct PlcReal(pub f32);
 
 impl PlcDataType for PlcReal {}
 
+impl PlcNumeric for PlcReal {
+    fn to_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
+
 impl From<f32> for PlcReal {
     fn from(value: f32) -> Self {
         Self(value)
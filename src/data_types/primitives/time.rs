@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 use super::udint::PlcUDInt;
 
@@ -8,6 +8,12 @@ pub struct PlcTime(PlcUDInt);
 
 impl PlcDataType for PlcTime {}
 
+impl PlcNumeric for PlcTime {
+    fn to_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
 impl From<u32> for PlcTime {
     fn from(value: u32) -> Self {
         Self(PlcUDInt::from(value))
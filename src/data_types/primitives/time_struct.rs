@@ -1,6 +1,12 @@
-use chrono::{DateTime, NaiveDate, Utc};
+use std::{fmt, str::FromStr};
 
-use crate::data_types::PlcDataType;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike, Utc};
+
+use crate::data_types::{
+    literal::LiteralError,
+    value::{PlcType, PlcValue},
+    PlcDataType,
+};
 
 use super::word::PlcWord;
 
@@ -41,3 +47,89 @@ impl From<PlcTimeStruct> for Option<DateTime<Utc>> {
         })
     }
 }
+
+impl From<NaiveDateTime> for PlcTimeStruct {
+    fn from(value: NaiveDateTime) -> Self {
+        let field = |field: u32| PlcWord::from(field as u16);
+
+        Self {
+            year: field(value.year() as u32),
+            month: field(value.month()),
+            day_of_week: field(value.weekday().num_days_from_sunday()),
+            day: field(value.day()),
+            hour: field(value.hour()),
+            minute: field(value.minute()),
+            second: field(value.second()),
+            milliseconds: field(value.nanosecond() / 1_000_000),
+        }
+    }
+}
+
+/// Displays as a `DT#` literal with milliseconds, from the fields as they are even if they are not a valid date.
+impl fmt::Display for PlcTimeStruct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = |field: &PlcWord| u16::from(field.clone());
+
+        write!(
+            f,
+            "DT#{:04}-{:02}-{:02}-{:02}:{:02}:{:02}",
+            field(&self.year),
+            field(&self.month),
+            field(&self.day),
+            field(&self.hour),
+            field(&self.minute),
+            field(&self.second)
+        )?;
+
+        match field(&self.milliseconds) {
+            0 => Ok(()),
+            milliseconds => write!(f, ".{milliseconds:03}"),
+        }
+    }
+}
+
+impl FromStr for PlcTimeStruct {
+    type Err = LiteralError;
+
+    fn from_str(literal: &str) -> Result<Self, Self::Err> {
+        let value = PlcType::DateAndTime.parse_literal(literal)?;
+
+        PlcTimeStruct::try_from(value).map_err(|error| LiteralError {
+            literal: literal.to_string(),
+            expected: PlcType::DateAndTime.to_string(),
+            reason: error.to_string(),
+        })
+    }
+}
+
+impl TryFrom<PlcTimeStruct> for PlcValue {
+    type Error = crate::error::PlcError;
+
+    fn try_from(value: PlcTimeStruct) -> Result<Self, Self::Error> {
+        let invalid = crate::error::PlcError::InvalidValue {
+            reason: format!("{value} is not a valid date and time"),
+        };
+
+        Option::<DateTime<Utc>>::from(value)
+            .map(|date_time| PlcValue::DateAndTime(date_time.naive_utc()))
+            .ok_or(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_structs_display_and_parse_as_date_and_time_literals() {
+        let time_struct: PlcTimeStruct = "DT#2024-01-31-12:30:05.250".parse().unwrap();
+
+        assert_eq!(time_struct.to_string(), "DT#2024-01-31-12:30:05.250");
+        assert_eq!(u16::from(time_struct.day_of_week.clone()), 3);
+        assert_eq!(
+            PlcTimeStruct::default().to_string(),
+            "DT#0000-00-00-00:00:00"
+        );
+        assert!(PlcValue::try_from(PlcTimeStruct::default()).is_err());
+    }
+}
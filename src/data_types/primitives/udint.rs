@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 #[derive(Clone, Debug, Default, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
 #[repr(C)]
@@ -6,6 +6,12 @@ pub struct PlcUDInt(u32);
 
 impl PlcDataType for PlcUDInt {}
 
+impl PlcNumeric for PlcUDInt {
+    fn to_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
+
 impl From<[u16; 2]> for PlcUDInt {
     fn from(value: [u16; 2]) -> Self {
         Self(bytemuck::cast(value))
@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 #[derive(Clone, Debug, Default, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
 #[repr(C)]
@@ -6,6 +6,12 @@ pub struct PlcUInt(u16);
 
 impl PlcDataType for PlcUInt {}
 
+impl PlcNumeric for PlcUInt {
+    fn to_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
+
 impl From<u16> for PlcUInt {
     fn from(value: u16) -> Self {
         Self(value)
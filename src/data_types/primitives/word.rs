@@ -1,4 +1,4 @@
-use crate::data_types::PlcDataType;
+use crate::data_types::{PlcDataType, PlcNumeric};
 
 #[derive(Clone, Debug, Default, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
 #[repr(C)]
@@ -6,8 +6,20 @@ pub struct PlcWord(u16);
 
 impl PlcDataType for PlcWord {}
 
+impl PlcNumeric for PlcWord {
+    fn to_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
+
 impl From<PlcWord> for u16 {
     fn from(value: PlcWord) -> Self {
         value.0
     }
 }
+
+impl From<u16> for PlcWord {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
@@ -0,0 +1,561 @@
+//! Values of the PLC's elementary data types, for symbols whose type is only known at runtime, e.g: in a config file
+//! or on the command line.
+
+use std::{fmt, mem, time::Duration};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use zerocopy::AsBytes;
+
+use crate::{
+    data_types::primitives::{
+        bool::PlcBool, dint::PlcDInt, int::PlcInt, lreal::PlcLReal, real::PlcReal,
+        string16::PlcString16, time::PlcTime, time_struct::PlcTimeStruct, udint::PlcUDInt,
+        uint::PlcUInt, word::PlcWord,
+    },
+    data_types::PlcDataType,
+    error::{PlcError, PlcResult},
+};
+
+// TwinCAT's length for a STRING declared without one
+const DEFAULT_STRING_LENGTH: usize = 80;
+
+const MILLISECONDS_PER_DAY: u32 = 86_400_000;
+
+/// An elementary data type, named as on the PLC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlcType {
+    Bool,
+    SInt,
+    USInt,
+    Byte,
+    Int,
+    UInt,
+    Word,
+    DInt,
+    UDInt,
+    DWord,
+    LInt,
+    ULInt,
+    LWord,
+    Real,
+    LReal,
+    /// Milliseconds.
+    Time,
+    /// Nanoseconds.
+    LTime,
+    /// Seconds since 1970, at midnight.
+    Date,
+    /// Seconds since 1970.
+    DateAndTime,
+    /// Milliseconds since midnight.
+    TimeOfDay,
+    /// A latin-1 string of at most this many characters, followed by a null terminator.
+    String(usize),
+}
+
+impl PlcType {
+    /// Parses a type name as the PLC reports it, e.g: `INT`, `DWORD`, `DT` or `STRING(80)`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_uppercase();
+
+        let plc_type = match name.as_str() {
+            "BOOL" | "BIT" => PlcType::Bool,
+            "SINT" => PlcType::SInt,
+            "USINT" => PlcType::USInt,
+            "BYTE" => PlcType::Byte,
+            "INT" => PlcType::Int,
+            "UINT" => PlcType::UInt,
+            "WORD" => PlcType::Word,
+            "DINT" => PlcType::DInt,
+            "UDINT" => PlcType::UDInt,
+            "DWORD" => PlcType::DWord,
+            "LINT" => PlcType::LInt,
+            "ULINT" => PlcType::ULInt,
+            "LWORD" => PlcType::LWord,
+            "REAL" => PlcType::Real,
+            "LREAL" => PlcType::LReal,
+            "TIME" => PlcType::Time,
+            "LTIME" => PlcType::LTime,
+            "DATE" => PlcType::Date,
+            "DATE_AND_TIME" | "DT" => PlcType::DateAndTime,
+            "TIME_OF_DAY" | "TOD" => PlcType::TimeOfDay,
+            "STRING" => PlcType::String(DEFAULT_STRING_LENGTH),
+            _ => {
+                let length = name.strip_prefix("STRING")?.trim();
+                let length = length.strip_prefix(['(', '['])?.strip_suffix([')', ']'])?;
+
+                PlcType::String(length.trim().parse().ok()?)
+            }
+        };
+
+        Some(plc_type)
+    }
+
+    /// The size of a value on the PLC, in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            PlcType::Bool | PlcType::SInt | PlcType::USInt | PlcType::Byte => 1,
+            PlcType::Int | PlcType::UInt | PlcType::Word => 2,
+            PlcType::DInt
+            | PlcType::UDInt
+            | PlcType::DWord
+            | PlcType::Real
+            | PlcType::Time
+            | PlcType::Date
+            | PlcType::DateAndTime
+            | PlcType::TimeOfDay => 4,
+            PlcType::LInt | PlcType::ULInt | PlcType::LWord | PlcType::LReal | PlcType::LTime => 8,
+            PlcType::String(length) => length + 1,
+        }
+    }
+
+    /// Whether both are the same type, regardless of a string's length.
+    pub(crate) fn is_kind_of(self, other: PlcType) -> bool {
+        mem::discriminant(&self) == mem::discriminant(&other)
+    }
+
+    /// Decodes a value from its bytes on the PLC, which must be at least the type's size.
+    pub fn decode(self, data: &[u8]) -> PlcResult<PlcValue> {
+        let data = data
+            .get(..self.size())
+            .ok_or_else(|| PlcError::InvalidValue {
+                reason: format!(
+                    "expected {} bytes for a {self} but got {}",
+                    self.size(),
+                    data.len()
+                ),
+            })?;
+
+        macro_rules! number {
+            ($variant:ident, $type:ty) => {
+                PlcValue::$variant(<$type>::from_le_bytes(
+                    data.try_into().expect("the type's size"),
+                ))
+            };
+        }
+
+        let u32_value = || u32::from_le_bytes(data.try_into().expect("four bytes"));
+
+        let invalid = || PlcError::InvalidValue {
+            reason: format!("{data:02x?} is not a valid {self}"),
+        };
+
+        let value = match self {
+            PlcType::Bool => PlcValue::Bool(data[0] != 0),
+            PlcType::SInt => number!(SInt, i8),
+            PlcType::USInt => number!(USInt, u8),
+            PlcType::Byte => number!(Byte, u8),
+            PlcType::Int => number!(Int, i16),
+            PlcType::UInt => number!(UInt, u16),
+            PlcType::Word => number!(Word, u16),
+            PlcType::DInt => number!(DInt, i32),
+            PlcType::UDInt => number!(UDInt, u32),
+            PlcType::DWord => number!(DWord, u32),
+            PlcType::LInt => number!(LInt, i64),
+            PlcType::ULInt => number!(ULInt, u64),
+            PlcType::LWord => number!(LWord, u64),
+            PlcType::Real => number!(Real, f32),
+            PlcType::LReal => number!(LReal, f64),
+            PlcType::Time => PlcValue::Time(Duration::from_millis(u32_value().into())),
+            PlcType::LTime => PlcValue::LTime(Duration::from_nanos(u64::from_le_bytes(
+                data.try_into().expect("eight bytes"),
+            ))),
+            PlcType::Date => PlcValue::Date(
+                DateTime::from_timestamp(u32_value().into(), 0)
+                    .ok_or_else(invalid)?
+                    .date_naive(),
+            ),
+            PlcType::DateAndTime => PlcValue::DateAndTime(
+                DateTime::from_timestamp(u32_value().into(), 0)
+                    .ok_or_else(invalid)?
+                    .naive_utc(),
+            ),
+            PlcType::TimeOfDay => {
+                let milliseconds = u32_value();
+
+                PlcValue::TimeOfDay(
+                    NaiveTime::from_num_seconds_from_midnight_opt(
+                        milliseconds / 1000,
+                        milliseconds % 1000 * 1_000_000,
+                    )
+                    .filter(|_| milliseconds < MILLISECONDS_PER_DAY)
+                    .ok_or_else(invalid)?,
+                )
+            }
+            PlcType::String(_) => PlcValue::String(
+                data.iter()
+                    .take_while(|&&byte| byte != 0)
+                    .map(|&byte| byte as char)
+                    .collect(),
+            ),
+        };
+
+        Ok(value)
+    }
+
+    /// Encodes a value of this type to its bytes on the PLC.
+    pub fn encode(self, value: &PlcValue) -> PlcResult<Vec<u8>> {
+        let invalid = |reason: &str| PlcError::InvalidValue {
+            reason: format!("{value} {reason}"),
+        };
+
+        if !self.is_kind_of(value.plc_type()) {
+            return Err(invalid(&format!("is not a {self}")));
+        }
+
+        let data = match value {
+            PlcValue::Bool(value) => vec![u8::from(*value)],
+            PlcValue::SInt(value) => value.to_le_bytes().to_vec(),
+            PlcValue::USInt(value) | PlcValue::Byte(value) => vec![*value],
+            PlcValue::Int(value) => value.to_le_bytes().to_vec(),
+            PlcValue::UInt(value) | PlcValue::Word(value) => value.to_le_bytes().to_vec(),
+            PlcValue::DInt(value) => value.to_le_bytes().to_vec(),
+            PlcValue::UDInt(value) | PlcValue::DWord(value) => value.to_le_bytes().to_vec(),
+            PlcValue::LInt(value) => value.to_le_bytes().to_vec(),
+            PlcValue::ULInt(value) | PlcValue::LWord(value) => value.to_le_bytes().to_vec(),
+            PlcValue::Real(value) => value.to_le_bytes().to_vec(),
+            PlcValue::LReal(value) => value.to_le_bytes().to_vec(),
+            PlcValue::Time(duration) => {
+                if duration.subsec_nanos() % 1_000_000 != 0 {
+                    return Err(invalid(
+                        "is finer than the millisecond resolution of a TIME",
+                    ));
+                }
+
+                u32::try_from(duration.as_millis())
+                    .map_err(|_| invalid("is too long for a TIME"))?
+                    .to_le_bytes()
+                    .to_vec()
+            }
+            PlcValue::LTime(duration) => u64::try_from(duration.as_nanos())
+                .map_err(|_| invalid("is too long for an LTIME"))?
+                .to_le_bytes()
+                .to_vec(),
+            PlcValue::Date(date) => {
+                u32::try_from(date.and_time(NaiveTime::MIN).and_utc().timestamp())
+                    .map_err(|_| invalid("is out of the range of a DATE"))?
+                    .to_le_bytes()
+                    .to_vec()
+            }
+            PlcValue::DateAndTime(date_time) => {
+                if date_time.nanosecond() != 0 {
+                    return Err(invalid("is finer than the second resolution of a DT"));
+                }
+
+                u32::try_from(date_time.and_utc().timestamp())
+                    .map_err(|_| invalid("is out of the range of a DT"))?
+                    .to_le_bytes()
+                    .to_vec()
+            }
+            PlcValue::TimeOfDay(time) => {
+                if time.nanosecond() % 1_000_000 != 0 {
+                    return Err(invalid("is finer than the millisecond resolution of a TOD"));
+                }
+
+                (time.num_seconds_from_midnight() * 1000 + time.nanosecond() / 1_000_000)
+                    .to_le_bytes()
+                    .to_vec()
+            }
+            PlcValue::String(text) => {
+                let PlcType::String(length) = self else {
+                    unreachable!("a string type");
+                };
+
+                let mut data = text
+                    .chars()
+                    .map(|character| u8::try_from(character).ok())
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(|| invalid("has characters outside latin-1"))?;
+
+                if data.len() > length {
+                    return Err(invalid(&format!(
+                        "is longer than the {length} characters of a {self}"
+                    )));
+                }
+
+                // Pads with nulls up to the declared length, including the terminator
+                data.resize(length + 1, 0);
+
+                data
+            }
+        };
+
+        Ok(data)
+    }
+}
+
+impl fmt::Display for PlcType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PlcType::Bool => "BOOL",
+            PlcType::SInt => "SINT",
+            PlcType::USInt => "USINT",
+            PlcType::Byte => "BYTE",
+            PlcType::Int => "INT",
+            PlcType::UInt => "UINT",
+            PlcType::Word => "WORD",
+            PlcType::DInt => "DINT",
+            PlcType::UDInt => "UDINT",
+            PlcType::DWord => "DWORD",
+            PlcType::LInt => "LINT",
+            PlcType::ULInt => "ULINT",
+            PlcType::LWord => "LWORD",
+            PlcType::Real => "REAL",
+            PlcType::LReal => "LREAL",
+            PlcType::Time => "TIME",
+            PlcType::LTime => "LTIME",
+            PlcType::Date => "DATE",
+            PlcType::DateAndTime => "DT",
+            PlcType::TimeOfDay => "TOD",
+            PlcType::String(length) => return write!(f, "STRING({length})"),
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A value of one of the PLC's elementary data types.
+///
+/// Displays as an IEC 61131-3 literal, e.g: `TRUE`, `16#00FF`, `T#1h2m` or `'text'`, which
+/// [`PlcType::parse_literal`] reads back. [`PlcValue::to_typed_literal`] adds the type where the literal alone does
+/// not give it, e.g: `INT#5`, which parses back with [`str::parse`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlcValue {
+    Bool(bool),
+    SInt(i8),
+    USInt(u8),
+    Byte(u8),
+    Int(i16),
+    UInt(u16),
+    Word(u16),
+    DInt(i32),
+    UDInt(u32),
+    DWord(u32),
+    LInt(i64),
+    ULInt(u64),
+    LWord(u64),
+    Real(f32),
+    LReal(f64),
+    Time(Duration),
+    LTime(Duration),
+    Date(NaiveDate),
+    DateAndTime(NaiveDateTime),
+    TimeOfDay(NaiveTime),
+    String(String),
+}
+
+impl PlcValue {
+    /// The value's type. Strings are given TwinCAT's default length, or their own if longer.
+    pub fn plc_type(&self) -> PlcType {
+        match self {
+            PlcValue::Bool(_) => PlcType::Bool,
+            PlcValue::SInt(_) => PlcType::SInt,
+            PlcValue::USInt(_) => PlcType::USInt,
+            PlcValue::Byte(_) => PlcType::Byte,
+            PlcValue::Int(_) => PlcType::Int,
+            PlcValue::UInt(_) => PlcType::UInt,
+            PlcValue::Word(_) => PlcType::Word,
+            PlcValue::DInt(_) => PlcType::DInt,
+            PlcValue::UDInt(_) => PlcType::UDInt,
+            PlcValue::DWord(_) => PlcType::DWord,
+            PlcValue::LInt(_) => PlcType::LInt,
+            PlcValue::ULInt(_) => PlcType::ULInt,
+            PlcValue::LWord(_) => PlcType::LWord,
+            PlcValue::Real(_) => PlcType::Real,
+            PlcValue::LReal(_) => PlcType::LReal,
+            PlcValue::Time(_) => PlcType::Time,
+            PlcValue::LTime(_) => PlcType::LTime,
+            PlcValue::Date(_) => PlcType::Date,
+            PlcValue::DateAndTime(_) => PlcType::DateAndTime,
+            PlcValue::TimeOfDay(_) => PlcType::TimeOfDay,
+            PlcValue::String(text) => {
+                PlcType::String(text.chars().count().max(DEFAULT_STRING_LENGTH))
+            }
+        }
+    }
+}
+
+/// Converts primitives to and from a value of their type.
+macro_rules! primitive_conversions {
+    ($($primitive:ty => $variant:ident),* $(,)?) => {$(
+        impl From<$primitive> for PlcValue {
+            fn from(value: $primitive) -> Self {
+                PlcValue::$variant(value.into())
+            }
+        }
+
+        impl TryFrom<PlcValue> for $primitive {
+            type Error = PlcError;
+
+            fn try_from(value: PlcValue) -> PlcResult<Self> {
+                match value {
+                    PlcValue::$variant(value) => Ok(value.into()),
+                    value => Err(not_a(&value, PlcType::$variant)),
+                }
+            }
+        }
+    )*};
+}
+
+primitive_conversions!(
+    PlcDInt => DInt,
+    PlcInt => Int,
+    PlcLReal => LReal,
+    PlcReal => Real,
+    PlcUDInt => UDInt,
+    PlcUInt => UInt,
+    PlcWord => Word,
+);
+
+impl From<PlcBool> for PlcValue {
+    fn from(value: PlcBool) -> Self {
+        // NB: Any value other than 0 is true on the PLC
+        PlcValue::Bool(value.as_bytes()[0] != 0)
+    }
+}
+
+impl TryFrom<PlcValue> for PlcBool {
+    type Error = PlcError;
+
+    fn try_from(value: PlcValue) -> PlcResult<Self> {
+        match value {
+            PlcValue::Bool(value) => Ok(value.into()),
+            value => Err(not_a(&value, PlcType::Bool)),
+        }
+    }
+}
+
+impl From<PlcTime> for PlcValue {
+    fn from(value: PlcTime) -> Self {
+        PlcValue::Time(Duration::from_millis(u32::from(value).into()))
+    }
+}
+
+impl TryFrom<PlcValue> for PlcTime {
+    type Error = PlcError;
+
+    fn try_from(value: PlcValue) -> PlcResult<Self> {
+        let data = PlcType::Time.encode(&value)?;
+
+        Ok(u32::from_le_bytes(data.try_into().expect("4 bytes")).into())
+    }
+}
+
+impl From<PlcString16> for PlcValue {
+    fn from(value: PlcString16) -> Self {
+        PlcValue::String(value.into())
+    }
+}
+
+impl TryFrom<PlcValue> for PlcString16 {
+    type Error = PlcError;
+
+    fn try_from(value: PlcValue) -> PlcResult<Self> {
+        // NB: encoded as latin-1, as the PLC stores it, rather than the UTF-8 `TryFrom<String>` copies
+        let data = PlcType::String(16).encode(&value)?;
+
+        Ok(PlcString16::from_bytes(&data).expect("17 bytes"))
+    }
+}
+
+impl TryFrom<PlcValue> for PlcTimeStruct {
+    type Error = PlcError;
+
+    fn try_from(value: PlcValue) -> PlcResult<Self> {
+        match value {
+            PlcValue::DateAndTime(date_time) if date_time.nanosecond() % 1_000_000 == 0 => {
+                Ok(date_time.into())
+            }
+            PlcValue::DateAndTime(_) => Err(PlcError::InvalidValue {
+                reason: format!("{value} is finer than the milliseconds of a time struct"),
+            }),
+            value => Err(not_a(&value, PlcType::DateAndTime)),
+        }
+    }
+}
+
+fn not_a(value: &PlcValue, plc_type: PlcType) -> PlcError {
+    PlcError::InvalidValue {
+        reason: format!("{} is not a {plc_type}", value.to_typed_literal()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_names_parse_as_reported_by_the_plc() {
+        assert_eq!(PlcType::from_name("INT"), Some(PlcType::Int));
+        assert_eq!(PlcType::from_name("dword"), Some(PlcType::DWord));
+        assert_eq!(
+            PlcType::from_name("DATE_AND_TIME"),
+            Some(PlcType::DateAndTime)
+        );
+        assert_eq!(PlcType::from_name("STRING"), Some(PlcType::String(80)));
+        assert_eq!(PlcType::from_name("STRING(20)"), Some(PlcType::String(20)));
+        assert_eq!(PlcType::from_name("STRING[20]"), Some(PlcType::String(20)));
+        assert_eq!(PlcType::from_name("ST_Status"), None);
+        assert_eq!(PlcType::from_name("STRING(x)"), None);
+    }
+
+    #[test]
+    fn values_round_trip_through_their_bytes() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        for (plc_type, value) in [
+            (PlcType::Bool, PlcValue::Bool(true)),
+            (PlcType::SInt, PlcValue::SInt(-5)),
+            (PlcType::Word, PlcValue::Word(0xff00)),
+            (PlcType::UDInt, PlcValue::UDInt(4_000_000_000)),
+            (PlcType::LInt, PlcValue::LInt(-9_000_000_000)),
+            (PlcType::Real, PlcValue::Real(1.5)),
+            (PlcType::LReal, PlcValue::LReal(0.1)),
+            (
+                PlcType::Time,
+                PlcValue::Time(Duration::from_millis(3_723_004)),
+            ),
+            (
+                PlcType::LTime,
+                PlcValue::LTime(Duration::from_nanos(1_000_000_001)),
+            ),
+            (PlcType::Date, PlcValue::Date(date)),
+            (
+                PlcType::DateAndTime,
+                PlcValue::DateAndTime(date.and_hms_opt(12, 30, 5).unwrap()),
+            ),
+            (
+                PlcType::TimeOfDay,
+                PlcValue::TimeOfDay(NaiveTime::from_hms_milli_opt(23, 59, 59, 999).unwrap()),
+            ),
+            (PlcType::String(10), PlcValue::String("Running".to_string())),
+        ] {
+            let data = plc_type.encode(&value).unwrap();
+
+            assert_eq!(data.len(), plc_type.size());
+            assert_eq!(plc_type.decode(&data).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn values_that_do_not_fit_are_rejected() {
+        assert!(PlcType::Int.encode(&PlcValue::Real(1.5)).is_err());
+        assert!(PlcType::Time
+            .encode(&PlcValue::Time(Duration::from_micros(1500)))
+            .is_err());
+        assert!(PlcType::Time
+            .encode(&PlcValue::Time(Duration::from_secs(50 * 86_400)))
+            .is_err());
+        assert!(PlcType::String(3)
+            .encode(&PlcValue::String("Running".to_string()))
+            .is_err());
+        assert!(PlcType::String(10)
+            .encode(&PlcValue::String("日本".to_string()))
+            .is_err());
+        assert!(PlcType::DInt.decode(&[0, 0]).is_err());
+        assert!(PlcType::TimeOfDay
+            .decode(&MILLISECONDS_PER_DAY.to_le_bytes())
+            .is_err());
+    }
+}
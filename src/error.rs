@@ -0,0 +1,247 @@
+use crate::return_code::{ReturnCode, ReturnCodeClass};
+
+/// Result alias for PLC operations.
+pub type PlcResult<T> = std::result::Result<T, PlcError>;
+
+/// The ways talking to the PLC can fail.
+#[derive(Debug, thiserror::Error)]
+pub enum PlcError {
+    #[error("PLC is not connected.")]
+    NotConnected,
+
+    #[error("PLC is not in run mode.")]
+    NotInRunMode,
+
+    #[error("Symbol {symbol} not found: {source}")]
+    SymbolNotFound {
+        symbol: String,
+        #[source]
+        source: ads::Error,
+    },
+
+    #[error(
+        "Size of symbol {symbol} does not match the expected {expected} bytes{}",
+        size_mismatch_details(*actual, source.as_ref())
+    )]
+    SizeMismatch {
+        symbol: String,
+        expected: usize,
+        /// The symbol's size on the PLC, if known, e.g: from resolving the symbol.
+        actual: Option<usize>,
+        /// The PLC's error, if it rejected a request rather than the size being checked up front.
+        #[source]
+        source: Option<ads::Error>,
+    },
+
+    #[error("Timed out waiting for the PLC.")]
+    Timeout,
+
+    #[error("ADS device error {code:#x} ({name}): {source}")]
+    AdsDeviceError {
+        code: u32,
+        /// Symbolic name from the return code catalogue, or UNKNOWN.
+        name: &'static str,
+        #[source]
+        source: ads::Error,
+    },
+
+    #[error("Connection to the PLC was lost: {source}")]
+    TransportLost {
+        #[source]
+        source: ads::Error,
+    },
+
+    #[error("Unexpected reply from the PLC: {source}")]
+    InvalidReply {
+        #[source]
+        source: ads::Error,
+    },
+
+    #[error("Invalid value: {reason}")]
+    InvalidValue { reason: String },
+}
+
+impl PlcError {
+    /// Converts an error from an operation on a symbol, adding the symbol's name.
+    pub(crate) fn for_symbol(symbol: &str, error: ads::Error) -> Self {
+        match error {
+            ads::Error::Ads(_, _, 0x710) => PlcError::SymbolNotFound {
+                symbol: symbol.to_string(),
+                source: error,
+            },
+            error => error.into(),
+        }
+    }
+
+    /// Converts an error from reading or writing a symbol's data, adding the symbol's name and expected size.
+    pub(crate) fn for_symbol_data(symbol: &str, expected: usize, error: ads::Error) -> Self {
+        match error {
+            ads::Error::Ads(_, _, 0x705)
+            | ads::Error::Reply(_, "got less data than expected", _) => PlcError::SizeMismatch {
+                symbol: symbol.to_string(),
+                expected,
+                actual: None,
+                source: Some(error),
+            },
+            error => Self::for_symbol(symbol, error),
+        }
+    }
+
+    /// The catalogued ADS return code the device replied with, if any.
+    pub fn return_code(&self) -> Option<&'static ReturnCode> {
+        match self {
+            PlcError::SymbolNotFound { source, .. }
+            | PlcError::SizeMismatch {
+                source: Some(source),
+                ..
+            }
+            | PlcError::AdsDeviceError { source, .. } => match source {
+                ads::Error::Ads(_, _, code) => ReturnCode::lookup(*code),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the error means the connection can no longer be used and should be dropped.
+    pub(crate) fn should_disconnect(&self) -> bool {
+        match self {
+            PlcError::TransportLost { .. } => true,
+            PlcError::AdsDeviceError { code, .. } => {
+                ReturnCode::classify(*code) == ReturnCodeClass::Disconnect
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<ads::Error> for PlcError {
+    fn from(error: ads::Error) -> Self {
+        match error {
+            ads::Error::Io(_, _) | ads::Error::Reply(_, "unexpected invoke ID", _) => {
+                PlcError::TransportLost { source: error }
+            }
+            ads::Error::Ads(_, _, code) => PlcError::AdsDeviceError {
+                code,
+                name: ReturnCode::lookup(code).map_or("UNKNOWN", |return_code| return_code.name),
+                source: error,
+            },
+            ads::Error::Reply(_, _, _) => PlcError::InvalidReply { source: error },
+            ads::Error::Overflow(_) => PlcError::InvalidValue {
+                reason: error.to_string(),
+            },
+        }
+    }
+}
+
+/// The end of a size mismatch's message, with the symbol's size on the PLC and the PLC's error where there are any.
+fn size_mismatch_details(actual: Option<usize>, source: Option<&ads::Error>) -> String {
+    let actual = actual.map_or(String::new(), |actual| {
+        format!(", it is {actual} bytes on the PLC")
+    });
+    let source = source.map_or(String::new(), |source| format!(": {source}"));
+
+    format!("{actual}{source}")
+}
+
+/// Opt-in to treating a disconnected PLC as an absent value rather than an error.
+pub trait PlcResultExt<T> {
+    /// Converts NotConnected errors to Ok(None).
+    fn disconnected_as_none(self) -> PlcResult<Option<T>>;
+}
+
+impl<T> PlcResultExt<T> for PlcResult<T> {
+    fn disconnected_as_none(self) -> PlcResult<Option<T>> {
+        match self {
+            Ok(value) => Ok(Some(value)),
+            Err(PlcError::NotConnected) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn io_errors_lose_the_transport() {
+        let error = PlcError::from(ads::Error::Io(
+            "receiving reply (route set?)",
+            ErrorKind::TimedOut.into(),
+        ));
+
+        assert!(matches!(error, PlcError::TransportLost { .. }));
+        assert!(error.should_disconnect());
+    }
+
+    #[test]
+    fn symbol_errors_keep_the_symbol() {
+        let error = PlcError::for_symbol(
+            "MAIN.nCounter",
+            ads::Error::Ads("write and read data", "Symbol not found", 0x710),
+        );
+
+        assert!(matches!(
+            error,
+            PlcError::SymbolNotFound { ref symbol, .. } if symbol == "MAIN.nCounter"
+        ));
+        assert!(!error.should_disconnect());
+        assert_eq!(
+            error.return_code().map(|return_code| return_code.name),
+            Some("ADSERR_DEVICE_SYMBOLNOTFOUND")
+        );
+    }
+
+    #[test]
+    fn device_errors_disconnect_by_class() {
+        let lost = PlcError::from(ads::Error::Ads(
+            "get state",
+            "Target port not found, possibly ADS server not started",
+            0x006,
+        ));
+        let busy = PlcError::from(ads::Error::Ads("read data", "Device is busy", 0x708));
+
+        assert!(matches!(
+            lost,
+            PlcError::AdsDeviceError {
+                name: "ERR_TARGETPORTNOTFOUND",
+                ..
+            }
+        ));
+        assert!(lost.should_disconnect());
+        assert!(!busy.should_disconnect());
+    }
+
+    #[test]
+    fn short_reads_are_size_mismatches() {
+        let error = PlcError::for_symbol_data(
+            "MAIN.fTemperature",
+            4,
+            ads::Error::Reply("read data", "got less data than expected", 2),
+        );
+
+        assert!(matches!(
+            error,
+            PlcError::SizeMismatch {
+                expected: 4,
+                actual: None,
+                source: Some(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn not_connected_can_be_none() {
+        let result: PlcResult<u32> = Err(PlcError::NotConnected);
+
+        assert!(matches!(result.disconnected_as_none(), Ok(None)));
+
+        let result: PlcResult<u32> = Err(PlcError::Timeout);
+
+        assert!(result.disconnected_as_none().is_err());
+    }
+}
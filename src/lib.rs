@@ -1,3 +1,24 @@
+#[cfg(feature = "tokio")]
+pub mod async_connection;
+pub mod capture;
+pub mod coalescing;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+pub mod command_handshake;
 pub mod data_types;
+pub mod error;
+#[cfg(feature = "default-subscriber")]
+pub mod logging;
+pub mod plc_backend;
 pub mod plc_client;
 pub mod plc_connection;
+pub mod plc_interface;
+pub mod recording;
+pub mod return_code;
+pub mod scope;
+pub mod subscription;
+pub mod symbol;
+pub mod symbol_mirror;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
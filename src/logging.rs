@@ -0,0 +1,15 @@
+//! A default tracing subscriber for simple binaries.
+
+use tracing_subscriber::EnvFilter;
+
+/// Installs a subscriber that prints to stdout, filtered by RUST_LOG and defaulting to the info level.
+///
+/// Does nothing if a global subscriber is already installed.
+pub fn install_default_subscriber() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .try_init()
+        .ok();
+}
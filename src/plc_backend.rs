@@ -0,0 +1,115 @@
+//! The operations applications use to talk to a PLC, so business logic can be unit tested against a fake PLC.
+
+use ads::AdsState;
+
+use crate::{
+    data_types::PlcDataType,
+    error::PlcResult,
+    plc_connection::PlcConnection,
+    subscription::{DeliveryPolicy, Subscription, SubscriptionOptions},
+};
+
+/// A PLC that symbols can be read from and written to, RPC methods called on and notifications subscribed to.
+///
+/// [`PlcConnection`] talks to a real PLC over ADS. With the `testing` feature, `testing::FakePlc` keeps symbols in
+/// memory and records every call, so code written against `impl PlcBackend` can be tested without a network.
+///
+/// ```
+/// use ads_client::{
+///     data_types::primitives::{bool::PlcBool, real::PlcReal},
+///     error::PlcResult,
+///     plc_backend::PlcBackend,
+/// };
+///
+/// fn stop_if_too_fast(plc: &impl PlcBackend) -> PlcResult<()> {
+///     let speed: PlcReal = plc.read_symbol("MAIN.fSpeed")?;
+///
+///     if f32::from(speed) > 2.0 {
+///         plc.write_symbol("MAIN.bStop", PlcBool::from(true))?;
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub trait PlcBackend {
+    fn is_connected(&self) -> bool;
+
+    /// The PLC's ADS state, e.g: to check it is in run mode.
+    fn ads_state(&self) -> PlcResult<AdsState>;
+
+    fn read_symbol<T: PlcDataType>(&self, name: &str) -> PlcResult<T>;
+
+    fn write_symbol<T: PlcDataType>(&self, name: &str, value: T) -> PlcResult<()>;
+
+    /// Calls an RPC method with the parameters laid out one after the other, returning its result.
+    fn call_rpc_method<R: PlcDataType>(&self, name: &str, params: &[&[u8]]) -> PlcResult<R>;
+
+    /// Calls an RPC method with the parameters laid out one after the other, ignoring any result.
+    fn invoke_rpc_method_with_params(&self, name: &str, params: &[&[u8]]) -> PlcResult<()>;
+
+    /// Subscribes to notifications from a symbol, filtered and delivered to the returned subscription according to
+    /// the options.
+    fn subscribe_with_options<T: PlcDataType>(
+        &self,
+        name: &str,
+        options: SubscriptionOptions<T>,
+    ) -> PlcResult<Subscription<T>>;
+
+    fn is_run_mode(&self) -> PlcResult<bool> {
+        Ok(self.ads_state()? == AdsState::Run)
+    }
+
+    fn invoke_rpc_method(&self, name: &str) -> PlcResult<()> {
+        self.invoke_rpc_method_with_params(name, &[])
+    }
+
+    fn subscribe_with_policy<T: PlcDataType>(
+        &self,
+        name: &str,
+        delivery_policy: DeliveryPolicy,
+    ) -> PlcResult<Subscription<T>> {
+        self.subscribe_with_options(
+            name,
+            SubscriptionOptions::new().with_delivery_policy(delivery_policy),
+        )
+    }
+}
+
+// NB: inherent methods take precedence, so these forward to PlcConnection's own methods
+impl PlcBackend for PlcConnection {
+    fn is_connected(&self) -> bool {
+        self.is_connected()
+    }
+
+    fn ads_state(&self) -> PlcResult<AdsState> {
+        self.ads_state()
+    }
+
+    fn read_symbol<T: PlcDataType>(&self, name: &str) -> PlcResult<T> {
+        self.read_symbol(name)
+    }
+
+    fn write_symbol<T: PlcDataType>(&self, name: &str, value: T) -> PlcResult<()> {
+        self.write_symbol(name, value)
+    }
+
+    fn call_rpc_method<R: PlcDataType>(&self, name: &str, params: &[&[u8]]) -> PlcResult<R> {
+        self.call_rpc_method(name, params)
+    }
+
+    fn invoke_rpc_method_with_params(&self, name: &str, params: &[&[u8]]) -> PlcResult<()> {
+        self.invoke_rpc_method_with_params(name, params)
+    }
+
+    fn subscribe_with_options<T: PlcDataType>(
+        &self,
+        name: &str,
+        options: SubscriptionOptions<T>,
+    ) -> PlcResult<Subscription<T>> {
+        self.subscribe_with_options(name, options)
+    }
+
+    fn invoke_rpc_method(&self, name: &str) -> PlcResult<()> {
+        self.invoke_rpc_method(name)
+    }
+}
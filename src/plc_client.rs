@@ -1,146 +1,523 @@
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
-use ads::{AmsAddr, Client, Device, Handle, Result};
+use ads::AmsAddr;
 use crossbeam_channel::Receiver;
 
-use crate::data_types::PlcDataType;
+use crate::{
+    coalescing::{self, Coalescer, Pending, ReadBatch, ReadRequest, WriteBatch, WriteRequest},
+    data_types::PlcDataType,
+    error::{PlcError, PlcResult},
+    subscription::{NotificationRouter, SampleQueue},
+    transport::{self, AmsTransport, Device},
+};
 
+// Sum commands rejected with these codes are not supported by the PLC, so the batch is sent as individual requests
+const SUM_COMMAND_UNSUPPORTED: [u32; 2] = [0x701, 0x702];
+
+// The read length other ADS clients use for symbol entries, which fits any realistic name, type and comment
+const SYMBOL_ENTRY_MAX_LENGTH: usize = 0xFFFF;
+
+/// A connected PLC. Requests can be made from several threads at once, and are in flight on the connection together.
 pub struct PlcClient {
-    safe_cell: PlcClientSelfCell,
-    notification_handles: Vec<u32>,
+    transport: AmsTransport,
+    plc_ams_address: AmsAddr,
+    handles: Mutex<HashMap<String, u32>>,
+    notification_handles: Mutex<Vec<u32>>,
+    notification_router: Arc<NotificationRouter>,
+    coalescer: Option<Coalescer>,
 }
 
-// Using self_cell here so we can create a struct that owns an ads Client, Device, and set of Handles. It would not be possible
-// otherwise as the Device and Handles must refer to the Client, and Rust does not make it easy to create self-referrential structs
-// TODO: long term we should investigate the ads crate and find a better solution without self_cell
-self_cell::self_cell!(
-    struct PlcClientSelfCell {
-        owner: Client,
+impl PlcClient {
+    pub(crate) fn new(
+        transport: AmsTransport,
+        plc_ams_address: AmsAddr,
+        coalescer: Option<Coalescer>,
+    ) -> Self {
+        let notification_router = NotificationRouter::start(transport.notification_receiver());
+
+        Self {
+            transport,
+            plc_ams_address,
+            handles: Default::default(),
+            notification_handles: Default::default(),
+            notification_router,
+            coalescer,
+        }
+    }
 
-        #[covariant]
-        dependent: PlcDevice,
+    fn device(&self) -> Device<'_> {
+        self.transport.device(self.plc_ams_address)
     }
-);
 
-struct PlcDevice<'c> {
-    device: Device<'c>,
-    handles: HashMap<String, Handle<'c>>,
-}
+    /// Whether the connection to the PLC was closed, in which case every request fails.
+    pub fn is_closed(&self) -> bool {
+        self.transport.is_closed()
+    }
 
-impl<'c> PlcDevice<'c> {
-    fn handle(&mut self, name: &str) -> Result<&Handle<'c>> {
+    fn handle(&self, name: &str) -> PlcResult<u32> {
         // TODO: might need to think a bit more about other cases we may need to invalidate these handles e.g: new code flashed onto the PLC
-        let handle = match self.handles.entry(name.to_string()) {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => entry.insert(Handle::new(self.device, name)?),
-        };
+        if let Some(handle) = self.handles.lock().unwrap().get(name) {
+            return Ok(*handle);
+        }
+
+        // NB: the lock is not held over the request, so two threads may both fetch a handle for the same symbol
+        let mut handle_bytes = [0; 4];
+
+        self.device()
+            .write_read_exact(
+                ads::index::GET_SYMHANDLE_BYNAME,
+                0,
+                name.as_bytes(),
+                &mut handle_bytes,
+            )
+            .map_err(|error| PlcError::for_symbol(name, error))?;
+
+        let fetched = u32::from_le_bytes(handle_bytes);
+
+        // NB: the handle another thread cached first may already be in use, so the one just fetched is released instead
+        let handle = *self
+            .handles
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert(fetched);
+
+        if handle != fetched {
+            self.release_handle(fetched);
+        }
 
         Ok(handle)
     }
-}
 
-impl PlcClient {
-    pub fn new(ads_client: Client, plc_ams_address: AmsAddr) -> Self {
-        let safe_cell = PlcClientSelfCell::new(ads_client, |ads_client| PlcDevice {
-            device: ads_client.device(plc_ams_address),
-            handles: HashMap::default(),
-        });
-
-        Self {
-            safe_cell,
-            notification_handles: Default::default(),
+    /// Fetches a symbol's handle, after checking its size on the PLC matches the expected size.
+    pub fn resolve_symbol(&self, name: &str, expected: usize) -> PlcResult<u32> {
+        let mut symbol_info = [0; 12];
+
+        self.device()
+            .write_read_exact(
+                ads::index::GET_SYMINFO_BYNAME,
+                0,
+                name.as_bytes(),
+                &mut symbol_info,
+            )
+            .map_err(|error| PlcError::for_symbol(name, error))?;
+
+        // The symbol's index group and offset come first, followed by its size
+        let size = u32::from_le_bytes(symbol_info[8..].try_into().expect("four bytes"));
+
+        if size as usize != expected {
+            return Err(PlcError::SizeMismatch {
+                symbol: name.to_string(),
+                expected,
+                actual: Some(size as usize),
+                source: None,
+            });
         }
+
+        self.handle(name)
     }
 
-    fn ads_client(&self) -> &Client {
-        self.safe_cell.borrow_owner()
+    /// Looks up a symbol's type and size, including members of structures and elements of arrays.
+    pub fn symbol_info(&self, name: &str) -> PlcResult<ads::symbol::Symbol> {
+        let mut entry = self
+            .device()
+            .write_read(
+                ads::index::GET_SYMINFO_BYNAME_EX,
+                0,
+                name.as_bytes(),
+                SYMBOL_ENTRY_MAX_LENGTH,
+            )
+            .map_err(|error| PlcError::for_symbol(name, error))?;
+
+        // NB: the entry starts with its own length, which the decoder trusts, so check it before decoding
+        let length = entry
+            .get(..4)
+            .map(|length| u32::from_le_bytes(length.try_into().expect("four bytes")) as usize)
+            .filter(|length| (4..=entry.len()).contains(length))
+            .ok_or(ads::Error::Reply(
+                "reading symbol info",
+                "invalid symbol entry length",
+                entry.len() as u32,
+            ))?;
+
+        entry.truncate(length);
+
+        let (symbols, _) = ads::symbol::decode_symbol_info(entry, Vec::new())?;
+
+        symbols
+            .into_iter()
+            .next()
+            .ok_or_else(|| ads::Error::Reply("reading symbol info", "no symbol entry", 0).into())
     }
 
-    fn device(&self) -> Device {
-        self.safe_cell.borrow_dependent().device
+    fn release_handle(&self, handle: u32) {
+        // NB: the PLC frees handles with the connection anyway, so failing to release one is not a problem
+        self.device()
+            .write(ads::index::RELEASE_SYMHANDLE, 0, &handle.to_le_bytes())
+            .ok();
     }
 
-    fn handle(&mut self, name: &str) -> Result<&Handle> {
-        self.safe_cell
-            .with_dependent_mut(|_, plc_device| plc_device.handle(name))
+    fn release_handles(&self) {
+        let handles = std::mem::take(&mut *self.handles.lock().unwrap());
+
+        if self.is_closed() {
+            return;
+        }
+
+        for handle in handles.into_values() {
+            self.release_handle(handle);
+        }
     }
 
-    pub fn is_run_mode(&self) -> Result<bool> {
+    pub fn ads_state(&self) -> PlcResult<ads::AdsState> {
         let (state, _) = self.device().get_state()?;
 
-        Ok(state == ads::AdsState::Run)
+        Ok(state)
     }
 
-    pub fn set_to_run_mode(&self) -> Result<()> {
+    /// The name and version of the PLC's runtime.
+    pub fn device_info(&self) -> PlcResult<ads::client::DeviceInfo> {
+        Ok(self.device().get_info()?)
+    }
+
+    /// Uploads the PLC's symbol table along with the data types it uses.
+    pub fn upload_symbol_info(
+        &self,
+    ) -> PlcResult<(Vec<ads::symbol::Symbol>, ads::symbol::TypeMap)> {
         let device = self.device();
 
-        println!("Device Info: {:?}", device.get_info());
+        // The symbol count and length come first, followed by the data type count and length
+        let mut upload_info = [0; 24];
+        device.read_exact(ads::index::SYM_UPLOAD_INFO2, 0, &mut upload_info)?;
+
+        let symbol_length = u32::from_le_bytes(upload_info[4..8].try_into().expect("four bytes"));
+        let type_length = u32::from_le_bytes(upload_info[12..16].try_into().expect("four bytes"));
+
+        let mut type_data = vec![0; type_length as usize];
+        device.read_exact(ads::index::SYM_DT_UPLOAD, 0, &mut type_data)?;
+
+        let mut symbol_data = vec![0; symbol_length as usize];
+        device.read_exact(ads::index::SYM_UPLOAD, 0, &mut symbol_data)?;
+
+        Ok(ads::symbol::decode_symbol_info(symbol_data, type_data)?)
+    }
+
+    pub fn is_run_mode(&self) -> PlcResult<bool> {
+        Ok(self.ads_state()? == ads::AdsState::Run)
+    }
+
+    pub fn set_to_run_mode(&self) -> PlcResult<()> {
+        let device = self.device();
+
+        tracing::debug!(info = ?device.get_info(), "Device info");
 
         let (state, dev_state) = device.get_state()?;
 
-        println!("Device State: {:?}", (state, dev_state));
+        tracing::debug!(?state, dev_state, "Device state");
 
         if state != ads::AdsState::Run {
-            println!("Attempting to set PLC to run mode...");
+            tracing::info!("Attempting to set PLC to run mode");
 
             device.write_control(ads::AdsState::Run, dev_state)?;
 
-            println!("Device State: {:?}", device.get_state());
+            tracing::debug!(state = ?device.get_state(), "Device state");
         }
 
-        assert!(device.get_state()?.0 == ads::AdsState::Run);
+        if device.get_state()?.0 != ads::AdsState::Run {
+            return Err(PlcError::NotInRunMode);
+        }
 
         Ok(())
     }
 
-    pub fn read_symbol<T: PlcDataType>(&mut self, name: &str) -> Result<T> {
-        let handle = self.handle(name)?;
+    pub fn read_symbol<T: PlcDataType>(&self, name: &str) -> PlcResult<T> {
+        let index_offset = self.handle(name)?;
 
         let mut read_data = T::default();
 
-        let index_offset = handle.raw();
-
-        self.device().read_exact(
-            ads::index::RW_SYMVAL_BYHANDLE,
-            index_offset,
-            read_data.as_bytes_mut(),
-        )?;
+        match &self.coalescer {
+            Some(coalescer) => {
+                let request = ReadRequest {
+                    symbol: name.to_string(),
+                    handle: index_offset,
+                    length: T::size(),
+                };
+
+                let data = coalescer.read(request, |batch| self.send_reads(batch))?;
+
+                read_data.as_bytes_mut().copy_from_slice(&data);
+            }
+            None => self
+                .device()
+                .read_exact(
+                    ads::index::RW_SYMVAL_BYHANDLE,
+                    index_offset,
+                    read_data.as_bytes_mut(),
+                )
+                .map_err(|error| PlcError::for_symbol_data(name, T::size(), error))?,
+        }
 
         Ok(read_data)
     }
 
-    pub fn invoke_rpc_method(&mut self, name: &str) -> Result<()> {
-        let handle = self.handle(name)?;
+    pub fn write_symbol<T: PlcDataType>(&self, name: &str, value: T) -> PlcResult<()> {
+        let index_offset = self.handle(name)?;
+
+        let write_data = value.as_bytes();
+
+        match &self.coalescer {
+            Some(coalescer) => {
+                let request = WriteRequest {
+                    symbol: name.to_string(),
+                    handle: index_offset,
+                    data: write_data.to_vec(),
+                };
+
+                coalescer.write(request, |batch| self.send_writes(batch))
+            }
+            None => self
+                .device()
+                .write(ads::index::RW_SYMVAL_BYHANDLE, index_offset, write_data)
+                .map_err(|error| PlcError::for_symbol_data(name, T::size(), error)),
+        }
+    }
+
+    /// Reads a symbol's bytes, for symbols whose type is only known at runtime.
+    pub fn read_symbol_raw(&self, name: &str, length: usize) -> PlcResult<Vec<u8>> {
+        let request = ReadRequest {
+            symbol: name.to_string(),
+            handle: self.handle(name)?,
+            length,
+        };
+
+        match &self.coalescer {
+            Some(coalescer) => coalescer.read(request, |batch| self.send_reads(batch)),
+            None => self.read_one(&request),
+        }
+    }
+
+    /// Writes a symbol's bytes, for symbols whose type is only known at runtime.
+    pub fn write_symbol_raw(&self, name: &str, data: &[u8]) -> PlcResult<()> {
+        let request = WriteRequest {
+            symbol: name.to_string(),
+            handle: self.handle(name)?,
+            data: data.to_vec(),
+        };
+
+        match &self.coalescer {
+            Some(coalescer) => coalescer.write(request, |batch| self.send_writes(batch)),
+            None => self.write_one(&request),
+        }
+    }
+
+    /// Reads several symbols with one sum command, given their names and sizes. Each read succeeds or fails on its own.
+    pub fn read_symbols_raw(&self, symbols: &[(&str, usize)]) -> Vec<PlcResult<Vec<u8>>> {
+        let mut batch = Vec::new();
+
+        let receivers: Vec<_> = symbols
+            .iter()
+            .map(|(name, length)| -> PlcResult<_> {
+                let request = ReadRequest {
+                    symbol: name.to_string(),
+                    handle: self.handle(name)?,
+                    length: *length,
+                };
+
+                let (pending, receiver) = Pending::new(request);
+
+                batch.push(pending);
+
+                Ok(receiver)
+            })
+            .collect();
+
+        let mut requests = batch.into_iter().peekable();
+
+        // NB: the PLC takes no more than so many sub-commands in one sum command
+        while requests.peek().is_some() {
+            self.send_reads(requests.by_ref().take(coalescing::MAX_BATCH_SIZE).collect());
+        }
+
+        receivers
+            .into_iter()
+            .map(|receiver| receiver?.recv().unwrap_or(Err(PlcError::NotConnected)))
+            .collect()
+    }
+
+    fn read_one(&self, request: &ReadRequest) -> PlcResult<Vec<u8>> {
+        let mut data = vec![0; request.length];
+
+        self.device()
+            .read_exact(ads::index::RW_SYMVAL_BYHANDLE, request.handle, &mut data)
+            .map_err(|error| PlcError::for_symbol_data(&request.symbol, request.length, error))?;
+
+        Ok(data)
+    }
+
+    fn write_one(&self, request: &WriteRequest) -> PlcResult<()> {
+        self.device()
+            .write(
+                ads::index::RW_SYMVAL_BYHANDLE,
+                request.handle,
+                &request.data,
+            )
+            .map_err(|error| PlcError::for_symbol_data(&request.symbol, request.data.len(), error))
+    }
+
+    /// Sends a batch of reads as one sum command, or as a plain read if there is only one.
+    fn send_reads(&self, batch: ReadBatch) {
+        if batch.len() == 1 {
+            for pending in batch {
+                let result = self.read_one(&pending.request);
+
+                pending.complete(result);
+            }
+
+            return;
+        }
+
+        let (request, reply_length) = coalescing::sum_read_request(&batch);
+
+        let mut reply = vec![0; reply_length];
+
+        let result = self.device().write_read_exact(
+            ads::index::SUMUP_READ,
+            batch.len() as u32,
+            &request,
+            &mut reply,
+        );
+
+        match result {
+            Ok(()) => {
+                let lengths = batch.iter().map(|pending| pending.request.length);
+                let results = coalescing::split_sum_read_reply(&reply, lengths);
+
+                for (pending, result) in batch.into_iter().zip(results) {
+                    let result = result.map(<[u8]>::to_vec).map_err(|code| {
+                        PlcError::for_symbol_data(
+                            &pending.request.symbol,
+                            pending.request.length,
+                            transport::ads_error("Sum read", code),
+                        )
+                    });
+
+                    pending.complete(result);
+                }
+            }
+            Err(ads::Error::Ads(_, _, code)) if SUM_COMMAND_UNSUPPORTED.contains(&code) => {
+                self.coalescer_fallback();
+
+                for pending in batch {
+                    let result = self.read_one(&pending.request);
+
+                    pending.complete(result);
+                }
+            }
+            Err(error) => {
+                for pending in batch {
+                    pending.complete(Err(coalescing::copy_ads_error(&error).into()));
+                }
+            }
+        }
+    }
 
-        let index_offset = handle.raw();
+    /// Sends a batch of writes as one sum command, or as a plain write if there is only one.
+    fn send_writes(&self, batch: WriteBatch) {
+        if batch.len() == 1 {
+            for pending in batch {
+                let result = self.write_one(&pending.request);
 
-        self.device().write_read_exact(
-            ads::index::RW_SYMVAL_BYHANDLE,
-            index_offset,
-            &[],
-            &mut [],
-        )?;
+                pending.complete(result);
+            }
+
+            return;
+        }
+
+        let request = coalescing::sum_write_request(&batch);
+
+        let mut reply = vec![0; batch.len() * 4];
+
+        let result = self.device().write_read_exact(
+            ads::index::SUMUP_WRITE,
+            batch.len() as u32,
+            &request,
+            &mut reply,
+        );
+
+        match result {
+            Ok(()) => {
+                let codes = coalescing::split_sum_write_reply(&reply, batch.len());
+
+                for (pending, code) in batch.into_iter().zip(codes) {
+                    let result = match code {
+                        0 => Ok(()),
+                        code => Err(PlcError::for_symbol_data(
+                            &pending.request.symbol,
+                            pending.request.data.len(),
+                            transport::ads_error("Sum write", code),
+                        )),
+                    };
+
+                    pending.complete(result);
+                }
+            }
+            Err(ads::Error::Ads(_, _, code)) if SUM_COMMAND_UNSUPPORTED.contains(&code) => {
+                self.coalescer_fallback();
+
+                for pending in batch {
+                    let result = self.write_one(&pending.request);
+
+                    pending.complete(result);
+                }
+            }
+            Err(error) => {
+                for pending in batch {
+                    pending.complete(Err(coalescing::copy_ads_error(&error).into()));
+                }
+            }
+        }
+    }
+
+    fn coalescer_fallback(&self) {
+        tracing::debug!(
+            "PLC does not support sum commands, sending the batch as individual requests"
+        );
+
+        if let Some(coalescer) = &self.coalescer {
+            coalescer.metrics().record_fallback();
+        }
+    }
+
+    pub fn invoke_rpc_method(&self, name: &str) -> PlcResult<()> {
+        let index_offset = self.handle(name)?;
+
+        self.device()
+            .write_read_exact(ads::index::RW_SYMVAL_BYHANDLE, index_offset, &[], &mut [])
+            .map_err(|error| PlcError::for_symbol(name, error))?;
 
         Ok(())
     }
 
     pub fn invoke_rpc_method_with_param<P: PlcDataType>(
-        &mut self,
+        &self,
         name: &str,
         param: P,
-    ) -> Result<()> {
-        let handle = self.handle(name)?;
+    ) -> PlcResult<()> {
+        let index_offset = self.handle(name)?;
 
         let write_data = param.as_bytes();
 
-        let index_offset = handle.raw();
-
-        self.device().write_read_exact(
-            ads::index::RW_SYMVAL_BYHANDLE,
-            index_offset,
-            write_data,
-            &mut [],
-        )?;
+        self.device()
+            .write_read_exact(
+                ads::index::RW_SYMVAL_BYHANDLE,
+                index_offset,
+                write_data,
+                &mut [],
+            )
+            .map_err(|error| PlcError::for_symbol_data(name, P::size(), error))?;
 
         Ok(())
     }
@@ -151,88 +528,216 @@ impl PlcClient {
         P2: PlcDataType,
         P3: PlcDataType,
     >(
-        &mut self,
+        &self,
         name: &str,
         param_1: P1,
         param_2: P2,
         param_3: P3,
-    ) -> Result<()> {
-        let handle = self.handle(name)?;
+    ) -> PlcResult<()> {
+        let index_offset = self.handle(name)?;
 
         let write_data_1 = param_1.as_bytes();
         let write_data_2 = param_2.as_bytes();
         let write_data_3 = param_3.as_bytes();
         let write_data = [write_data_1, write_data_2, write_data_3].concat();
 
-        let index_offset = handle.raw();
+        self.device()
+            .write_read_exact(
+                ads::index::RW_SYMVAL_BYHANDLE,
+                index_offset,
+                &write_data,
+                &mut [],
+            )
+            .map_err(|error| PlcError::for_symbol_data(name, write_data.len(), error))?;
+
+        Ok(())
+    }
+
+    /// Calls an RPC method with the parameters laid out one after the other, returning its result.
+    pub fn call_rpc_method<R: PlcDataType>(&self, name: &str, params: &[&[u8]]) -> PlcResult<R> {
+        let index_offset = self.handle(name)?;
+
+        let write_data = params.concat();
+
+        let mut read_data = R::default();
+
+        self.device()
+            .write_read_exact(
+                ads::index::RW_SYMVAL_BYHANDLE,
+                index_offset,
+                &write_data,
+                read_data.as_bytes_mut(),
+            )
+            .map_err(|error| PlcError::for_symbol_data(name, write_data.len(), error))?;
+
+        Ok(read_data)
+    }
+
+    /// Calls an RPC method with the parameters laid out one after the other, returning the result's bytes.
+    pub fn call_rpc_method_raw(
+        &self,
+        name: &str,
+        params: &[&[u8]],
+        result_length: usize,
+    ) -> PlcResult<Vec<u8>> {
+        let index_offset = self.handle(name)?;
+
+        let write_data = params.concat();
+
+        let mut read_data = vec![0; result_length];
+
+        self.device()
+            .write_read_exact(
+                ads::index::RW_SYMVAL_BYHANDLE,
+                index_offset,
+                &write_data,
+                &mut read_data,
+            )
+            .map_err(|error| PlcError::for_symbol_data(name, write_data.len(), error))?;
+
+        Ok(read_data)
+    }
+
+    /// Calls an RPC method with the parameters laid out one after the other, ignoring any result.
+    pub fn invoke_rpc_method_with_params(&self, name: &str, params: &[&[u8]]) -> PlcResult<()> {
+        let index_offset = self.handle(name)?;
+
+        let write_data = params.concat();
 
-        self.device().write_read_exact(
-            ads::index::RW_SYMVAL_BYHANDLE,
-            index_offset,
-            &write_data,
-            &mut [],
-        )?;
+        self.device()
+            .write_read_exact(
+                ads::index::RW_SYMVAL_BYHANDLE,
+                index_offset,
+                &write_data,
+                &mut [],
+            )
+            .map_err(|error| PlcError::for_symbol_data(name, write_data.len(), error))?;
 
         Ok(())
     }
 
-    pub fn fetch_from_rpc_method<T: PlcDataType>(&mut self, name: &str) -> Result<T> {
-        let handle = self.handle(name)?;
+    /// Checks an RPC method exists, fetching its handle.
+    pub fn resolve_rpc_method(&self, name: &str) -> PlcResult<u32> {
+        self.handle(name)
+    }
 
-        let mut read_data = T::default();
+    pub fn fetch_from_rpc_method<T: PlcDataType>(&self, name: &str) -> PlcResult<T> {
+        let index_offset = self.handle(name)?;
 
-        let index_offset = handle.raw();
+        let mut read_data = T::default();
 
-        self.device().write_read_exact(
-            ads::index::RW_SYMVAL_BYHANDLE,
-            index_offset,
-            &[],
-            read_data.as_bytes_mut(),
-        )?;
+        self.device()
+            .write_read_exact(
+                ads::index::RW_SYMVAL_BYHANDLE,
+                index_offset,
+                &[],
+                read_data.as_bytes_mut(),
+            )
+            .map_err(|error| PlcError::for_symbol_data(name, T::size(), error))?;
 
         Ok(read_data)
     }
 
-    pub fn subscribe<T: PlcDataType>(&mut self, name: &str) -> Result<u32> {
-        let notification_handle = {
-            let handle = self.handle(name)?;
+    pub fn subscribe<T: PlcDataType>(&self, name: &str) -> PlcResult<u32> {
+        self.add_notification(name, T::size())
+    }
+
+    /// Subscribes to a symbol's bytes, for symbols whose type is only known at runtime.
+    pub fn subscribe_raw(&self, name: &str, length: usize) -> PlcResult<u32> {
+        self.add_notification(name, length)
+    }
+
+    /// Subscribes to a symbol, delivering its samples to the given queue instead of the notification receiver.
+    pub(crate) fn subscribe_to_queue<T: PlcDataType>(
+        &self,
+        name: &str,
+        queue: Arc<SampleQueue>,
+    ) -> PlcResult<u32> {
+        let notification_router = self.notification_router.clone();
 
-            let index_offset = handle.raw();
+        // Hold the routes while adding the notification, so the initial sample is not delivered elsewhere
+        let mut routes = notification_router.routes();
 
-            self.device().add_notification(
+        let notification_handle = self.add_notification(name, T::size())?;
+
+        routes.insert(notification_handle, queue);
+
+        Ok(notification_handle)
+    }
+
+    /// Unsubscribes a queue, unless the subscription already ended with a previous connection.
+    pub(crate) fn unsubscribe_queue(&self, notification_handle: u32, queue: &Arc<SampleQueue>) {
+        if self
+            .notification_router
+            .remove_route(notification_handle, queue)
+        {
+            self.unsubscribe(notification_handle);
+        }
+    }
+
+    fn add_notification(&self, name: &str, length: usize) -> PlcResult<u32> {
+        let index_offset = self.handle(name)?;
+
+        let notification_handle = self
+            .device()
+            .add_notification(
                 ads::index::RW_SYMVAL_BYHANDLE,
                 index_offset,
                 &ads::notif::Attributes::new(
-                    T::size(),
+                    length,
                     ads::notif::TransmissionMode::ServerOnChange,
                     std::time::Duration::ZERO,
                     // TODO: setting this to higher e.g: 1000ms does not work, maybe because the status data is changing every PLC cycle?
                     // NB: Setting this to 10ms to match the PLC cycle time that it seems to be reporting at anyway
                     std::time::Duration::from_millis(10),
                 ),
-            )?
-        };
+            )
+            .map_err(|error| PlcError::for_symbol_data(name, length, error))?;
 
-        self.notification_handles.push(notification_handle);
+        self.notification_handles
+            .lock()
+            .unwrap()
+            .push(notification_handle);
 
         Ok(notification_handle)
     }
 
+    /// Receives notifications that are not delivered to a subscription queue.
     pub fn notification_receiver(&self) -> Receiver<ads::notif::Notification> {
-        self.ads_client().get_notification_channel()
+        self.notification_router.unrouted_receiver()
     }
 
     pub fn unsubscribe(&self, notification_handle: u32) {
+        self.delete_notification(notification_handle);
+
+        self.notification_handles
+            .lock()
+            .unwrap()
+            .retain(|handle| *handle != notification_handle);
+    }
+
+    pub fn unsubscribe_all(&self) {
+        let notification_handles = std::mem::take(&mut *self.notification_handles.lock().unwrap());
+
+        if self.is_closed() {
+            return;
+        }
+
+        for notification_handle in notification_handles {
+            self.delete_notification(notification_handle);
+        }
+    }
+
+    fn delete_notification(&self, notification_handle: u32) {
         // NB: unsure why, but deleting the notification returns a "Notification handle is invalid" error, hence the ok() here.
         // It still works though, so maybe not a problem.
         self.device().delete_notification(notification_handle).ok();
     }
+}
 
-    pub fn unsubscribe_all(&mut self) {
-        for notification_handle in &self.notification_handles {
-            self.unsubscribe(*notification_handle);
-        }
-
-        self.notification_handles.clear();
+impl Drop for PlcClient {
+    fn drop(&mut self) {
+        self.release_handles();
+        self.notification_router.close();
     }
 }
@@ -1,14 +1,34 @@
-use anyhow::{anyhow, Context, Result};
-use crossbeam_channel::Receiver;
+use anyhow::{Context, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
 use std::{
     net::{SocketAddr, ToSocketAddrs},
-    sync::{Arc, Mutex},
-    time::Duration,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
 };
 
-use ads::{AmsAddr, Client};
+use ads::AmsAddr;
+use tracing::{field, Span};
+
+use crate::{
+    capture::PacketCapture,
+    coalescing::{Coalescer, CoalescingMetrics, CoalescingStats},
+    data_types::PlcDataType,
+    error::{PlcError, PlcResult},
+    plc_client::PlcClient,
+    recording::SessionRecorder,
+    scope::Scope,
+    subscription::{DeliveryPolicy, SampleQueue, Subscription, SubscriptionOptions},
+    symbol::Symbol,
+    transport::{AmsTransport, FrameTap, Taps},
+};
+
+// Matches the cycle time notifications are checked at
+const WAIT_UNTIL_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
-use crate::{data_types::PlcDataType, plc_client::PlcClient};
+pub(crate) const CONNECTION_RETRY_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Clone)]
 pub struct PlcConnection {
@@ -16,7 +36,16 @@ pub struct PlcConnection {
     plc_ams_address: AmsAddr,
     local_ams_address: Option<AmsAddr>,
     set_to_run_mode: bool,
-    state: Arc<Mutex<PlcConnectionState>>,
+    coalescing_window: Option<Duration>,
+    // Kept across connections, so the stats cover the connection's whole lifetime
+    coalescing_metrics: Arc<CoalescingMetrics>,
+    recorder: Option<Arc<SessionRecorder>>,
+    capture: Option<Arc<PacketCapture>>,
+    state: Arc<RwLock<PlcConnectionState>>,
+    // Serialises connection attempts, without blocking requests on the current connection
+    connecting: Arc<Mutex<()>>,
+    connection_generation: Arc<AtomicU64>,
+    span: Span,
 }
 
 pub struct PlcConnectionBuilder {
@@ -24,6 +53,9 @@ pub struct PlcConnectionBuilder {
     plc_ams_address: AmsAddr,
     local_ams_address: Option<AmsAddr>,
     set_to_run_mode: bool,
+    coalescing_window: Option<Duration>,
+    recorder: Option<SessionRecorder>,
+    capture: Option<PacketCapture>,
 }
 
 impl PlcConnectionBuilder {
@@ -39,6 +71,9 @@ impl PlcConnectionBuilder {
             plc_ams_address,
             local_ams_address: None,
             set_to_run_mode: false,
+            coalescing_window: None,
+            recorder: None,
+            capture: None,
         }
     }
 
@@ -56,53 +91,152 @@ impl PlcConnectionBuilder {
         }
     }
 
+    /// Coalesces symbol reads and writes made within the window of each other into ADS sum commands. Off by default.
+    ///
+    /// Every read and write then waits up to the window before it is sent, trading latency for fewer round trips.
+    pub fn with_coalescing_window(self, coalescing_window: Option<Duration>) -> Self {
+        Self {
+            coalescing_window,
+            ..self
+        }
+    }
+
+    /// Records every frame sent to and received from the PLC, across reconnects, e.g: to replay a field issue offline.
+    pub fn with_recorder(self, recorder: Option<SessionRecorder>) -> Self {
+        Self { recorder, ..self }
+    }
+
+    /// Captures every frame sent to and received from the PLC in pcap format, across reconnects, for Wireshark.
+    pub fn with_capture(self, capture: Option<PacketCapture>) -> Self {
+        Self { capture, ..self }
+    }
+
     pub fn build(self) -> PlcConnection {
         PlcConnection {
             ads_router_address: self.ads_router_address,
             plc_ams_address: self.plc_ams_address,
             local_ams_address: self.local_ams_address,
             set_to_run_mode: self.set_to_run_mode,
+            coalescing_window: self.coalescing_window,
+            coalescing_metrics: Default::default(),
+            recorder: self.recorder.map(Arc::new),
+            capture: self.capture.map(Arc::new),
             state: Default::default(),
+            connecting: Default::default(),
+            connection_generation: Default::default(),
+            span: tracing::info_span!(
+                "plc_connection",
+                router = %self.ads_router_address,
+                plc = %self.plc_ams_address,
+            ),
         }
     }
 }
 
-// TODO: this is hacky, due to self_cell usage in PlcClient, but we know PlcConnection is thread safe due to Arc<Mutex<...>>
-unsafe impl Send for PlcConnection {}
-unsafe impl Sync for PlcConnection {}
-
 impl PlcConnection {
     /// Blocks the current thread until a PLC is successfully connected over ADS.
     pub fn run_connection_loop(&self) {
+        let _span = self.span.enter();
+
         loop {
-            {
-                let mut plc_connection_state = self.state.lock().unwrap();
-                if let Err(error) = plc_connection_state.connect(
-                    self.ads_router_address,
-                    self.plc_ams_address,
-                    self.local_ams_address,
-                    self.set_to_run_mode,
-                ) {
-                    println!("PLC connection failed, {}. Retrying in 2 seconds...", error);
-                } else {
-                    println!("PLC connection successful!");
-
-                    return;
-                }
+            if let Err(error) = self.try_connect() {
+                tracing::warn!(%error, "PLC connection failed, retrying in 2 seconds");
+            } else {
+                tracing::info!("PLC connection successful");
+
+                return;
             }
 
-            std::thread::sleep(Duration::from_secs(2));
+            std::thread::sleep(CONNECTION_RETRY_INTERVAL);
         }
     }
 
+    /// Makes a single attempt to connect to the PLC, succeeding immediately if it is already connected.
+    ///
+    /// Requests on other threads are not held up while connecting.
+    pub fn try_connect(&self) -> PlcResult<()> {
+        let _connecting = self.connecting.lock().unwrap();
+
+        if self.is_connected() {
+            tracing::debug!("Attempted to connect to PLC but it is already connected");
+
+            return Ok(());
+        }
+
+        let plc_client = connect_client(
+            self.ads_router_address,
+            self.plc_ams_address,
+            self.local_ams_address,
+            self.set_to_run_mode,
+            self.coalescing_window
+                .map(|window| Coalescer::new(window, self.coalescing_metrics.clone())),
+            self.taps(),
+        )?;
+
+        *self.state.write().unwrap() = PlcConnectionState::Connected(Arc::new(plc_client));
+
+        self.connection_generation.fetch_add(1, Ordering::SeqCst);
+
+        Ok(())
+    }
+
     pub fn disconnect(&self) {
-        let mut plc_connection_state = self.state.lock().unwrap();
+        let _span = self.span.enter();
+
+        let plc_connection_state = std::mem::take(&mut *self.state.write().unwrap());
 
         plc_connection_state.disconnect();
     }
 
+    fn taps(&self) -> Taps {
+        let recorder = self.recorder.clone().map(|tap| tap as Arc<dyn FrameTap>);
+        let capture = self.capture.clone().map(|tap| tap as Arc<dyn FrameTap>);
+
+        Taps::new(recorder.into_iter().chain(capture).collect())
+    }
+
+    /// Drops the connection after an error that calls for it.
+    fn handle_disconnect_error(&self, client: &Arc<PlcClient>, error: &PlcError) {
+        if !error.should_disconnect() {
+            return;
+        }
+
+        tracing::warn!(%error, "PLC client error indicates we should disconnect");
+
+        self.drop_client(client);
+    }
+
+    /// Drops the connection if the PLC closed it, which is otherwise only noticed once a request fails.
+    pub(crate) fn drop_if_closed(&self) {
+        if let Some(client) = self.client().filter(|client| client.is_closed()) {
+            tracing::warn!("PLC closed the connection");
+
+            self.drop_client(&client);
+        }
+    }
+
+    /// Drops the connection, unless it was already replaced by a new one.
+    fn drop_client(&self, client: &Arc<PlcClient>) {
+        let plc_connection_state = {
+            let mut plc_connection_state = self.state.write().unwrap();
+
+            match &*plc_connection_state {
+                PlcConnectionState::Connected(current) if Arc::ptr_eq(current, client) => {
+                    std::mem::take(&mut *plc_connection_state)
+                }
+                _ => return,
+            }
+        };
+
+        plc_connection_state.disconnect();
+    }
+
+    fn client(&self) -> Option<Arc<PlcClient>> {
+        self.state.read().unwrap().client()
+    }
+
     pub fn is_connected(&self) -> bool {
-        let plc_connection_state = self.state.lock().unwrap();
+        let plc_connection_state = self.state.read().unwrap();
 
         match *plc_connection_state {
             PlcConnectionState::Connected(_) => true,
@@ -110,108 +244,278 @@ impl PlcConnection {
         }
     }
 
-    /// Read a symbol from the PLC.
+    /// The PLC's ADS state, e.g: to check it is still in run mode.
+    pub fn ads_state(&self) -> PlcResult<ads::AdsState> {
+        self.request("reading state of", "PLC", None, |client| client.ads_state())
+    }
+
+    /// The name and version of the PLC's runtime.
+    pub fn device_info(&self) -> PlcResult<ads::client::DeviceInfo> {
+        self.request("reading device info of", "PLC", None, |client| {
+            client.device_info()
+        })
+    }
+
+    /// Looks up a symbol's type and size, including members of structures and elements of arrays.
+    pub fn symbol_info(&self, name: &str) -> PlcResult<ads::symbol::Symbol> {
+        self.request(
+            "reading symbol info of",
+            name,
+            Some(ads::index::GET_SYMINFO_BYNAME_EX),
+            |client| client.symbol_info(name),
+        )
+    }
+
+    /// Uploads the PLC's symbol table along with the data types it uses, e.g: to browse the symbols.
     ///
-    /// Returns None if the PLC is not connected.
-    pub fn read_symbol<T: PlcDataType>(&self, name: &str) -> Result<Option<T>> {
-        let mut plc_connection_state = self.state.lock().unwrap();
+    /// The whole table is uploaded each time, which can be several megabytes on a large project.
+    pub fn upload_symbol_info(
+        &self,
+    ) -> PlcResult<(Vec<ads::symbol::Symbol>, ads::symbol::TypeMap)> {
+        self.request(
+            "uploading symbols of",
+            "PLC",
+            Some(ads::index::SYM_UPLOAD),
+            |client| client.upload_symbol_info(),
+        )
+    }
 
-        if let Some(client) = plc_connection_state.client_mut() {
-            let value = client.read_symbol(name).map_err(|error| {
-                println!("PLC client error when reading symbol {}: {}", name, error);
+    /// Increases every time a new connection to the PLC is established.
+    ///
+    /// Notification handles do not survive a reconnect, so this can be used to detect when to subscribe again.
+    pub fn connection_generation(&self) -> u64 {
+        self.connection_generation.load(Ordering::SeqCst)
+    }
 
-                plc_connection_state.handle_disconnect_error(&error);
+    /// How reads and writes were coalesced so far, if coalescing is enabled.
+    pub fn coalescing_stats(&self) -> Option<CoalescingStats> {
+        self.coalescing_window
+            .map(|_| self.coalescing_metrics.stats())
+    }
 
-                error
-            })?;
+    /// A typed view of a symbol, resolved on first use. Call Symbol::resolve to check it up front instead.
+    pub fn symbol<T: PlcDataType>(&self, name: &str) -> Symbol<T> {
+        Symbol::new(self.clone(), name)
+    }
 
-            return Ok(Some(value));
-        }
+    /// A view of this connection resolving symbol paths relative to the prefix.
+    pub fn scope(&self, prefix: &str) -> Scope {
+        Scope::new(self.clone(), prefix)
+    }
 
-        Ok(None)
+    /// Fetches a symbol's handle, failing with PlcError::SizeMismatch if its size on the PLC differs from T.
+    pub(crate) fn resolve_symbol<T: PlcDataType>(&self, name: &str) -> PlcResult<u32> {
+        self.request(
+            "resolving symbol",
+            name,
+            Some(ads::index::GET_SYMINFO_BYNAME),
+            |client| client.resolve_symbol(name, T::size()),
+        )
     }
 
-    /// Calls an RPC method on the PLC that returns a value.
+    /// Runs a request against the connected client, dropping the connection if the error calls for it.
     ///
-    /// Returns None if the PLC is not connected.
-    pub fn fetch_from_rpc_method<T: PlcDataType>(&self, name: &str) -> Result<Option<T>> {
-        let mut plc_connection_state = self.state.lock().unwrap();
+    /// The index group is recorded on the request's span, and is `None` for commands without one, e.g: reading the
+    /// PLC's state.
+    fn request<R>(
+        &self,
+        description: &str,
+        name: &str,
+        index_group: Option<u32>,
+        request: impl FnOnce(&PlcClient) -> PlcResult<R>,
+    ) -> PlcResult<R> {
+        let span = tracing::debug_span!(
+            parent: &self.span,
+            "plc_request",
+            operation = description,
+            symbol = name,
+            index_group,
+            latency_us = field::Empty,
+            result = field::Empty,
+        );
+        let _span = span.enter();
+
+        // NB: the client is shared rather than locked, so other requests can be in flight at the same time
+        let Some(client) = self.client() else {
+            span.record("result", "not connected");
 
-        if let Some(client) = plc_connection_state.client_mut() {
-            let value = client.fetch_from_rpc_method(name).map_err(|error| {
-                eprintln!(
-                    "PLC client error when invoking RPC method {}: {}",
-                    name, error
-                );
+            return Err(PlcError::NotConnected);
+        };
 
-                plc_connection_state.handle_disconnect_error(&error);
+        let started = Instant::now();
+        let result = request(&client);
 
-                error
-            })?;
+        span.record("latency_us", started.elapsed().as_micros() as u64);
 
-            return Ok(Some(value));
+        match &result {
+            Ok(_) => {
+                span.record("result", "ok");
+            }
+            Err(error) => {
+                span.record("result", field::display(error));
+
+                tracing::warn!(%error, "PLC client error when {} {}", description, name);
+
+                self.handle_disconnect_error(&client, error);
+            }
         }
 
-        Ok(None)
+        result
     }
 
-    /// Calls an RPC method on the PLC.
-    ///
-    /// Returns None if the PLC is not connected.
-    pub fn invoke_rpc_method(&self, name: &str) -> Result<Option<()>> {
-        let mut plc_connection_state = self.state.lock().unwrap();
+    /// Read a symbol from the PLC.
+    pub fn read_symbol<T: PlcDataType>(&self, name: &str) -> PlcResult<T> {
+        self.request(
+            "reading symbol",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.read_symbol(name),
+        )
+    }
 
-        if let Some(client) = plc_connection_state.client_mut() {
-            client.invoke_rpc_method(name).map_err(|error| {
-                eprintln!(
-                    "PLC client error when invoking RPC method {}: {}",
-                    name, error
-                );
+    /// Write a symbol to the PLC.
+    pub fn write_symbol<T: PlcDataType>(&self, name: &str, value: T) -> PlcResult<()> {
+        self.request(
+            "writing symbol",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.write_symbol(name, value),
+        )
+    }
 
-                plc_connection_state.handle_disconnect_error(&error);
+    /// Reads a symbol's bytes, for symbols whose type is only known at runtime.
+    pub fn read_symbol_raw(&self, name: &str, length: usize) -> PlcResult<Vec<u8>> {
+        self.request(
+            "reading symbol",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.read_symbol_raw(name, length),
+        )
+    }
 
-                error
-            })?;
+    /// Writes a symbol's bytes, for symbols whose type is only known at runtime.
+    pub fn write_symbol_raw(&self, name: &str, data: &[u8]) -> PlcResult<()> {
+        self.request(
+            "writing symbol",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.write_symbol_raw(name, data),
+        )
+    }
 
-            return Ok(Some(()));
-        }
+    /// Reads several symbols together, given their names and sizes, in one round trip where the PLC supports it.
+    ///
+    /// Each read succeeds or fails on its own, unless the connection drops.
+    pub fn read_symbols_raw(
+        &self,
+        symbols: &[(&str, usize)],
+    ) -> PlcResult<Vec<PlcResult<Vec<u8>>>> {
+        let names = symbols
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        self.request(
+            "reading symbols",
+            &names,
+            Some(ads::index::SUMUP_READ),
+            |client| {
+                let mut results = client.read_symbols_raw(symbols);
+
+                // Fail the whole request, so a dropped connection is noticed
+                match results.iter().position(|result| {
+                    result
+                        .as_ref()
+                        .is_err_and(|error| error.should_disconnect())
+                }) {
+                    Some(index) => Err(results.swap_remove(index).unwrap_err()),
+                    None => Ok(results),
+                }
+            },
+        )
+    }
 
-        Ok(None)
+    /// Calls an RPC method with the parameters laid out one after the other, returning its result.
+    pub fn call_rpc_method<R: PlcDataType>(&self, name: &str, params: &[&[u8]]) -> PlcResult<R> {
+        self.request(
+            "invoking RPC method",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.call_rpc_method(name, params),
+        )
     }
 
-    /// Calls an RPC method on the PLC with one parameter.
-    ///
-    /// Returns None if the PLC is not connected.
-    pub fn invoke_rpc_method_with_param<P: PlcDataType>(
+    /// Calls an RPC method with the parameters laid out one after the other, returning the result's bytes.
+    pub fn call_rpc_method_raw(
         &self,
         name: &str,
-        param: P,
-    ) -> Result<Option<()>> {
-        let mut plc_connection_state = self.state.lock().unwrap();
+        params: &[&[u8]],
+        result_length: usize,
+    ) -> PlcResult<Vec<u8>> {
+        self.request(
+            "invoking RPC method",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.call_rpc_method_raw(name, params, result_length),
+        )
+    }
 
-        if let Some(client) = plc_connection_state.client_mut() {
-            client
-                .invoke_rpc_method_with_param(name, param)
-                .map_err(|error| {
-                    eprintln!(
-                        "PLC client error when invoking RPC method {}: {}",
-                        name, error
-                    );
+    /// Calls an RPC method with the parameters laid out one after the other, ignoring any result.
+    pub fn invoke_rpc_method_with_params(&self, name: &str, params: &[&[u8]]) -> PlcResult<()> {
+        self.request(
+            "invoking RPC method",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.invoke_rpc_method_with_params(name, params),
+        )
+    }
 
-                    plc_connection_state.handle_disconnect_error(&error);
+    /// Checks an RPC method exists, e.g: as part of a startup check.
+    pub fn resolve_rpc_method(&self, name: &str) -> PlcResult<()> {
+        self.request(
+            "resolving RPC method",
+            name,
+            Some(ads::index::GET_SYMHANDLE_BYNAME),
+            |client| client.resolve_rpc_method(name).map(|_| ()),
+        )
+    }
 
-                    error
-                })?;
+    /// Calls an RPC method on the PLC that returns a value.
+    pub fn fetch_from_rpc_method<T: PlcDataType>(&self, name: &str) -> PlcResult<T> {
+        self.request(
+            "invoking RPC method",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.fetch_from_rpc_method(name),
+        )
+    }
 
-            return Ok(Some(()));
-        }
+    /// Calls an RPC method on the PLC.
+    pub fn invoke_rpc_method(&self, name: &str) -> PlcResult<()> {
+        self.request(
+            "invoking RPC method",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.invoke_rpc_method(name),
+        )
+    }
 
-        Ok(None)
+    /// Calls an RPC method on the PLC with one parameter.
+    pub fn invoke_rpc_method_with_param<P: PlcDataType>(
+        &self,
+        name: &str,
+        param: P,
+    ) -> PlcResult<()> {
+        self.request(
+            "invoking RPC method",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.invoke_rpc_method_with_param(name, param),
+        )
     }
 
     /// Calls an RPC method on the PLC with three parameters.
-    ///
-    /// Returns None if the PLC is not connected.
     pub fn invoke_rpc_method_with_three_params<
         P1: PlcDataType,
         P2: PlcDataType,
@@ -222,122 +526,177 @@ impl PlcConnection {
         param_1: P1,
         param_2: P2,
         param_3: P3,
-    ) -> Result<Option<()>> {
-        let mut plc_connection_state = self.state.lock().unwrap();
-
-        if let Some(client) = plc_connection_state.client_mut() {
-            client
-                .invoke_rpc_method_with_three_params(name, param_1, param_2, param_3)
-                .map_err(|error| {
-                    eprintln!(
-                        "PLC client error when invoking RPC method {}: {}",
-                        name, error
-                    );
-
-                    plc_connection_state.handle_disconnect_error(&error);
-
-                    error
-                })?;
-
-            return Ok(Some(()));
-        }
-
-        Ok(None)
+    ) -> PlcResult<()> {
+        self.request(
+            "invoking RPC method",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.invoke_rpc_method_with_three_params(name, param_1, param_2, param_3),
+        )
     }
 
     /// Subscribes to a notification channel on the PLC, returning a handle to the channel.
-    ///
-    /// Returns None if the PLC is not connected.
-    pub fn subscribe<T: PlcDataType>(&self, name: &str) -> Result<Option<u32>> {
-        let mut plc_connection_state = self.state.lock().unwrap();
+    pub fn subscribe<T: PlcDataType>(&self, name: &str) -> PlcResult<u32> {
+        self.request(
+            "subscribing to notifications from",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.subscribe::<T>(name),
+        )
+    }
 
-        if let Some(client) = plc_connection_state.client_mut() {
-            let handle = client.subscribe::<T>(name).map_err(|error| {
-                eprintln!(
-                    "PLC client error when subscribing to notifications from {}: {}",
-                    name, error
-                );
+    /// Subscribes to a notification channel for a symbol's bytes, for symbols whose type is only known at runtime.
+    pub fn subscribe_raw(&self, name: &str, length: usize) -> PlcResult<u32> {
+        self.request(
+            "subscribing to notifications from",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.subscribe_raw(name, length),
+        )
+    }
 
-                plc_connection_state.handle_disconnect_error(&error);
+    /// Subscribes to notifications from a symbol, delivered to the returned subscription according to the policy.
+    pub fn subscribe_with_policy<T: PlcDataType>(
+        &self,
+        name: &str,
+        delivery_policy: DeliveryPolicy,
+    ) -> PlcResult<Subscription<T>> {
+        self.subscribe_with_options(
+            name,
+            SubscriptionOptions::new().with_delivery_policy(delivery_policy),
+        )
+    }
 
-                error
-            })?;
+    /// Subscribes to notifications from a symbol, filtered and delivered to the returned subscription according to
+    /// the options.
+    pub fn subscribe_with_options<T: PlcDataType>(
+        &self,
+        name: &str,
+        options: SubscriptionOptions<T>,
+    ) -> PlcResult<Subscription<T>> {
+        let queue = SampleQueue::new(options);
+
+        let handle = self.request(
+            "subscribing to notifications from",
+            name,
+            Some(ads::index::RW_SYMVAL_BYHANDLE),
+            |client| client.subscribe_to_queue::<T>(name, queue.clone()),
+        )?;
+
+        Ok(Subscription::new(handle, queue, self.clone()))
+    }
 
-            return Ok(Some(handle));
+    pub(crate) fn unsubscribe_queue(&self, notification_handle: u32, queue: &Arc<SampleQueue>) {
+        if let Some(client) = self.client() {
+            client.unsubscribe_queue(notification_handle, queue);
         }
+    }
 
-        Ok(None)
+    /// Deletes a notification previously created with the subscribe function.
+    ///
+    /// Does nothing if the PLC is not connected, as all notifications are dropped with the connection.
+    pub fn unsubscribe(&self, notification_handle: u32) {
+        if let Some(client) = self.client() {
+            client.unsubscribe(notification_handle);
+        }
     }
 
     /// Gets a notification receiver that streams symbol data as it changes on the PLC.
     ///
-    /// A symbol must first be subscribed using the subscribe function.
-    pub fn notification_receiver(&self) -> Option<Receiver<ads::notif::Notification>> {
-        let plc_connection_state = self.state.lock().unwrap();
-
-        plc_connection_state
-            .client()
+    /// A symbol must first be subscribed using the subscribe function. Samples delivered to a subscription created
+    /// with subscribe_with_policy do not appear here.
+    pub fn notification_receiver(&self) -> PlcResult<Receiver<ads::notif::Notification>> {
+        self.client()
             .map(|client| client.notification_receiver())
+            .ok_or(PlcError::NotConnected)
     }
-}
 
-enum PlcConnectionState {
-    Connected(PlcClient),
-    Disconnected,
-}
-
-impl Default for PlcConnectionState {
-    fn default() -> Self {
-        Self::Disconnected
-    }
-}
+    /// Blocks until a symbol satisfies the predicate, returning the matching value.
+    ///
+    /// Values are received through a notification where possible, otherwise the symbol is polled. Fails with
+    /// PlcError::Timeout if no matching value arrives in time, or PlcError::NotConnected if the connection drops.
+    pub fn wait_until<T: PlcDataType>(
+        &self,
+        name: &str,
+        mut predicate: impl FnMut(&T) -> bool,
+        timeout: Duration,
+    ) -> PlcResult<T> {
+        let deadline = Instant::now() + timeout;
 
-impl PlcConnectionState {
-    fn connect(
-        &mut self,
-        ads_router_address: SocketAddr,
-        plc_ams_address: AmsAddr,
-        local_ams_address: Option<AmsAddr>,
-        set_to_run_mode: bool,
-    ) -> Result<()> {
-        match self {
-            PlcConnectionState::Connected(_) => {
-                println!("Attempted to connect to PLC but it is already connected!")
-            }
-            PlcConnectionState::Disconnected => {
-                let ams_source: ads::Source =
-                    local_ams_address.map_or(ads::Source::Request, ads::Source::Addr);
+        let subscription =
+            match self.subscribe_with_policy::<T>(name, DeliveryPolicy::CoalesceLatest) {
+                Ok(subscription) => subscription,
+                Err(error) if !self.is_connected() => return Err(error),
+                Err(_) => return self.poll_until(name, predicate, deadline),
+            };
 
-                let mut timeouts = ads::Timeouts::new(Duration::from_millis(1000));
-                timeouts.read = Some(Duration::from_millis(2000));
+        // NB: notifications just stop when the connection drops, so the client is checked whenever none arrived
+        let client = self.client();
 
-                let ads_client = Client::new(ads_router_address, timeouts, ams_source)?;
+        loop {
+            let now = Instant::now();
 
-                let plc_client = PlcClient::new(ads_client, plc_ams_address);
+            if now >= deadline {
+                return Err(PlcError::Timeout);
+            }
 
-                if !plc_client.is_run_mode()? && set_to_run_mode {
-                    plc_client.set_to_run_mode()?;
+            match subscription.recv_timeout(WAIT_UNTIL_POLL_INTERVAL.min(deadline - now)) {
+                Ok(value) if predicate(&value) => return Ok(value),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout)
+                    if client.as_ref().is_none_or(|client| client.is_closed()) =>
+                {
+                    return Err(PlcError::NotConnected)
                 }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(PlcError::NotConnected),
+            }
+        }
+    }
 
-                if !plc_client.is_run_mode()? {
-                    return Err(anyhow!("PLC not in run mode, stopping connection."));
-                }
+    fn poll_until<T: PlcDataType>(
+        &self,
+        name: &str,
+        mut predicate: impl FnMut(&T) -> bool,
+        deadline: Instant,
+    ) -> PlcResult<T> {
+        loop {
+            let value = match self.read_symbol::<T>(name) {
+                Ok(value) => value,
+                // NB: reported like a dropped notification, whichever way the value was waited for
+                Err(_) if !self.is_connected() => return Err(PlcError::NotConnected),
+                Err(error) => return Err(error),
+            };
+
+            if predicate(&value) {
+                return Ok(value);
+            }
+
+            let now = Instant::now();
 
-                *self = PlcConnectionState::Connected(plc_client);
+            if now >= deadline {
+                return Err(PlcError::Timeout);
             }
-        }
 
-        Ok(())
+            std::thread::sleep(WAIT_UNTIL_POLL_INTERVAL.min(deadline - now));
+        }
     }
+}
+
+#[derive(Default)]
+enum PlcConnectionState {
+    Connected(Arc<PlcClient>),
+    #[default]
+    Disconnected,
+}
 
-    fn disconnect(&mut self) {
+impl PlcConnectionState {
+    fn disconnect(self) {
         match self {
             PlcConnectionState::Connected(plc_client) => {
                 plc_client.unsubscribe_all();
 
-                *self = PlcConnectionState::Disconnected;
-
-                println!("PLC connection was dropped.");
+                tracing::info!("PLC connection was dropped");
             }
             PlcConnectionState::Disconnected => {
                 // Already disconnected...
@@ -345,34 +704,41 @@ impl PlcConnectionState {
         }
     }
 
-    fn client(&self) -> Option<&PlcClient> {
+    fn client(&self) -> Option<Arc<PlcClient>> {
         match self {
-            PlcConnectionState::Connected(plc_client) => Some(plc_client),
+            PlcConnectionState::Connected(plc_client) => Some(plc_client.clone()),
             PlcConnectionState::Disconnected => None,
         }
     }
+}
 
-    fn client_mut(&mut self) -> Option<&mut PlcClient> {
-        match self {
-            PlcConnectionState::Connected(plc_client) => Some(plc_client),
-            PlcConnectionState::Disconnected => None,
-        }
+fn connect_client(
+    ads_router_address: SocketAddr,
+    plc_ams_address: AmsAddr,
+    local_ams_address: Option<AmsAddr>,
+    set_to_run_mode: bool,
+    coalescer: Option<Coalescer>,
+    taps: Taps,
+) -> PlcResult<PlcClient> {
+    let transport = AmsTransport::connect(
+        ads_router_address,
+        local_ams_address,
+        Duration::from_millis(1000),
+        Duration::from_millis(2000),
+        taps,
+    )?;
+
+    let plc_client = PlcClient::new(transport, plc_ams_address, coalescer);
+
+    if !plc_client.is_run_mode()? && set_to_run_mode {
+        plc_client.set_to_run_mode()?;
     }
 
-    fn handle_disconnect_error(&mut self, error: &ads::Error) {
-        let should_disconnect = matches!(
-            error,
-            ads::Error::Io(_, _)
-                | ads::Error::Ads(_, _, 0x006)
-                | ads::Error::Reply(_, "unexpected invoke ID", _)
-        );
-
-        if should_disconnect {
-            println!("PLC client error indicates we should disconnect...");
-
-            self.disconnect();
-        }
+    if !plc_client.is_run_mode()? {
+        return Err(PlcError::NotInRunMode);
     }
+
+    Ok(plc_client)
 }
 
 pub fn parse_socket_address_from_env(
@@ -397,10 +763,16 @@ pub fn parse_ams_address_from_env(net_id_key: &str, port_key: &str) -> Result<Am
 
     let net_id: [u8; 6] = net_id_string
         .split('.')
-        .map(|e| e.parse::<u8>().unwrap())
-        .collect::<Vec<u8>>()
+        .map(|e| e.parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()
+        .context(format!(
+            "Environment variable {net_id_key} is not an AMS net ID."
+        ))?
         .as_slice()
-        .try_into()?;
+        .try_into()
+        .context(format!(
+            "Environment variable {net_id_key} is not an AMS net ID."
+        ))?;
 
     let port: u16 = port_string.parse()?;
 
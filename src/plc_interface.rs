@@ -0,0 +1,222 @@
+//! Declares a typed client for a function block on the PLC, see [`plc_interface!`](crate::plc_interface!).
+
+/// Declares a struct wrapping a [`PlcConnection`](crate::plc_connection::PlcConnection) with a typed
+/// [`Symbol`](crate::symbol::Symbol) per variable and a method per RPC method of a function block.
+///
+/// Variables are found at `prefix.name` and RPC methods at `prefix#name`, the name defaulting to the member's own name.
+/// `in_scope` points the struct at another instance of the same function block instead of the prefix.
+/// The struct also gets `read_all`, reading every variable in one round trip into the values struct, and `validate`,
+/// checking every variable and RPC method against the PLC e.g: at startup.
+///
+/// ```no_run
+/// use ads_client::{
+///     data_types::primitives::{bool::PlcBool, dint::PlcDInt, real::PlcReal},
+///     plc_interface,
+/// };
+///
+/// plc_interface! {
+///     /// The conveyor belt feeding the packing station.
+///     pub struct Conveyor {
+///         prefix = "MAIN.fbConveyor";
+///         values = ConveyorValues;
+///
+///         /// Belt speed in m/s.
+///         var speed: PlcReal = "fSpeed";
+///         var running: PlcBool = "bRunning";
+///
+///         rpc start(speed: PlcDInt) -> PlcBool = "Start";
+///         rpc stop() = "Stop";
+///     }
+/// }
+///
+/// # fn run(connection: ads_client::plc_connection::PlcConnection) -> ads_client::error::PlcResult<()> {
+/// let conveyor = Conveyor::new(connection);
+/// conveyor.validate()?;
+///
+/// conveyor.start(PlcDInt::from(2))?;
+/// let values = conveyor.read_all()?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! plc_interface {
+    (@member $separator:literal, $member:ident) => {
+        concat!($separator, stringify!($member))
+    };
+    (@member $separator:literal, $member:ident $name:literal) => {
+        concat!($separator, $name)
+    };
+    (@return) => { () };
+    (@return $ret:ty) => { $ret };
+    (@call $connection:expr, $path:expr, [$($param:expr),*]) => {
+        $connection.invoke_rpc_method_with_params(
+            $path,
+            &[$($crate::plc_interface::__private::bytes(&$param)),*],
+        )
+    };
+    (@call $connection:expr, $path:expr, [$($param:expr),*] $ret:ty) => {
+        $connection.call_rpc_method::<$ret>(
+            $path,
+            &[$($crate::plc_interface::__private::bytes(&$param)),*],
+        )
+    };
+    // Members are collected one at a time, so variables and RPC methods can each have attributes
+    (@munch $header:tt [$($vars:tt)*] $rpcs:tt
+        $(#[$meta:meta])* var $var:ident : $ty:ty $(= $name:literal)?; $($rest:tt)*
+    ) => {
+        $crate::plc_interface!(
+            @munch $header [$($vars)* {[$(#[$meta])*] $var, $ty, ($($name)?)}] $rpcs $($rest)*
+        );
+    };
+    (@munch $header:tt $vars:tt [$($rpcs:tt)*]
+        $(#[$meta:meta])* rpc $rpc:ident ($($param:ident : $param_ty:ty),* $(,)?) $(-> $ret:ty)? $(= $name:literal)?;
+        $($rest:tt)*
+    ) => {
+        $crate::plc_interface!(
+            @munch $header $vars [$($rpcs)* {[$(#[$meta])*] $rpc, ($($param: $param_ty),*), ($($ret)?), ($($name)?)}]
+            $($rest)*
+        );
+    };
+    (@munch
+        [[$(#[$meta:meta])*] $vis:vis, $struct_name:ident, $prefix:literal, $values:ident]
+        [$({[$(#[$var_meta:meta])*] $var:ident, $var_ty:ty, ($($var_name:literal)?)})*]
+        [$({
+            [$(#[$rpc_meta:meta])*] $rpc:ident, ($($param:ident : $param_ty:ty),*), ($($ret:ty)?), ($($rpc_name:literal)?)
+        })*]
+    ) => {
+        $(#[$meta])*
+        $vis struct $struct_name {
+            scope: $crate::scope::Scope,
+            $($var: $crate::symbol::Symbol<$var_ty>,)*
+        }
+
+        /// Every variable's value, read together.
+        #[derive(Clone, Debug)]
+        $vis struct $values {
+            $($(#[$var_meta])* pub $var: $var_ty,)*
+        }
+
+        impl $struct_name {
+            pub const PREFIX: &'static str = $prefix;
+
+            pub fn new(connection: $crate::plc_connection::PlcConnection) -> Self {
+                Self::in_scope(connection.scope(Self::PREFIX))
+            }
+
+            /// Points at the function block instance the scope points at, instead of the prefix.
+            pub fn in_scope(scope: $crate::scope::Scope) -> Self {
+                Self {
+                    $($var: scope.symbol($crate::plc_interface!(@member ".", $var $($var_name)?)),)*
+                    scope,
+                }
+            }
+
+            pub fn connection(&self) -> &$crate::plc_connection::PlcConnection {
+                self.scope.connection()
+            }
+
+            pub fn scope(&self) -> &$crate::scope::Scope {
+                &self.scope
+            }
+
+            $(
+                $(#[$var_meta])*
+                pub fn $var(&self) -> &$crate::symbol::Symbol<$var_ty> {
+                    &self.$var
+                }
+            )*
+
+            $(
+                $(#[$rpc_meta])*
+                pub fn $rpc(
+                    &self,
+                    $($param: $param_ty),*
+                ) -> $crate::error::PlcResult<$crate::plc_interface!(@return $($ret)?)> {
+                    $crate::plc_interface!(
+                        @call self.scope.connection(),
+                        &self.scope.path($crate::plc_interface!(@member "#", $rpc $($rpc_name)?)),
+                        [$($param),*] $($ret)?
+                    )
+                }
+            )*
+
+            /// Reads every variable, in one round trip where the PLC supports it.
+            #[allow(unused_mut, unused_variables)]
+            pub fn read_all(&self) -> $crate::error::PlcResult<$values> {
+                let symbols: &[(&str, usize)] = &[
+                    $((self.$var.name(), <$var_ty as $crate::data_types::PlcDataType>::size()),)*
+                ];
+
+                let mut results = self.connection().read_symbols_raw(symbols)?.into_iter();
+
+                Ok($values {
+                    $($var: $crate::plc_interface::__private::decode(self.$var.name(), results.next())?,)*
+                })
+            }
+
+            /// Checks every variable exists with the expected size, and every RPC method exists.
+            pub fn validate(&self) -> $crate::error::PlcResult<()> {
+                $(self.$var.resolve()?;)*
+
+                $(
+                    self.connection().resolve_rpc_method(
+                        &self.scope.path($crate::plc_interface!(@member "#", $rpc $($rpc_name)?)),
+                    )?;
+                )*
+
+                Ok(())
+            }
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $struct_name:ident {
+            prefix = $prefix:literal;
+            values = $values:ident;
+            $($members:tt)*
+        }
+    ) => {
+        $crate::plc_interface!(
+            @munch [[$(#[$meta])*] $vis, $struct_name, $prefix, $values] [] [] $($members)*
+        );
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use crate::{
+        data_types::PlcDataType,
+        error::{PlcError, PlcResult},
+        scope::Scope,
+    };
+
+    pub fn bytes<T: PlcDataType>(value: &T) -> &[u8] {
+        value.as_bytes()
+    }
+
+    pub fn decode<T: PlcDataType>(
+        symbol: &str,
+        result: Option<PlcResult<Vec<u8>>>,
+    ) -> PlcResult<T> {
+        let data = result.expect("a result for every symbol")?;
+
+        T::from_bytes(&data).ok_or_else(|| PlcError::InvalidValue {
+            reason: format!("{symbol} read {} bytes", data.len()),
+        })
+    }
+
+    /// Calls an RPC method for a result of any type with a fixed layout, e.g: an array, for generated bindings.
+    pub fn call_rpc_method<R: zerocopy::FromBytes>(
+        scope: &Scope,
+        relative: &str,
+        params: &[&[u8]],
+    ) -> PlcResult<R> {
+        let data = scope.connection().call_rpc_method_raw(
+            &scope.path(relative),
+            params,
+            std::mem::size_of::<R>(),
+        )?;
+
+        Ok(R::read_from(data.as_slice()).expect("a result of the requested length"))
+    }
+}
@@ -0,0 +1,234 @@
+//! Records every AMS frame sent to and received from the PLC, with timings, so a session can be replayed offline.
+//!
+//! A recording is a text file with one frame per line, e.g:
+//!
+//! ```text
+//! # ads-client session recorded 2024-05-01T12:00:00+00:00
+//! connect 0
+//! > 120 00002c000000...
+//! < 950 000030000000...
+//! ```
+//!
+//! `>` lines were sent and `<` lines received, each with the microseconds since recording started and the frame in
+//! hex. `connect` starts the frames of a new connection. With the `testing` feature, `testing::ReplayServer` serves a
+//! recording back to a connection.
+
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufRead, BufReader, LineWriter, Write},
+    net::SocketAddr,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::transport::FrameTap;
+
+const HEADER: &str = "# ads-client session recorded";
+
+/// Writes the frames of every connection a `PlcConnection` makes to a recording, see
+/// `PlcConnectionBuilder::with_recorder`.
+pub struct SessionRecorder {
+    writer: Mutex<Box<dyn Write + Send>>,
+    started: Instant,
+}
+
+impl SessionRecorder {
+    /// Records to a new file, replacing any existing one.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(LineWriter::new(File::create(path)?))
+    }
+
+    /// Records to any writer, e.g: an in-memory buffer.
+    pub fn new(writer: impl Write + Send + 'static) -> io::Result<Self> {
+        let mut writer: Box<dyn Write + Send> = Box::new(writer);
+
+        writeln!(writer, "{HEADER} {}", chrono::Local::now().to_rfc3339())?;
+
+        Ok(Self {
+            writer: Mutex::new(writer),
+            started: Instant::now(),
+        })
+    }
+
+    fn elapsed(&self) -> u128 {
+        self.started.elapsed().as_micros()
+    }
+
+    fn write_line(&self, line: &str) {
+        // NB: a recording is a diagnostic aid, so failing to write it must not fail the request being recorded
+        if let Err(error) = writeln!(self.writer.lock().unwrap(), "{line}") {
+            tracing::warn!(%error, "Failed to write session recording");
+        }
+    }
+}
+
+impl FrameTap for SessionRecorder {
+    fn connected(&self, _local: SocketAddr, _peer: SocketAddr) {
+        self.write_line(&format!("connect {}", self.elapsed()));
+    }
+
+    fn frame(&self, direction: Direction, frame: &[u8]) {
+        let mut line = format!("{} {} ", direction.symbol(), self.elapsed());
+
+        for byte in frame {
+            write!(line, "{byte:02x}").unwrap();
+        }
+
+        self.write_line(&line);
+    }
+}
+
+/// Which way a frame went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+impl Direction {
+    fn symbol(self) -> char {
+        match self {
+            Direction::Sent => '>',
+            Direction::Received => '<',
+        }
+    }
+}
+
+/// A frame sent or received during a recorded session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// Time since the recording started.
+    pub elapsed: Duration,
+    pub direction: Direction,
+    /// The whole AMS/TCP frame, including its TCP header.
+    pub frame: Vec<u8>,
+}
+
+/// A session read back from a recording, one list of events per connection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    pub connections: Vec<Vec<Event>>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let mut recording = Recording::default();
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}", number + 1),
+                )
+            };
+
+            let mut fields = line.split_whitespace();
+
+            let direction = match fields.next() {
+                None => continue,
+                Some(field) if field.starts_with('#') => continue,
+                Some("connect") => {
+                    recording.connections.push(Vec::new());
+
+                    continue;
+                }
+                Some(">") => Direction::Sent,
+                Some("<") => Direction::Received,
+                Some(_) => return Err(invalid("unknown event")),
+            };
+
+            let elapsed = fields
+                .next()
+                .and_then(|elapsed| elapsed.parse().ok())
+                .map(Duration::from_micros)
+                .ok_or_else(|| invalid("invalid time"))?;
+
+            let frame = fields
+                .next()
+                .and_then(parse_hex)
+                .ok_or_else(|| invalid("invalid frame"))?;
+
+            recording
+                .connections
+                .last_mut()
+                .ok_or_else(|| invalid("frame before the first connect"))?
+                .push(Event {
+                    elapsed,
+                    direction,
+                    frame,
+                });
+        }
+
+        Ok(recording)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(data)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn recordings_read_back() {
+        let buffer = Buffer::default();
+        let recorder = SessionRecorder::new(buffer.clone()).unwrap();
+
+        let address = SocketAddr::from(([127, 0, 0, 1], 48898));
+
+        recorder.connected(address, address);
+        recorder.frame(Direction::Sent, &[0, 0, 0xab]);
+        recorder.frame(Direction::Received, &[0x10, 0]);
+        recorder.connected(address, address);
+
+        let recording = Recording::read(&buffer.0.lock().unwrap()[..]).unwrap();
+
+        assert_eq!(recording.connections.len(), 2);
+        assert_eq!(recording.connections[0][0].direction, Direction::Sent);
+        assert_eq!(recording.connections[0][0].frame, [0, 0, 0xab]);
+        assert_eq!(recording.connections[0][1].direction, Direction::Received);
+        assert_eq!(recording.connections[0][1].frame, [0x10, 0]);
+        assert!(recording.connections[0][0].elapsed <= recording.connections[0][1].elapsed);
+        assert!(recording.connections[1].is_empty());
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected() {
+        assert!(Recording::read(&b"> 1 00"[..]).is_err());
+        assert!(Recording::read(&b"connect 0\n> x 00"[..]).is_err());
+        assert!(Recording::read(&b"connect 0\n> 1 0"[..]).is_err());
+        assert!(Recording::read(&b"connect 0\n? 1 00"[..]).is_err());
+    }
+}
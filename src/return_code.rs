@@ -0,0 +1,806 @@
+//! Catalogue of the ADS return codes a TwinCAT device can reply with.
+//!
+//! Names and descriptions follow the Beckhoff ADS return code documentation.
+
+use ReturnCodeClass::*;
+
+/// How an ADS return code should be handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReturnCodeClass {
+    /// The request may succeed if retried later, e.g: the device is busy.
+    Transient,
+    /// The device or router failed in a way the client cannot recover from.
+    Fatal,
+    /// The request or the system is set up wrong, e.g: an unknown symbol or a missing license.
+    Configuration,
+    /// The connection to the device is gone or unusable, and should be re-established.
+    Disconnect,
+}
+
+/// A known ADS return code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReturnCode {
+    pub code: u32,
+    /// Symbolic name, as used in the TwinCAT headers e.g: ADSERR_DEVICE_SYMBOLNOTFOUND.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub class: ReturnCodeClass,
+}
+
+impl ReturnCode {
+    /// Looks up a return code in the catalogue.
+    pub fn lookup(code: u32) -> Option<&'static ReturnCode> {
+        RETURN_CODES
+            .binary_search_by_key(&code, |return_code| return_code.code)
+            .ok()
+            .map(|index| &RETURN_CODES[index])
+    }
+
+    /// Classifies a return code, treating codes missing from the catalogue as fatal.
+    pub fn classify(code: u32) -> ReturnCodeClass {
+        Self::lookup(code).map_or(Fatal, |return_code| return_code.class)
+    }
+}
+
+impl std::fmt::Display for ReturnCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:#x}): {}", self.name, self.code, self.description)
+    }
+}
+
+const fn code(
+    code: u32,
+    name: &'static str,
+    description: &'static str,
+    class: ReturnCodeClass,
+) -> ReturnCode {
+    ReturnCode {
+        code,
+        name,
+        description,
+        class,
+    }
+}
+
+// NB: must stay sorted by code, lookups are a binary search
+// NB: handles are cached per connection, so codes asking for a new handle are treated as a disconnect
+pub const RETURN_CODES: &[ReturnCode] = &[
+    // Global errors
+    code(0x000, "ERR_NOERROR", "No error", Fatal),
+    code(0x001, "ERR_INTERNAL", "Internal error", Fatal),
+    code(0x002, "ERR_NORTIME", "No real-time", Fatal),
+    code(
+        0x003,
+        "ERR_ALLOCLOCKEDMEM",
+        "Allocation locked - memory error",
+        Fatal,
+    ),
+    code(
+        0x004,
+        "ERR_INSERTMAILBOX",
+        "Mailbox full - ADS message could not be sent",
+        Transient,
+    ),
+    code(0x005, "ERR_WRONGRECEIVEHMSG", "Wrong receive HMSG", Fatal),
+    code(
+        0x006,
+        "ERR_TARGETPORTNOTFOUND",
+        "Target port not found, possibly ADS server not started",
+        Disconnect,
+    ),
+    code(
+        0x007,
+        "ERR_TARGETMACHINENOTFOUND",
+        "Target machine not found, possibly missing ADS routes",
+        Disconnect,
+    ),
+    code(0x008, "ERR_UNKNOWNCMDID", "Unknown command ID", Fatal),
+    code(0x009, "ERR_BADTASKID", "Invalid task ID", Fatal),
+    code(0x00a, "ERR_NOIO", "No IO", Fatal),
+    code(0x00b, "ERR_UNKNOWNAMSCMD", "Unknown AMS command", Fatal),
+    code(0x00c, "ERR_WIN32ERROR", "Win32 error", Fatal),
+    code(
+        0x00d,
+        "ERR_PORTNOTCONNECTED",
+        "Port not connected",
+        Disconnect,
+    ),
+    code(0x00e, "ERR_INVALIDAMSLENGTH", "Invalid AMS length", Fatal),
+    code(
+        0x00f,
+        "ERR_INVALIDAMSNETID",
+        "Invalid AMS NetID",
+        Configuration,
+    ),
+    code(
+        0x010,
+        "ERR_LOWINSTLEVEL",
+        "Low installation level",
+        Configuration,
+    ),
+    code(
+        0x011,
+        "ERR_NODEBUGINTAVAILABLE",
+        "No debugging available",
+        Configuration,
+    ),
+    code(
+        0x012,
+        "ERR_PORTDISABLED",
+        "Port disabled - system service not started",
+        Disconnect,
+    ),
+    code(
+        0x013,
+        "ERR_PORTALREADYCONNECTED",
+        "Port already connected",
+        Configuration,
+    ),
+    code(0x014, "ERR_AMSSYNC_W32ERROR", "AMS Sync Win32 error", Fatal),
+    code(0x015, "ERR_AMSSYNC_TIMEOUT", "AMS Sync timeout", Transient),
+    code(0x016, "ERR_AMSSYNC_AMSERROR", "AMS Sync error", Fatal),
+    code(
+        0x017,
+        "ERR_AMSSYNC_NOINDEXINMAP",
+        "AMS Sync no index map",
+        Fatal,
+    ),
+    code(
+        0x018,
+        "ERR_INVALIDAMSPORT",
+        "Invalid AMS port",
+        Configuration,
+    ),
+    code(0x019, "ERR_NOMEMORY", "No memory", Fatal),
+    code(0x01a, "ERR_TCPSEND", "TCP send error", Disconnect),
+    code(0x01b, "ERR_HOSTUNREACHABLE", "Host unreachable", Disconnect),
+    code(
+        0x01c,
+        "ERR_INVALIDAMSFRAGMENT",
+        "Invalid AMS fragment",
+        Fatal,
+    ),
+    code(
+        0x01d,
+        "ERR_TLSSEND",
+        "TLS send error - secure ADS connection failed",
+        Disconnect,
+    ),
+    code(
+        0x01e,
+        "ERR_ACCESSDENIED",
+        "Access denied - secure ADS access denied",
+        Configuration,
+    ),
+    // Router errors
+    code(
+        0x500,
+        "ROUTERERR_NOLOCKEDMEMORY",
+        "Router: no locked memory",
+        Fatal,
+    ),
+    code(
+        0x501,
+        "ROUTERERR_RESIZEMEMORY",
+        "Router: memory size could not be changed",
+        Fatal,
+    ),
+    code(
+        0x502,
+        "ROUTERERR_MAILBOXFULL",
+        "Router: mailbox full",
+        Transient,
+    ),
+    code(
+        0x503,
+        "ROUTERERR_DEBUGBOXFULL",
+        "Router: debug mailbox full",
+        Transient,
+    ),
+    code(
+        0x504,
+        "ROUTERERR_UNKNOWNPORTTYPE",
+        "Router: port type is unknown",
+        Configuration,
+    ),
+    code(
+        0x505,
+        "ROUTERERR_NOTINITIALIZED",
+        "Router is not initialized",
+        Disconnect,
+    ),
+    code(
+        0x506,
+        "ROUTERERR_PORTALREADYINUSE",
+        "Router: desired port number is already assigned",
+        Configuration,
+    ),
+    code(
+        0x507,
+        "ROUTERERR_NOTREGISTERED",
+        "Router: port not registered",
+        Disconnect,
+    ),
+    code(
+        0x508,
+        "ROUTERERR_NOMOREQUEUES",
+        "Router: maximum number of ports reached",
+        Fatal,
+    ),
+    code(
+        0x509,
+        "ROUTERERR_INVALIDPORT",
+        "Router: port is invalid",
+        Configuration,
+    ),
+    code(
+        0x50a,
+        "ROUTERERR_NOTACTIVATED",
+        "Router is not active",
+        Disconnect,
+    ),
+    code(
+        0x50b,
+        "ROUTERERR_FRAGMENTBOXFULL",
+        "Router: mailbox full for fragmented messages",
+        Transient,
+    ),
+    code(
+        0x50c,
+        "ROUTERERR_FRAGMENTTIMEOUT",
+        "Router: fragment timeout occurred",
+        Transient,
+    ),
+    code(
+        0x50d,
+        "ROUTERERR_TOBEREMOVED",
+        "Router: port removed",
+        Disconnect,
+    ),
+    // General ADS device errors
+    code(0x700, "ADSERR_DEVICE_ERROR", "General device error", Fatal),
+    code(
+        0x701,
+        "ADSERR_DEVICE_SRVNOTSUPP",
+        "Service is not supported by server",
+        Configuration,
+    ),
+    code(
+        0x702,
+        "ADSERR_DEVICE_INVALIDGRP",
+        "Invalid index group",
+        Configuration,
+    ),
+    code(
+        0x703,
+        "ADSERR_DEVICE_INVALIDOFFSET",
+        "Invalid index offset",
+        Configuration,
+    ),
+    code(
+        0x704,
+        "ADSERR_DEVICE_INVALIDACCESS",
+        "Reading/writing not permitted",
+        Configuration,
+    ),
+    code(
+        0x705,
+        "ADSERR_DEVICE_INVALIDSIZE",
+        "Parameter size not correct",
+        Configuration,
+    ),
+    code(
+        0x706,
+        "ADSERR_DEVICE_INVALIDDATA",
+        "Invalid parameter value(s)",
+        Configuration,
+    ),
+    code(
+        0x707,
+        "ADSERR_DEVICE_NOTREADY",
+        "Device is not in a ready state",
+        Transient,
+    ),
+    code(0x708, "ADSERR_DEVICE_BUSY", "Device is busy", Transient),
+    code(
+        0x709,
+        "ADSERR_DEVICE_INVALIDCONTEXT",
+        "Invalid OS context -> use multi-task data access",
+        Fatal,
+    ),
+    code(0x70a, "ADSERR_DEVICE_NOMEMORY", "Out of memory", Fatal),
+    code(
+        0x70b,
+        "ADSERR_DEVICE_INVALIDPARM",
+        "Invalid parameter value(s)",
+        Configuration,
+    ),
+    code(
+        0x70c,
+        "ADSERR_DEVICE_NOTFOUND",
+        "Not found (files, ...)",
+        Configuration,
+    ),
+    code(
+        0x70d,
+        "ADSERR_DEVICE_SYNTAX",
+        "Syntax error in command or file",
+        Configuration,
+    ),
+    code(
+        0x70e,
+        "ADSERR_DEVICE_INCOMPATIBLE",
+        "Objects do not match",
+        Configuration,
+    ),
+    code(
+        0x70f,
+        "ADSERR_DEVICE_EXISTS",
+        "Object already exists",
+        Configuration,
+    ),
+    code(
+        0x710,
+        "ADSERR_DEVICE_SYMBOLNOTFOUND",
+        "Symbol not found",
+        Configuration,
+    ),
+    code(
+        0x711,
+        "ADSERR_DEVICE_SYMBOLVERSIONINVALID",
+        "Symbol version invalid -> create a new handle",
+        Disconnect,
+    ),
+    code(
+        0x712,
+        "ADSERR_DEVICE_INVALIDSTATE",
+        "Server is in an invalid state",
+        Fatal,
+    ),
+    code(
+        0x713,
+        "ADSERR_DEVICE_TRANSMODENOTSUPP",
+        "AdsTransMode not supported",
+        Configuration,
+    ),
+    code(
+        0x714,
+        "ADSERR_DEVICE_NOTIFYHNDINVALID",
+        "Notification handle is invalid",
+        Configuration,
+    ),
+    code(
+        0x715,
+        "ADSERR_DEVICE_CLIENTUNKNOWN",
+        "Notification client not registered",
+        Disconnect,
+    ),
+    code(
+        0x716,
+        "ADSERR_DEVICE_NOMOREHDLS",
+        "No more notification handles",
+        Transient,
+    ),
+    code(
+        0x717,
+        "ADSERR_DEVICE_INVALIDWATCHSIZE",
+        "Notification size too large",
+        Configuration,
+    ),
+    code(
+        0x718,
+        "ADSERR_DEVICE_NOTINIT",
+        "Device not initialized",
+        Transient,
+    ),
+    code(
+        0x719,
+        "ADSERR_DEVICE_TIMEOUT",
+        "Device has a timeout",
+        Transient,
+    ),
+    code(
+        0x71a,
+        "ADSERR_DEVICE_NOINTERFACE",
+        "Query interface failed",
+        Configuration,
+    ),
+    code(
+        0x71b,
+        "ADSERR_DEVICE_INVALIDINTERFACE",
+        "Wrong interface required",
+        Configuration,
+    ),
+    code(
+        0x71c,
+        "ADSERR_DEVICE_INVALIDCLSID",
+        "Class ID is invalid",
+        Configuration,
+    ),
+    code(
+        0x71d,
+        "ADSERR_DEVICE_INVALIDOBJID",
+        "Object ID is invalid",
+        Configuration,
+    ),
+    code(
+        0x71e,
+        "ADSERR_DEVICE_PENDING",
+        "Request is pending",
+        Transient,
+    ),
+    code(
+        0x71f,
+        "ADSERR_DEVICE_ABORTED",
+        "Request is aborted",
+        Transient,
+    ),
+    code(0x720, "ADSERR_DEVICE_WARNING", "Signal warning", Transient),
+    code(
+        0x721,
+        "ADSERR_DEVICE_INVALIDARRAYIDX",
+        "Invalid array index",
+        Configuration,
+    ),
+    code(
+        0x722,
+        "ADSERR_DEVICE_SYMBOLNOTACTIVE",
+        "Symbol not active -> release handle and try again",
+        Disconnect,
+    ),
+    code(
+        0x723,
+        "ADSERR_DEVICE_ACCESSDENIED",
+        "Access denied",
+        Configuration,
+    ),
+    code(
+        0x724,
+        "ADSERR_DEVICE_LICENSENOTFOUND",
+        "No license found -> activate license",
+        Configuration,
+    ),
+    code(
+        0x725,
+        "ADSERR_DEVICE_LICENSEEXPIRED",
+        "License expired",
+        Configuration,
+    ),
+    code(
+        0x726,
+        "ADSERR_DEVICE_LICENSEEXCEEDED",
+        "License exceeded",
+        Configuration,
+    ),
+    code(
+        0x727,
+        "ADSERR_DEVICE_LICENSEINVALID",
+        "License invalid",
+        Configuration,
+    ),
+    code(
+        0x728,
+        "ADSERR_DEVICE_LICENSESYSTEMID",
+        "Invalid system ID in license",
+        Configuration,
+    ),
+    code(
+        0x729,
+        "ADSERR_DEVICE_LICENSENOTIMELIMIT",
+        "License not time limited",
+        Configuration,
+    ),
+    code(
+        0x72a,
+        "ADSERR_DEVICE_LICENSEFUTUREISSUE",
+        "License issue time in the future",
+        Configuration,
+    ),
+    code(
+        0x72b,
+        "ADSERR_DEVICE_LICENSETIMETOLONG",
+        "License time period too long",
+        Configuration,
+    ),
+    code(
+        0x72c,
+        "ADSERR_DEVICE_EXCEPTION",
+        "Exception in device specific code -> check each device",
+        Fatal,
+    ),
+    code(
+        0x72d,
+        "ADSERR_DEVICE_LICENSEDUPLICATED",
+        "License file read twice",
+        Configuration,
+    ),
+    code(
+        0x72e,
+        "ADSERR_DEVICE_SIGNATUREINVALID",
+        "Invalid signature",
+        Configuration,
+    ),
+    code(
+        0x72f,
+        "ADSERR_DEVICE_CERTIFICATEINVALID",
+        "Invalid public key certificate",
+        Configuration,
+    ),
+    code(
+        0x730,
+        "ADSERR_DEVICE_LICENSEOEMNOTFOUND",
+        "Public key not known from OEM",
+        Configuration,
+    ),
+    code(
+        0x731,
+        "ADSERR_DEVICE_LICENSERESTRICTED",
+        "License not valid for this system ID",
+        Configuration,
+    ),
+    code(
+        0x732,
+        "ADSERR_DEVICE_LICENSEDEMODENIED",
+        "Demo license prohibited",
+        Configuration,
+    ),
+    code(
+        0x733,
+        "ADSERR_DEVICE_INVALIDFNCID",
+        "Invalid function ID",
+        Configuration,
+    ),
+    code(
+        0x734,
+        "ADSERR_DEVICE_OUTOFRANGE",
+        "Outside the valid range",
+        Configuration,
+    ),
+    code(
+        0x735,
+        "ADSERR_DEVICE_INVALIDALIGNMENT",
+        "Invalid alignment",
+        Configuration,
+    ),
+    code(
+        0x736,
+        "ADSERR_DEVICE_LICENSEPLATFORM",
+        "Invalid platform level",
+        Configuration,
+    ),
+    code(
+        0x737,
+        "ADSERR_DEVICE_FORWARD_PL",
+        "Context - forward to passive level",
+        Fatal,
+    ),
+    code(
+        0x738,
+        "ADSERR_DEVICE_FORWARD_DL",
+        "Context - forward to dispatch level",
+        Fatal,
+    ),
+    code(
+        0x739,
+        "ADSERR_DEVICE_FORWARD_RT",
+        "Context - forward to real-time",
+        Fatal,
+    ),
+    // ADS client errors
+    code(0x740, "ADSERR_CLIENT_ERROR", "General client error", Fatal),
+    code(
+        0x741,
+        "ADSERR_CLIENT_INVALIDPARM",
+        "Invalid parameter at service",
+        Configuration,
+    ),
+    code(
+        0x742,
+        "ADSERR_CLIENT_LISTEMPTY",
+        "Polling list is empty",
+        Configuration,
+    ),
+    code(
+        0x743,
+        "ADSERR_CLIENT_VARUSED",
+        "Var connection already in use",
+        Configuration,
+    ),
+    code(
+        0x744,
+        "ADSERR_CLIENT_DUPLINVOKEID",
+        "Invoke ID in use",
+        Transient,
+    ),
+    code(
+        0x745,
+        "ADSERR_CLIENT_SYNCTIMEOUT",
+        "Timeout elapsed -> check route setting",
+        Disconnect,
+    ),
+    code(
+        0x746,
+        "ADSERR_CLIENT_W32ERROR",
+        "Error in Win32 subsystem",
+        Fatal,
+    ),
+    code(
+        0x747,
+        "ADSERR_CLIENT_TIMEOUTINVALID",
+        "Invalid client timeout value",
+        Configuration,
+    ),
+    code(
+        0x748,
+        "ADSERR_CLIENT_PORTNOTOPEN",
+        "ADS port not opened",
+        Disconnect,
+    ),
+    code(
+        0x749,
+        "ADSERR_CLIENT_NOAMSADDR",
+        "No AMS address",
+        Disconnect,
+    ),
+    code(
+        0x750,
+        "ADSERR_CLIENT_SYNCINTERNAL",
+        "Internal error in ADS sync",
+        Fatal,
+    ),
+    code(0x751, "ADSERR_CLIENT_ADDHASH", "Hash table overflow", Fatal),
+    code(
+        0x752,
+        "ADSERR_CLIENT_REMOVEHASH",
+        "Key not found in hash",
+        Fatal,
+    ),
+    code(
+        0x753,
+        "ADSERR_CLIENT_NOMORESYM",
+        "No more symbols in cache",
+        Fatal,
+    ),
+    code(
+        0x754,
+        "ADSERR_CLIENT_SYNCRESINVALID",
+        "Invalid response received",
+        Disconnect,
+    ),
+    code(
+        0x755,
+        "ADSERR_CLIENT_SYNCPORTLOCKED",
+        "Sync port is locked",
+        Transient,
+    ),
+    code(
+        0x756,
+        "ADSERR_CLIENT_REQUESTCANCELLED",
+        "Request was cancelled",
+        Transient,
+    ),
+    // RTIME errors
+    code(
+        0x1000,
+        "RTERR_INTERNAL",
+        "Internal error in real-time system",
+        Fatal,
+    ),
+    code(
+        0x1001,
+        "RTERR_BADTIMERPERIODS",
+        "Timer value not valid",
+        Configuration,
+    ),
+    code(
+        0x1002,
+        "RTERR_INVALIDTASKPTR",
+        "Task pointer has invalid value 0",
+        Fatal,
+    ),
+    code(
+        0x1003,
+        "RTERR_INVALIDSTACKPTR",
+        "Stack pointer has invalid value 0",
+        Fatal,
+    ),
+    code(
+        0x1004,
+        "RTERR_PRIOEXISTS",
+        "Requested task priority already assigned",
+        Configuration,
+    ),
+    code(
+        0x1005,
+        "RTERR_NOMORETCB",
+        "No free Task Control Block",
+        Fatal,
+    ),
+    code(0x1006, "RTERR_NOMORESEMAS", "No free semaphores", Fatal),
+    code(
+        0x1007,
+        "RTERR_NOMOREQUEUES",
+        "No free space in the queue",
+        Transient,
+    ),
+    code(
+        0x100d,
+        "RTERR_EXTIRQALREADYDEF",
+        "External sync interrupt already applied",
+        Configuration,
+    ),
+    code(
+        0x100e,
+        "RTERR_EXTIRQNOTDEF",
+        "No external sync interrupt applied",
+        Configuration,
+    ),
+    code(
+        0x100f,
+        "RTERR_EXTIRQINSTALLFAILED",
+        "External sync interrupt application failed",
+        Fatal,
+    ),
+    code(
+        0x1010,
+        "RTERR_IRQLNOTLESSOREQUAL",
+        "Call of service function in wrong context",
+        Fatal,
+    ),
+    code(
+        0x1017,
+        "RTERR_VMXNOTSUPPORTED",
+        "Intel VT-x not supported",
+        Configuration,
+    ),
+    code(
+        0x1018,
+        "RTERR_VMXDISABLED",
+        "Intel VT-x not enabled in BIOS",
+        Configuration,
+    ),
+    code(
+        0x1019,
+        "RTERR_VMXCONTROLSMISSING",
+        "Missing function in Intel VT-x",
+        Configuration,
+    ),
+    code(
+        0x101a,
+        "RTERR_VMXENABLEFAILS",
+        "Activation of Intel VT-x failed",
+        Configuration,
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_sorted_and_unique() {
+        assert!(RETURN_CODES
+            .windows(2)
+            .all(|pair| pair[0].code < pair[1].code));
+    }
+
+    #[test]
+    fn known_codes_are_found() {
+        let return_code = ReturnCode::lookup(0x710).unwrap();
+
+        assert_eq!(return_code.name, "ADSERR_DEVICE_SYMBOLNOTFOUND");
+        assert_eq!(return_code.class, Configuration);
+    }
+
+    #[test]
+    fn unknown_codes_are_fatal() {
+        assert!(ReturnCode::lookup(0x7FF).is_none());
+        assert_eq!(ReturnCode::classify(0x7FF), Fatal);
+    }
+
+    #[test]
+    fn lost_targets_disconnect() {
+        assert_eq!(ReturnCode::classify(0x006), Disconnect);
+        assert_eq!(ReturnCode::classify(0x50A), Disconnect);
+    }
+}
@@ -0,0 +1,164 @@
+use std::{fmt, time::Duration};
+
+use crate::{
+    data_types::PlcDataType,
+    error::PlcResult,
+    plc_connection::PlcConnection,
+    subscription::{Subscription, SubscriptionOptions},
+    symbol::Symbol,
+};
+
+/// A view of a connection that resolves symbol paths relative to a prefix, e.g: one function block instance.
+///
+/// Paths are joined with a dot, except for array indices (`[1]`) and RPC methods (`#Start`), so the same code can be
+/// pointed at `GVL.aStations[1].fbDrive` or `GVL.aStations[2].fbDrive`.
+#[derive(Clone)]
+pub struct Scope {
+    connection: PlcConnection,
+    prefix: String,
+}
+
+impl Scope {
+    pub(crate) fn new(connection: PlcConnection, prefix: &str) -> Self {
+        Self {
+            connection,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    pub fn connection(&self) -> &PlcConnection {
+        &self.connection
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The full path of a symbol relative to this scope.
+    pub fn path(&self, relative: &str) -> String {
+        join(&self.prefix, relative)
+    }
+
+    /// A nested scope, relative to this one.
+    pub fn scope(&self, relative: &str) -> Scope {
+        Scope::new(self.connection.clone(), &self.path(relative))
+    }
+
+    /// A scope for an element of the array this scope points at.
+    pub fn index(&self, index: i64) -> Scope {
+        self.indices(&[index])
+    }
+
+    /// A scope for an element of the multi-dimensional array this scope points at.
+    pub fn indices(&self, indices: &[i64]) -> Scope {
+        self.scope(&format_indices(indices))
+    }
+
+    pub fn symbol<T: PlcDataType>(&self, relative: &str) -> Symbol<T> {
+        self.connection.symbol(&self.path(relative))
+    }
+
+    pub fn read_symbol<T: PlcDataType>(&self, relative: &str) -> PlcResult<T> {
+        self.connection.read_symbol(&self.path(relative))
+    }
+
+    pub fn write_symbol<T: PlcDataType>(&self, relative: &str, value: T) -> PlcResult<()> {
+        self.connection.write_symbol(&self.path(relative), value)
+    }
+
+    pub fn subscribe_with_options<T: PlcDataType>(
+        &self,
+        relative: &str,
+        options: SubscriptionOptions<T>,
+    ) -> PlcResult<Subscription<T>> {
+        self.connection
+            .subscribe_with_options(&self.path(relative), options)
+    }
+
+    pub fn wait_until<T: PlcDataType>(
+        &self,
+        relative: &str,
+        predicate: impl FnMut(&T) -> bool,
+        timeout: Duration,
+    ) -> PlcResult<T> {
+        self.connection
+            .wait_until(&self.path(relative), predicate, timeout)
+    }
+
+    pub fn invoke_rpc_method(&self, relative: &str) -> PlcResult<()> {
+        self.connection.invoke_rpc_method(&self.path(relative))
+    }
+
+    pub fn invoke_rpc_method_with_params(&self, relative: &str, params: &[&[u8]]) -> PlcResult<()> {
+        self.connection
+            .invoke_rpc_method_with_params(&self.path(relative), params)
+    }
+
+    pub fn call_rpc_method<R: PlcDataType>(
+        &self,
+        relative: &str,
+        params: &[&[u8]],
+    ) -> PlcResult<R> {
+        self.connection
+            .call_rpc_method(&self.path(relative), params)
+    }
+
+    pub fn fetch_from_rpc_method<T: PlcDataType>(&self, relative: &str) -> PlcResult<T> {
+        self.connection.fetch_from_rpc_method(&self.path(relative))
+    }
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Scope").field(&self.prefix).finish()
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.prefix)
+    }
+}
+
+fn join(prefix: &str, relative: &str) -> String {
+    if prefix.is_empty() {
+        return relative.to_string();
+    }
+
+    if relative.is_empty() || relative.starts_with(['[', '#', '.']) {
+        return format!("{prefix}{relative}");
+    }
+
+    format!("{prefix}.{relative}")
+}
+
+fn format_indices(indices: &[i64]) -> String {
+    let indices: Vec<_> = indices.iter().map(i64::to_string).collect();
+
+    format!("[{}]", indices.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_join_with_a_dot() {
+        assert_eq!(join("GVL.fbDrive", "fSpeed"), "GVL.fbDrive.fSpeed");
+        assert_eq!(join("", "MAIN.fSpeed"), "MAIN.fSpeed");
+        assert_eq!(join("GVL.fbDrive", ""), "GVL.fbDrive");
+    }
+
+    #[test]
+    fn indices_and_methods_join_without_a_dot() {
+        assert_eq!(join("GVL.aStations", "[1]"), "GVL.aStations[1]");
+        assert_eq!(join("GVL.fbDrive", "#Start"), "GVL.fbDrive#Start");
+        assert_eq!(join("GVL.fbDrive", ".fSpeed"), "GVL.fbDrive.fSpeed");
+    }
+
+    #[test]
+    fn indices_are_comma_separated() {
+        assert_eq!(format_indices(&[3]), "[3]");
+        assert_eq!(format_indices(&[1, -2]), "[1,-2]");
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::{Duration, Instant};
+
+/// A stage that decides whether a notification sample is delivered to a subscription.
+pub(crate) trait SampleFilter: Send {
+    /// Called for every received sample, returns whether it should be delivered.
+    fn admits(&mut self, data: &[u8], received_at: Instant) -> bool;
+
+    /// Called for every sample that passed all filters and was delivered.
+    fn delivered(&mut self, _data: &[u8], _received_at: Instant) {}
+}
+
+/// Which transitions of a boolean symbol are delivered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// FALSE to TRUE.
+    Rising,
+    /// TRUE to FALSE.
+    Falling,
+    Both,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum DeadbandKind {
+    Absolute(f64),
+    /// Relative to the range the value spans, e.g: 10 for a 0 to 10 V input, so it does not shrink near zero.
+    Percent {
+        percent: f64,
+        range: f64,
+    },
+}
+
+/// Only delivers samples that moved far enough from the last delivered value.
+pub(crate) struct Deadband {
+    kind: DeadbandKind,
+    decode: fn(&[u8]) -> Option<f64>,
+    last_delivered: Option<f64>,
+}
+
+impl Deadband {
+    pub(crate) fn new(kind: DeadbandKind, decode: fn(&[u8]) -> Option<f64>) -> Self {
+        Self {
+            kind,
+            decode,
+            last_delivered: None,
+        }
+    }
+}
+
+impl SampleFilter for Deadband {
+    fn admits(&mut self, data: &[u8], _received_at: Instant) -> bool {
+        let (Some(value), Some(last_delivered)) = ((self.decode)(data), self.last_delivered) else {
+            return true;
+        };
+
+        let distance = (value - last_delivered).abs();
+
+        let deadband = match self.kind {
+            DeadbandKind::Absolute(deadband) => deadband,
+            DeadbandKind::Percent { percent, range } => range.abs() * percent / 100.0,
+        };
+
+        // NB: strictly more, so unchanged values are not delivered even with a deadband of 0
+        distance > deadband || value.is_nan() != last_delivered.is_nan()
+    }
+
+    fn delivered(&mut self, data: &[u8], _received_at: Instant) {
+        self.last_delivered = (self.decode)(data);
+    }
+}
+
+/// Delivers at most one sample per interval, dropping samples in between.
+pub(crate) struct MinInterval {
+    interval: Duration,
+    last_delivered: Option<Instant>,
+}
+
+impl MinInterval {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_delivered: None,
+        }
+    }
+}
+
+impl SampleFilter for MinInterval {
+    fn admits(&mut self, _data: &[u8], received_at: Instant) -> bool {
+        self.last_delivered.is_none_or(|last_delivered| {
+            received_at.duration_since(last_delivered) >= self.interval
+        })
+    }
+
+    fn delivered(&mut self, _data: &[u8], received_at: Instant) {
+        self.last_delivered = Some(received_at);
+    }
+}
+
+/// Delivers boolean samples that complete an edge. The first sample is never an edge, as there is nothing to compare it to.
+pub(crate) struct EdgeFilter {
+    edge: Edge,
+    last_seen: Option<bool>,
+}
+
+impl EdgeFilter {
+    pub(crate) fn new(edge: Edge) -> Self {
+        Self {
+            edge,
+            last_seen: None,
+        }
+    }
+}
+
+impl SampleFilter for EdgeFilter {
+    fn admits(&mut self, data: &[u8], _received_at: Instant) -> bool {
+        let Some(value) = data.first().map(|byte| *byte != 0) else {
+            return false;
+        };
+
+        let previous = self.last_seen.replace(value);
+
+        matches!(
+            (previous, value, self.edge),
+            (Some(false), true, Edge::Rising | Edge::Both)
+                | (Some(true), false, Edge::Falling | Edge::Both)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_f32(data: &[u8]) -> Option<f64> {
+        Some(f32::from_le_bytes(data.try_into().ok()?).into())
+    }
+
+    fn run(filter: &mut dyn SampleFilter, samples: &[(f32, u64)]) -> Vec<f32> {
+        let start = Instant::now();
+
+        samples
+            .iter()
+            .filter_map(|(value, millis)| {
+                let data = value.to_le_bytes();
+                let received_at = start + Duration::from_millis(*millis);
+
+                filter.admits(&data, received_at).then(|| {
+                    filter.delivered(&data, received_at);
+
+                    *value
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn absolute_deadband() {
+        let mut filter = Deadband::new(DeadbandKind::Absolute(0.5), decode_f32);
+
+        let delivered = run(
+            &mut filter,
+            &[(1.0, 0), (1.2, 0), (1.5, 0), (1.6, 0), (1.2, 0), (1.0, 0)],
+        );
+
+        assert_eq!(delivered, vec![1.0, 1.6, 1.0]);
+    }
+
+    #[test]
+    fn percent_deadband() {
+        let percent = |percent| {
+            Deadband::new(
+                DeadbandKind::Percent {
+                    percent,
+                    range: 100.0,
+                },
+                decode_f32,
+            )
+        };
+
+        // Noise around zero stays within the deadband
+        let delivered = run(
+            &mut percent(10.0),
+            &[
+                (0.0, 0),
+                (0.5, 0),
+                (-0.3, 0),
+                (10.0, 0),
+                (10.5, 0),
+                (20.6, 0),
+            ],
+        );
+
+        assert_eq!(delivered, vec![0.0, 10.5, 20.6]);
+    }
+
+    #[test]
+    fn zero_deadbands_drop_unchanged_values() {
+        let samples = [(1.0, 0), (1.0, 0), (1.5, 0), (1.5, 0)];
+
+        let mut absolute = Deadband::new(DeadbandKind::Absolute(0.0), decode_f32);
+
+        assert_eq!(run(&mut absolute, &samples), vec![1.0, 1.5]);
+
+        let mut percent = Deadband::new(
+            DeadbandKind::Percent {
+                percent: 0.0,
+                range: 100.0,
+            },
+            decode_f32,
+        );
+
+        assert_eq!(run(&mut percent, &samples), vec![1.0, 1.5]);
+    }
+
+    #[test]
+    fn min_interval() {
+        let mut filter = MinInterval::new(Duration::from_millis(100));
+
+        let delivered = run(
+            &mut filter,
+            &[(1.0, 0), (2.0, 50), (3.0, 100), (4.0, 150), (5.0, 250)],
+        );
+
+        assert_eq!(delivered, vec![1.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn edges() {
+        let samples = [false, true, true, false, true, false];
+
+        let delivered = |edge| {
+            let mut filter = EdgeFilter::new(edge);
+            let now = Instant::now();
+
+            samples
+                .iter()
+                .filter(|value| filter.admits(&[u8::from(**value)], now))
+                .copied()
+                .collect::<Vec<bool>>()
+        };
+
+        assert_eq!(delivered(Edge::Rising), vec![true, true]);
+        assert_eq!(delivered(Edge::Falling), vec![false, false]);
+        assert_eq!(delivered(Edge::Both), vec![true, false, true, false]);
+    }
+}
@@ -0,0 +1,592 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use ads::notif::Notification;
+use crossbeam_channel::{unbounded, Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError};
+
+use crate::{
+    data_types::{primitives::bool::PlcBool, PlcDataType, PlcNumeric},
+    plc_connection::PlcConnection,
+};
+
+use self::filter::{Deadband, DeadbandKind, EdgeFilter, MinInterval, SampleFilter};
+
+pub use self::filter::Edge;
+
+mod filter;
+
+/// What happens to notification samples that arrive faster than a subscription consumes them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryPolicy {
+    /// Queue every sample. Memory grows without limit if the consumer stalls.
+    Unbounded,
+    /// Queue up to the given number of samples, then block notification delivery until the consumer catches up.
+    ///
+    /// NB: all subscriptions on the connection share one delivery thread, so a full queue delays every subscription.
+    BoundedBlock(usize),
+    /// Queue up to the given number of samples, discarding the oldest queued sample to make room for a new one.
+    DropOldest(usize),
+    /// Keep only the most recent sample.
+    CoalesceLatest,
+}
+
+/// How a subscription's samples are filtered and delivered.
+///
+/// Filters are applied before delivery, so samples they reject never reach the queue.
+pub struct SubscriptionOptions<T> {
+    delivery_policy: DeliveryPolicy,
+    filters: Vec<Box<dyn SampleFilter>>,
+    data_type: PhantomData<fn() -> T>,
+}
+
+impl<T: PlcDataType> SubscriptionOptions<T> {
+    pub fn new() -> Self {
+        Self {
+            delivery_policy: DeliveryPolicy::Unbounded,
+            filters: Vec::new(),
+            data_type: PhantomData,
+        }
+    }
+
+    pub fn with_delivery_policy(self, delivery_policy: DeliveryPolicy) -> Self {
+        Self {
+            delivery_policy,
+            ..self
+        }
+    }
+
+    /// Delivers at most one sample per interval, dropping any samples in between.
+    pub fn with_min_interval(self, interval: Duration) -> Self {
+        self.with_filter(MinInterval::new(interval))
+    }
+
+    fn with_filter(mut self, filter: impl SampleFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+
+        self
+    }
+}
+
+impl<T: PlcNumeric> SubscriptionOptions<T> {
+    /// Only delivers samples that differ from the last delivered value by more than the deadband.
+    pub fn with_absolute_deadband(self, deadband: f64) -> Self {
+        self.with_filter(Deadband::new(DeadbandKind::Absolute(deadband), |data| {
+            T::from_bytes(data).map(|value| value.to_f64())
+        }))
+    }
+
+    /// Only delivers samples that differ from the last delivered value by more than the given percentage of the range
+    /// the value spans, e.g: 10 for a 0 to 10 V input.
+    pub fn with_percent_deadband(self, percent: f64, range: f64) -> Self {
+        self.with_filter(Deadband::new(
+            DeadbandKind::Percent { percent, range },
+            |data| T::from_bytes(data).map(|value| value.to_f64()),
+        ))
+    }
+}
+
+impl SubscriptionOptions<PlcBool> {
+    /// Only delivers samples that complete the given edge.
+    pub fn with_edge(self, edge: Edge) -> Self {
+        self.with_filter(EdgeFilter::new(edge))
+    }
+}
+
+impl<T: PlcDataType> Default for SubscriptionOptions<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Delivery counters for a single subscription.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubscriptionStats {
+    /// Samples received from the PLC.
+    pub received: u64,
+    /// Samples rejected by the subscription's filters.
+    pub filtered: u64,
+    /// Samples discarded before the consumer saw them, due to the delivery policy.
+    pub overflowed: u64,
+    /// Times notification delivery had to wait for the consumer, due to the delivery policy.
+    pub blocked: u64,
+}
+
+/// A subscription to a symbol's notifications, with its own delivery queue.
+///
+/// The subscription is closed when the PLC connection drops, after which any queued samples can still be received.
+/// Dropping the subscription deletes the notification on the PLC.
+pub struct Subscription<T> {
+    notification_handle: u32,
+    queue: Arc<SampleQueue>,
+    // None for subscriptions fed by something other than a PLC connection, e.g: a fake PLC
+    connection: Option<PlcConnection>,
+    data_type: PhantomData<fn() -> T>,
+}
+
+impl<T: PlcDataType> Subscription<T> {
+    pub(crate) fn new(
+        notification_handle: u32,
+        queue: Arc<SampleQueue>,
+        connection: PlcConnection,
+    ) -> Self {
+        Self {
+            notification_handle,
+            queue,
+            connection: Some(connection),
+            data_type: PhantomData,
+        }
+    }
+
+    /// A subscription whose samples are pushed to the queue by the caller, rather than a PLC connection.
+    #[cfg(feature = "testing")]
+    pub(crate) fn detached(notification_handle: u32, queue: Arc<SampleQueue>) -> Self {
+        Self {
+            notification_handle,
+            queue,
+            connection: None,
+            data_type: PhantomData,
+        }
+    }
+
+    pub fn notification_handle(&self) -> u32 {
+        self.notification_handle
+    }
+
+    /// Blocks until a sample is available, or returns an error once the subscription is closed and empty.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            let data = self.queue.pop(None).map_err(|_| RecvError)?;
+
+            if let Some(value) = T::from_bytes(&data) {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let data = self.queue.pop(Some(deadline))?;
+
+            if let Some(value) = T::from_bytes(&data) {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        loop {
+            let data = self.queue.try_pop()?;
+
+            if let Some(value) = T::from_bytes(&data) {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> SubscriptionStats {
+        self.queue.stats()
+    }
+
+    /// Number of samples lost to the delivery policy, useful for alarming on a slow consumer.
+    pub fn overflow_count(&self) -> u64 {
+        self.queue.stats().overflowed
+    }
+
+    /// When the most recently queued sample arrived from the PLC.
+    pub(crate) fn last_queued_at(&self) -> Option<Instant> {
+        self.queue.last_queued_at()
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.queue.close();
+
+        if let Some(connection) = &self.connection {
+            connection.unsubscribe_queue(self.notification_handle, &self.queue);
+        }
+    }
+}
+
+pub(crate) struct SampleQueue {
+    policy: DeliveryPolicy,
+    filters: Mutex<Vec<Box<dyn SampleFilter>>>,
+    state: Mutex<SampleQueueState>,
+    sample_available: Condvar,
+    space_available: Condvar,
+}
+
+#[derive(Default)]
+struct SampleQueueState {
+    samples: VecDeque<Vec<u8>>,
+    last_queued_at: Option<Instant>,
+    closed: bool,
+    stats: SubscriptionStats,
+}
+
+impl SampleQueue {
+    pub(crate) fn new<T>(options: SubscriptionOptions<T>) -> Arc<Self> {
+        Arc::new(Self {
+            policy: options.delivery_policy,
+            filters: Mutex::new(options.filters),
+            state: Default::default(),
+            sample_available: Condvar::new(),
+            space_available: Condvar::new(),
+        })
+    }
+
+    pub(crate) fn push(&self, data: Vec<u8>) {
+        let received_at = Instant::now();
+
+        // NB: held for the whole push, so samples pass the filters in the order they are queued. Callers pushing from
+        // several threads, e.g: a fake PLC, keep their own pushes in order
+        let mut filters = self.filters.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+
+        if state.closed {
+            return;
+        }
+
+        state.stats.received += 1;
+
+        // Every filter sees every sample, as some of them track the previous value
+        let admitted = filters.iter_mut().fold(true, |admitted, filter| {
+            filter.admits(&data, received_at) & admitted
+        });
+
+        if !admitted {
+            state.stats.filtered += 1;
+
+            return;
+        }
+
+        for filter in filters.iter_mut() {
+            filter.delivered(&data, received_at);
+        }
+
+        match self.policy {
+            DeliveryPolicy::Unbounded => {}
+            DeliveryPolicy::BoundedBlock(capacity) => {
+                if state.samples.len() >= capacity.max(1) {
+                    state.stats.blocked += 1;
+                }
+
+                while state.samples.len() >= capacity.max(1) && !state.closed {
+                    state = self.space_available.wait(state).unwrap();
+                }
+
+                if state.closed {
+                    return;
+                }
+            }
+            DeliveryPolicy::DropOldest(capacity) => {
+                while state.samples.len() >= capacity.max(1) {
+                    state.samples.pop_front();
+                    state.stats.overflowed += 1;
+                }
+            }
+            DeliveryPolicy::CoalesceLatest => {
+                state.stats.overflowed += state.samples.len() as u64;
+                state.samples.clear();
+            }
+        }
+
+        state.samples.push_back(data);
+        state.last_queued_at = Some(received_at);
+
+        self.sample_available.notify_one();
+    }
+
+    fn pop(&self, deadline: Option<Instant>) -> Result<Vec<u8>, RecvTimeoutError> {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(data) = state.samples.pop_front() {
+                self.space_available.notify_one();
+
+                return Ok(data);
+            }
+
+            if state.closed {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            state = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+
+                    if timeout.is_zero() {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+
+                    self.sample_available
+                        .wait_timeout(state, timeout)
+                        .unwrap()
+                        .0
+                }
+                None => self.sample_available.wait(state).unwrap(),
+            };
+        }
+    }
+
+    fn try_pop(&self) -> Result<Vec<u8>, TryRecvError> {
+        let mut state = self.state.lock().unwrap();
+
+        match state.samples.pop_front() {
+            Some(data) => {
+                self.space_available.notify_one();
+
+                Ok(data)
+            }
+            None if state.closed => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    fn stats(&self) -> SubscriptionStats {
+        self.state.lock().unwrap().stats
+    }
+
+    fn last_queued_at(&self) -> Option<Instant> {
+        self.state.lock().unwrap().last_queued_at
+    }
+
+    #[cfg(feature = "testing")]
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+
+        self.sample_available.notify_all();
+        self.space_available.notify_all();
+    }
+}
+
+/// Routes samples from a client's notification channel to the subscription they belong to.
+///
+/// Notifications with samples that do not belong to any subscription are forwarded whole to the unrouted channel,
+/// which backs `PlcConnection::notification_receiver`.
+pub(crate) struct NotificationRouter {
+    routes: Mutex<HashMap<u32, Arc<SampleQueue>>>,
+    unrouted_sender: Sender<Notification>,
+    unrouted_receiver: Receiver<Notification>,
+}
+
+impl NotificationRouter {
+    /// Starts delivering notifications on a background thread, which exits once the notification channel closes.
+    pub(crate) fn start(notifications: Receiver<Notification>) -> Arc<Self> {
+        let (unrouted_sender, unrouted_receiver) = unbounded();
+
+        let router = Arc::new(Self {
+            routes: Default::default(),
+            unrouted_sender,
+            unrouted_receiver,
+        });
+
+        {
+            let router = router.clone();
+
+            std::thread::spawn(move || router.run(notifications));
+        }
+
+        router
+    }
+
+    fn run(&self, notifications: Receiver<Notification>) {
+        for notification in notifications {
+            let mut unrouted = false;
+
+            for sample in notification.samples() {
+                let queue = self.routes.lock().unwrap().get(&sample.handle).cloned();
+
+                match queue {
+                    Some(queue) => queue.push(sample.data.to_vec()),
+                    None => unrouted = true,
+                }
+            }
+
+            if unrouted {
+                self.unrouted_sender.send(notification).ok();
+            }
+        }
+    }
+
+    /// Locks the routing table. Holding the lock while adding a notification makes sure its first sample is routed.
+    pub(crate) fn routes(&self) -> MutexGuard<'_, HashMap<u32, Arc<SampleQueue>>> {
+        self.routes.lock().unwrap()
+    }
+
+    /// Removes a route, returning false if the handle was not routed to this queue.
+    pub(crate) fn remove_route(&self, notification_handle: u32, queue: &Arc<SampleQueue>) -> bool {
+        let mut routes = self.routes();
+
+        match routes.get(&notification_handle) {
+            Some(routed_queue) if Arc::ptr_eq(routed_queue, queue) => {
+                routes.remove(&notification_handle);
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn unrouted_receiver(&self) -> Receiver<Notification> {
+        self.unrouted_receiver.clone()
+    }
+
+    /// Closes every subscription, used when the connection drops.
+    pub(crate) fn close(&self) {
+        for (_, queue) in self.routes().drain() {
+            queue.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(policy: DeliveryPolicy) -> Arc<SampleQueue> {
+        SampleQueue::new(SubscriptionOptions::<PlcBool>::new().with_delivery_policy(policy))
+    }
+
+    fn drain(queue: &SampleQueue) -> Vec<u8> {
+        std::iter::from_fn(|| queue.try_pop().ok())
+            .map(|data| data[0])
+            .collect()
+    }
+
+    #[test]
+    fn unbounded_queues_every_sample() {
+        let queue = queue(DeliveryPolicy::Unbounded);
+
+        for i in 0..100 {
+            queue.push(vec![i]);
+        }
+
+        assert_eq!(drain(&queue), (0..100).collect::<Vec<u8>>());
+        assert_eq!(queue.stats().overflowed, 0);
+    }
+
+    #[test]
+    fn drop_oldest_keeps_most_recent_samples() {
+        let queue = queue(DeliveryPolicy::DropOldest(3));
+
+        for i in 0..5 {
+            queue.push(vec![i]);
+        }
+
+        assert_eq!(drain(&queue), vec![2, 3, 4]);
+        assert_eq!(
+            queue.stats(),
+            SubscriptionStats {
+                received: 5,
+                filtered: 0,
+                overflowed: 2,
+                blocked: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn coalesce_latest_keeps_last_sample() {
+        let queue = queue(DeliveryPolicy::CoalesceLatest);
+
+        for i in 0..5 {
+            queue.push(vec![i]);
+        }
+
+        assert_eq!(drain(&queue), vec![4]);
+        assert_eq!(queue.stats().overflowed, 4);
+    }
+
+    #[test]
+    fn bounded_block_waits_for_consumer() {
+        let queue = queue(DeliveryPolicy::BoundedBlock(1));
+
+        queue.push(vec![0]);
+
+        let producer = {
+            let queue = queue.clone();
+
+            std::thread::spawn(move || queue.push(vec![1]))
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+
+        assert_eq!(queue.try_pop(), Ok(vec![0]));
+
+        producer.join().unwrap();
+
+        assert_eq!(drain(&queue), vec![1]);
+        assert_eq!(queue.stats().blocked, 1);
+        assert_eq!(queue.stats().overflowed, 0);
+    }
+
+    #[test]
+    fn closing_releases_blocked_producer() {
+        let queue = queue(DeliveryPolicy::BoundedBlock(1));
+
+        queue.push(vec![0]);
+
+        let producer = {
+            let queue = queue.clone();
+
+            std::thread::spawn(move || queue.push(vec![1]))
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+
+        queue.close();
+
+        producer.join().unwrap();
+
+        assert_eq!(queue.try_pop(), Ok(vec![0]));
+        assert_eq!(queue.try_pop(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn filters_apply_before_delivery() {
+        let queue = SampleQueue::new(
+            SubscriptionOptions::<PlcBool>::new()
+                .with_edge(Edge::Rising)
+                .with_delivery_policy(DeliveryPolicy::DropOldest(1)),
+        );
+
+        for value in [0, 1, 0, 1] {
+            queue.push(vec![value]);
+        }
+
+        assert_eq!(drain(&queue), vec![1]);
+        assert_eq!(
+            queue.stats(),
+            SubscriptionStats {
+                received: 4,
+                filtered: 2,
+                overflowed: 1,
+                blocked: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn closed_queue_times_out_as_disconnected() {
+        let queue = queue(DeliveryPolicy::Unbounded);
+
+        let deadline = Instant::now() + Duration::from_millis(10);
+
+        assert_eq!(queue.pop(Some(deadline)), Err(RecvTimeoutError::Timeout));
+
+        queue.close();
+
+        assert_eq!(queue.pop(None), Err(RecvTimeoutError::Disconnected));
+    }
+}
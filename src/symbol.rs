@@ -0,0 +1,131 @@
+use std::{marker::PhantomData, sync::Mutex, time::Duration};
+
+use crate::{
+    data_types::PlcDataType,
+    error::PlcResult,
+    plc_connection::PlcConnection,
+    subscription::{Subscription, SubscriptionOptions},
+};
+
+/// A symbol on the PLC with a known type, so its name is spelled out once and its size is checked against the PLC.
+///
+/// The symbol is resolved again after every reconnect, in case new code changed its type.
+pub struct Symbol<T> {
+    connection: PlcConnection,
+    name: String,
+    resolved: Mutex<Option<Resolved>>,
+    _type: PhantomData<fn() -> T>,
+}
+
+#[derive(Clone, Copy)]
+struct Resolved {
+    connection_generation: u64,
+    handle: u32,
+}
+
+impl<T: PlcDataType> Symbol<T> {
+    pub(crate) fn new(connection: PlcConnection, name: &str) -> Self {
+        Self {
+            connection,
+            name: name.to_string(),
+            resolved: Mutex::new(None),
+            _type: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The symbol's handle on the current connection, if it has been resolved since connecting.
+    pub fn handle(&self) -> Option<u32> {
+        self.current().map(|resolved| resolved.handle)
+    }
+
+    /// Checks the symbol exists and its size matches T, fetching its handle.
+    ///
+    /// Fails with PlcError::SymbolNotFound or PlcError::SizeMismatch, e.g: to check every symbol at startup.
+    pub fn resolve(&self) -> PlcResult<u32> {
+        // NB: read before resolving, so a reconnect in between makes the symbol resolve again on next use
+        let connection_generation = self.connection.connection_generation();
+
+        let handle = self.connection.resolve_symbol::<T>(&self.name)?;
+
+        *self.resolved.lock().unwrap() = Some(Resolved {
+            connection_generation,
+            handle,
+        });
+
+        Ok(handle)
+    }
+
+    fn current(&self) -> Option<Resolved> {
+        let connection_generation = self.connection.connection_generation();
+
+        self.resolved
+            .lock()
+            .unwrap()
+            .filter(|resolved| resolved.connection_generation == connection_generation)
+    }
+
+    fn ensure_resolved(&self) -> PlcResult<()> {
+        if self.current().is_none() {
+            self.resolve()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read(&self) -> PlcResult<T> {
+        self.ensure_resolved()?;
+
+        self.connection.read_symbol(&self.name)
+    }
+
+    pub fn write(&self, value: T) -> PlcResult<()> {
+        self.ensure_resolved()?;
+
+        self.connection.write_symbol(&self.name, value)
+    }
+
+    /// Subscribes to the symbol, delivering every change to the returned subscription.
+    pub fn subscribe(&self) -> PlcResult<Subscription<T>> {
+        self.subscribe_with_options(SubscriptionOptions::new())
+    }
+
+    pub fn subscribe_with_options(
+        &self,
+        options: SubscriptionOptions<T>,
+    ) -> PlcResult<Subscription<T>> {
+        self.ensure_resolved()?;
+
+        self.connection.subscribe_with_options(&self.name, options)
+    }
+
+    /// Blocks until the symbol satisfies the predicate, see PlcConnection::wait_until.
+    pub fn wait_until(&self, predicate: impl FnMut(&T) -> bool, timeout: Duration) -> PlcResult<T> {
+        self.ensure_resolved()?;
+
+        self.connection.wait_until(&self.name, predicate, timeout)
+    }
+}
+
+impl<T> Clone for Symbol<T> {
+    fn clone(&self) -> Self {
+        Self {
+            connection: self.connection.clone(),
+            name: self.name.clone(),
+            resolved: Mutex::new(*self.resolved.lock().unwrap()),
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Symbol<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Symbol")
+            .field("name", &self.name)
+            .field("type", &std::any::type_name::<T>())
+            .finish()
+    }
+}
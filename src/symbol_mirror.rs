@@ -0,0 +1,366 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::TryRecvError;
+
+use crate::{
+    data_types::PlcDataType,
+    error::{PlcError, PlcResult},
+    plc_connection::PlcConnection,
+    subscription::{DeliveryPolicy, Subscription},
+};
+
+/// How far a mirrored value can be trusted to reflect the PLC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quality {
+    /// The value is kept up to date by a notification, or was polled within the stale timeout.
+    Good,
+    /// The value has not been refreshed within the stale timeout.
+    Stale,
+    /// The PLC is not connected, the value is the last one seen before the connection dropped.
+    Disconnected,
+}
+
+/// A value served from the mirror's memory.
+#[derive(Clone, Debug)]
+pub struct MirroredValue<T> {
+    pub value: T,
+    /// Time since the value was last received from the PLC.
+    pub age: Duration,
+    pub quality: Quality,
+}
+
+pub struct SymbolMirrorBuilder {
+    connection: PlcConnection,
+    poll_interval: Duration,
+    stale_after: Duration,
+}
+
+impl SymbolMirrorBuilder {
+    pub fn new(connection: PlcConnection) -> Self {
+        Self {
+            connection,
+            poll_interval: Duration::from_millis(100),
+            stale_after: Duration::from_secs(1),
+        }
+    }
+
+    /// How often symbols that could not be subscribed to are read from the PLC.
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// How old a polled value may get before it is reported as stale.
+    pub fn with_stale_after(self, stale_after: Duration) -> Self {
+        Self {
+            stale_after,
+            ..self
+        }
+    }
+
+    pub fn build(self) -> SymbolMirror {
+        let shared = Arc::new(SymbolMirrorShared {
+            connection: self.connection,
+            poll_interval: self.poll_interval,
+            stale_after: self.stale_after,
+            running: AtomicBool::new(true),
+            connected: AtomicBool::new(false),
+            entries: Default::default(),
+        });
+
+        let worker = {
+            let shared = shared.clone();
+
+            std::thread::spawn(move || shared.run())
+        };
+
+        SymbolMirror {
+            shared,
+            worker: Some(worker),
+        }
+    }
+}
+
+/// Keeps the latest value of a set of symbols in memory.
+///
+/// Symbols are kept up to date through a subscription each, falling back to cyclic polling for any symbol that cannot
+/// be subscribed to. Subscriptions are re-established automatically after the connection is restored. Each symbol has
+/// its own subscription, so the mirror takes nothing from the connection's notification receiver.
+pub struct SymbolMirror {
+    shared: Arc<SymbolMirrorShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl SymbolMirror {
+    /// Starts mirroring a symbol. Registering a symbol that is already mirrored has no effect.
+    pub fn register<T: PlcDataType + 'static>(&self, name: &str) {
+        let mut entries = self.shared.entries.lock().unwrap();
+
+        entries
+            .entry(name.to_string())
+            .or_insert_with(MirrorEntry::new::<T>);
+    }
+
+    /// Stops mirroring a symbol, deleting its subscription if it has one.
+    pub fn unregister(&self, name: &str) {
+        let removed = self.shared.entries.lock().unwrap().remove(name);
+
+        // NB: dropped outside the lock, as deleting the subscription's notification is a request to the PLC
+        drop(removed);
+    }
+
+    /// Reads a symbol from memory.
+    ///
+    /// Returns None if the symbol is not registered or no value has been received for it yet.
+    pub fn read<T: PlcDataType>(&self, name: &str) -> Option<MirroredValue<T>> {
+        let connected = self.shared.connected.load(Ordering::SeqCst);
+        let mut entries = self.shared.entries.lock().unwrap();
+
+        let entry = entries.get_mut(name)?;
+        entry.refresh();
+
+        let (data, received_at) = entry.value.as_ref()?;
+
+        let age = received_at.elapsed();
+
+        Some(MirroredValue {
+            value: T::from_bytes(data)?,
+            age,
+            quality: entry.quality(connected, age, self.shared.stale_after),
+        })
+    }
+}
+
+impl Drop for SymbolMirror {
+    fn drop(&mut self) {
+        self.shared.running.store(false, Ordering::SeqCst);
+
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+
+        // NB: the subscriptions delete their notifications as they drop, after the lock is released
+        let entries = std::mem::take(&mut *self.shared.entries.lock().unwrap());
+
+        drop(entries);
+    }
+}
+
+struct SymbolMirrorShared {
+    connection: PlcConnection,
+    poll_interval: Duration,
+    stale_after: Duration,
+    running: AtomicBool,
+    connected: AtomicBool,
+    entries: Mutex<HashMap<String, MirrorEntry>>,
+}
+
+type SubscribeFn = fn(&PlcConnection, &str) -> PlcResult<Box<dyn LatestSample>>;
+type ReadFn = fn(&PlcConnection, &str) -> PlcResult<Vec<u8>>;
+
+/// A subscription holding only its latest sample, with the symbol's type erased.
+trait LatestSample: Send {
+    /// The latest sample's bytes and when it arrived, if one arrived since the last call.
+    fn try_latest(&self) -> Result<(Vec<u8>, Instant), TryRecvError>;
+}
+
+impl<T: PlcDataType> LatestSample for Subscription<T> {
+    fn try_latest(&self) -> Result<(Vec<u8>, Instant), TryRecvError> {
+        let value = self.try_recv()?;
+
+        let received_at = self.last_queued_at().unwrap_or_else(Instant::now);
+
+        Ok((value.as_bytes().to_vec(), received_at))
+    }
+}
+
+struct MirrorEntry {
+    subscribe: SubscribeFn,
+    read: ReadFn,
+    source: MirrorSource,
+    value: Option<(Vec<u8>, Instant)>,
+}
+
+enum MirrorSource {
+    /// Not yet subscribed on the current connection.
+    Unresolved,
+    Subscription(Box<dyn LatestSample>),
+    Polling,
+}
+
+impl MirrorEntry {
+    fn new<T: PlcDataType + 'static>() -> Self {
+        Self {
+            subscribe: |connection, name| {
+                connection
+                    .subscribe_with_policy::<T>(name, DeliveryPolicy::CoalesceLatest)
+                    .map(|subscription| Box::new(subscription) as Box<dyn LatestSample>)
+            },
+            read: |connection, name| {
+                connection
+                    .read_symbol::<T>(name)
+                    .map(|value| value.as_bytes().to_vec())
+            },
+            source: MirrorSource::Unresolved,
+            value: None,
+        }
+    }
+
+    /// Takes the latest sample from the entry's subscription, subscribing again once the subscription closes.
+    fn refresh(&mut self) {
+        let MirrorSource::Subscription(subscription) = &self.source else {
+            return;
+        };
+
+        match subscription.try_latest() {
+            Ok(value) => self.value = Some(value),
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.source = MirrorSource::Unresolved,
+        }
+    }
+
+    fn quality(&self, connected: bool, age: Duration, stale_after: Duration) -> Quality {
+        if !connected {
+            Quality::Disconnected
+        } else if matches!(self.source, MirrorSource::Subscription(_)) || age <= stale_after {
+            Quality::Good
+        } else {
+            Quality::Stale
+        }
+    }
+}
+
+impl SymbolMirrorShared {
+    fn run(&self) {
+        let mut connection_generation = None;
+
+        while self.running.load(Ordering::SeqCst) {
+            // NB: a mirror kept up to date by subscriptions alone makes no requests that would notice a drop
+            self.connection.drop_if_closed();
+
+            let connected = self.connection.is_connected();
+
+            self.connected.store(connected, Ordering::SeqCst);
+
+            if !connected {
+                connection_generation = None;
+
+                std::thread::sleep(self.poll_interval);
+
+                continue;
+            }
+
+            let current_generation = self.connection.connection_generation();
+
+            if connection_generation != Some(current_generation) {
+                // Subscriptions are closed with the old connection
+                self.reset_sources();
+
+                connection_generation = Some(current_generation);
+            }
+
+            self.refresh_subscriptions();
+            self.resolve_sources();
+            self.poll();
+
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+
+    fn reset_sources(&self) {
+        let mut sources = Vec::new();
+
+        for entry in self.entries.lock().unwrap().values_mut() {
+            sources.push(std::mem::replace(
+                &mut entry.source,
+                MirrorSource::Unresolved,
+            ));
+        }
+
+        // NB: dropped outside the lock, as dropping a subscription may delete its notification
+        drop(sources);
+    }
+
+    fn refresh_subscriptions(&self) {
+        for entry in self.entries.lock().unwrap().values_mut() {
+            entry.refresh();
+        }
+    }
+
+    fn resolve_sources(&self) {
+        let unresolved: Vec<(String, SubscribeFn)> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| matches!(entry.source, MirrorSource::Unresolved))
+            .map(|(name, entry)| (name.clone(), entry.subscribe))
+            .collect();
+
+        for (name, subscribe) in unresolved {
+            let source = match subscribe(&self.connection, &name) {
+                Ok(subscription) => MirrorSource::Subscription(subscription),
+                Err(PlcError::NotConnected) => MirrorSource::Unresolved,
+                Err(error) => {
+                    if !self.connection.is_connected() {
+                        MirrorSource::Unresolved
+                    } else {
+                        tracing::warn!(
+                            %error,
+                            symbol = name,
+                            "Could not subscribe, falling back to polling"
+                        );
+
+                        MirrorSource::Polling
+                    }
+                }
+            };
+
+            let mut entries = self.entries.lock().unwrap();
+
+            // NB: a symbol unregistered while subscribing drops its new subscription here, outside the lock
+            let unregistered = match entries.get_mut(&name) {
+                Some(entry) => {
+                    entry.source = source;
+                    entry.refresh();
+
+                    None
+                }
+                None => Some(source),
+            };
+
+            drop(entries);
+            drop(unregistered);
+        }
+    }
+
+    fn poll(&self) {
+        let polled: Vec<(String, ReadFn)> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| matches!(entry.source, MirrorSource::Polling))
+            .map(|(name, entry)| (name.clone(), entry.read))
+            .collect();
+
+        for (name, read) in polled {
+            // Errors are reported by the connection, the value simply goes stale
+            if let Ok(data) = read(&self.connection, &name) {
+                if let Some(entry) = self.entries.lock().unwrap().get_mut(&name) {
+                    entry.value = Some((data, Instant::now()));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,602 @@
+//! An in-memory PLC for unit tests, with expectations and a record of every call made on it.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use ads::AdsState;
+
+use crate::{
+    data_types::PlcDataType,
+    error::{PlcError, PlcResult},
+    plc_backend::PlcBackend,
+    return_code::ReturnCode,
+    subscription::{SampleQueue, Subscription, SubscriptionOptions},
+};
+
+use super::{ERROR_INVALID_SIZE, ERROR_SYMBOL_NOT_FOUND};
+
+/// A [`PlcBackend`] keeping symbols in memory, for unit testing code written against `impl PlcBackend`.
+///
+/// It behaves like a PLC where that matters to the caller: unknown symbols are not found, values of the wrong size
+/// are rejected, subscriptions get the current value and then every change, and errors that would drop a real
+/// connection disconnect it. Every call is recorded, and expectations on reads, writes and RPC methods can both
+/// script replies and be verified.
+///
+/// Expectations are verified when the last clone of the fake is dropped, or earlier with [`FakePlc::verify`].
+///
+/// Subscribers see changes in the order they are stored, even from several threads. Like the single delivery thread of
+/// a real connection, a full `DeliveryPolicy::BoundedBlock` queue holds up every change until its consumer catches up.
+///
+/// ```
+/// use ads_client::{
+///     data_types::primitives::bool::PlcBool, plc_backend::PlcBackend, testing::FakePlc,
+/// };
+///
+/// let plc = FakePlc::new();
+/// plc.add_symbol("MAIN.bStop", PlcBool::from(false));
+/// plc.expect_write("MAIN.bStop").with(PlcBool::from(true)).times(1);
+///
+/// plc.write_symbol("MAIN.bStop", PlcBool::from(true)).unwrap();
+///
+/// plc.verify();
+/// ```
+#[derive(Clone)]
+pub struct FakePlc {
+    state: Arc<Mutex<FakeState>>,
+    // Taken before the state, and held until subscribers are notified, so changes reach them in the order stored
+    delivery: Arc<Mutex<()>>,
+}
+
+struct FakeState {
+    symbols: HashMap<String, Vec<u8>>,
+    connected: bool,
+    ads_state: AdsState,
+    expectations: Vec<ExpectationState>,
+    calls: Vec<Call>,
+    subscriptions: Vec<(String, Arc<SampleQueue>)>,
+    next_handle: u32,
+}
+
+/// A call made on a fake PLC while it was connected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Call {
+    Read { name: String },
+    Write { name: String, data: Vec<u8> },
+    Rpc { name: String, params: Vec<u8> },
+    Subscribe { name: String },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operation {
+    Read,
+    Write,
+    Rpc,
+}
+
+struct ExpectationState {
+    operation: Operation,
+    name: String,
+    data: Option<Vec<u8>>,
+    // None expects at least one call
+    times: Option<usize>,
+    calls: usize,
+    result: Option<Vec<u8>>,
+    error: Option<u32>,
+}
+
+impl FakePlc {
+    /// A connected PLC in run mode, without any symbols.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(FakeState {
+                symbols: HashMap::new(),
+                connected: true,
+                ads_state: AdsState::Run,
+                expectations: Vec::new(),
+                calls: Vec::new(),
+                subscriptions: Vec::new(),
+                next_handle: 1,
+            })),
+            delivery: Default::default(),
+        }
+    }
+
+    pub fn add_symbol<T: PlcDataType>(&self, name: &str, value: T) {
+        self.state
+            .lock()
+            .unwrap()
+            .symbols
+            .insert(name.to_string(), value.as_bytes().to_vec());
+    }
+
+    pub fn value<T: PlcDataType>(&self, name: &str) -> Option<T> {
+        self.state
+            .lock()
+            .unwrap()
+            .symbols
+            .get(name)
+            .and_then(|value| T::from_bytes(value))
+    }
+
+    /// Changes a symbol as if the PLC program wrote it, notifying subscribers. This is not recorded as a call.
+    pub fn set_value<T: PlcDataType>(&self, name: &str, value: T) {
+        self.store(name, value.as_bytes().to_vec());
+    }
+
+    /// Connects or disconnects the PLC. While disconnected every call fails with NotConnected, and disconnecting
+    /// closes every subscription.
+    pub fn set_connected(&self, connected: bool) {
+        let mut state = self.state.lock().unwrap();
+
+        if connected {
+            state.connected = true;
+        } else {
+            state.disconnect();
+        }
+    }
+
+    pub fn set_ads_state(&self, ads_state: AdsState) {
+        self.state.lock().unwrap().ads_state = ads_state;
+    }
+
+    /// Expects a read of the symbol, by default at least once.
+    pub fn expect_read(&self, name: &str) -> Expectation<'_> {
+        self.expect(Operation::Read, name)
+    }
+
+    /// Expects a write to the symbol, by default at least once.
+    pub fn expect_write(&self, name: &str) -> Expectation<'_> {
+        self.expect(Operation::Write, name)
+    }
+
+    /// Expects a call of the RPC method, by default at least once. RPC methods without an expectation are not found.
+    pub fn expect_rpc(&self, name: &str) -> Expectation<'_> {
+        self.expect(Operation::Rpc, name)
+    }
+
+    fn expect(&self, operation: Operation, name: &str) -> Expectation<'_> {
+        let mut state = self.state.lock().unwrap();
+
+        state.expectations.push(ExpectationState {
+            operation,
+            name: name.to_string(),
+            data: None,
+            times: None,
+            calls: 0,
+            result: None,
+            error: None,
+        });
+
+        Expectation {
+            plc: self,
+            index: state.expectations.len() - 1,
+        }
+    }
+
+    /// Panics if any expectation was not met.
+    pub fn verify(&self) {
+        let state = self.state.lock().unwrap();
+
+        let unmet: Vec<String> = state
+            .expectations
+            .iter()
+            .filter(|expectation| !expectation.is_met())
+            .map(ExpectationState::to_string)
+            .collect();
+
+        drop(state);
+
+        assert!(
+            unmet.is_empty(),
+            "Unmet expectations on the fake PLC:\n{}",
+            unmet.join("\n")
+        );
+    }
+
+    /// Verifies the expectations so far, then clears them, e.g: between phases of a test.
+    pub fn checkpoint(&self) {
+        self.verify();
+
+        self.state.lock().unwrap().expectations.clear();
+    }
+
+    /// Every call made so far, in order.
+    pub fn calls(&self) -> Vec<Call> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// The values written to a symbol so far, in order.
+    pub fn writes<T: PlcDataType>(&self, name: &str) -> Vec<T> {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                Call::Write {
+                    name: written,
+                    data,
+                } if written == name => T::from_bytes(data),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn clear_calls(&self) {
+        self.state.lock().unwrap().calls.clear();
+    }
+
+    /// Records a call and runs it past the expectations, returning any scripted result.
+    fn call(
+        &self,
+        call: Call,
+        operation: Operation,
+        name: &str,
+        data: &[u8],
+    ) -> PlcResult<Option<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap();
+
+        if !state.connected {
+            return Err(PlcError::NotConnected);
+        }
+
+        state.calls.push(call);
+
+        let Some(expectation) = state.expectation_for(operation, name, data) else {
+            return Ok(None);
+        };
+
+        expectation.calls += 1;
+
+        // Calls beyond the expected count fail verification, but otherwise behave like unexpected ones
+        if expectation
+            .times
+            .is_some_and(|times| expectation.calls > times)
+        {
+            return Ok(None);
+        }
+
+        if let Some(code) = expectation.error {
+            let error = device_error(name, code);
+
+            if error.should_disconnect() {
+                state.disconnect();
+            }
+
+            return Err(error);
+        }
+
+        Ok(expectation.result.clone())
+    }
+
+    /// Stores a symbol's new value, notifying subscribers if it changed.
+    fn store(&self, name: &str, value: Vec<u8>) {
+        let _delivery = self.delivery.lock().unwrap();
+
+        let subscriptions: Vec<Arc<SampleQueue>> = {
+            let mut state = self.state.lock().unwrap();
+
+            if state
+                .symbols
+                .insert(name.to_string(), value.clone())
+                .as_ref()
+                == Some(&value)
+            {
+                return;
+            }
+
+            state
+                .subscriptions
+                .iter()
+                .filter(|(symbol, _)| symbol == name)
+                .map(|(_, queue)| queue.clone())
+                .collect()
+        };
+
+        // NB: pushed without the state lock, as a blocking delivery policy waits for the consumer
+        for queue in subscriptions {
+            queue.push(value.clone());
+        }
+    }
+}
+
+impl Default for FakePlc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for FakePlc {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.state) == 1 && !std::thread::panicking() {
+            self.verify();
+        }
+    }
+}
+
+impl PlcBackend for FakePlc {
+    fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().connected
+    }
+
+    fn ads_state(&self) -> PlcResult<AdsState> {
+        let state = self.state.lock().unwrap();
+
+        if !state.connected {
+            return Err(PlcError::NotConnected);
+        }
+
+        Ok(state.ads_state)
+    }
+
+    fn read_symbol<T: PlcDataType>(&self, name: &str) -> PlcResult<T> {
+        let call = Call::Read {
+            name: name.to_string(),
+        };
+
+        let data = match self.call(call, Operation::Read, name, &[])? {
+            Some(data) => data,
+            None => self
+                .state
+                .lock()
+                .unwrap()
+                .symbols
+                .get(name)
+                .cloned()
+                .ok_or_else(|| device_error(name, ERROR_SYMBOL_NOT_FOUND))?,
+        };
+
+        decode(name, &data)
+    }
+
+    fn write_symbol<T: PlcDataType>(&self, name: &str, value: T) -> PlcResult<()> {
+        let data = value.as_bytes();
+
+        let call = Call::Write {
+            name: name.to_string(),
+            data: data.to_vec(),
+        };
+
+        self.call(call, Operation::Write, name, data)?;
+
+        match self.state.lock().unwrap().symbols.get(name) {
+            None => return Err(device_error(name, ERROR_SYMBOL_NOT_FOUND)),
+            Some(current) if current.len() != data.len() => {
+                return Err(size_mismatch(name, data.len()));
+            }
+            Some(_) => {}
+        }
+
+        self.store(name, data.to_vec());
+
+        Ok(())
+    }
+
+    fn call_rpc_method<R: PlcDataType>(&self, name: &str, params: &[&[u8]]) -> PlcResult<R> {
+        let params = params.concat();
+
+        let call = Call::Rpc {
+            name: name.to_string(),
+            params: params.clone(),
+        };
+
+        match self.call(call, Operation::Rpc, name, &params)? {
+            Some(result) => decode(name, &result),
+            None => Err(device_error(name, ERROR_SYMBOL_NOT_FOUND)),
+        }
+    }
+
+    fn invoke_rpc_method_with_params(&self, name: &str, params: &[&[u8]]) -> PlcResult<()> {
+        let params = params.concat();
+
+        let call = Call::Rpc {
+            name: name.to_string(),
+            params: params.clone(),
+        };
+
+        self.call(call, Operation::Rpc, name, &params)?;
+
+        let expected = self
+            .state
+            .lock()
+            .unwrap()
+            .expectations
+            .iter()
+            .any(|expectation| expectation.operation == Operation::Rpc && expectation.name == name);
+
+        if !expected {
+            return Err(device_error(name, ERROR_SYMBOL_NOT_FOUND));
+        }
+
+        Ok(())
+    }
+
+    fn subscribe_with_options<T: PlcDataType>(
+        &self,
+        name: &str,
+        options: SubscriptionOptions<T>,
+    ) -> PlcResult<Subscription<T>> {
+        let _delivery = self.delivery.lock().unwrap();
+
+        let (handle, queue, value) = {
+            let mut state = self.state.lock().unwrap();
+
+            if !state.connected {
+                return Err(PlcError::NotConnected);
+            }
+
+            state.calls.push(Call::Subscribe {
+                name: name.to_string(),
+            });
+
+            let value = state
+                .symbols
+                .get(name)
+                .cloned()
+                .ok_or_else(|| device_error(name, ERROR_SYMBOL_NOT_FOUND))?;
+
+            if value.len() != T::size() {
+                return Err(size_mismatch(name, T::size()));
+            }
+
+            let queue = SampleQueue::new(options);
+            let handle = state.next_handle;
+
+            state.next_handle += 1;
+            state.subscriptions.retain(|(_, queue)| !queue.is_closed());
+            state.subscriptions.push((name.to_string(), queue.clone()));
+
+            (handle, queue, value)
+        };
+
+        // Like notifications on a PLC, the first sample is the current value
+        queue.push(value);
+
+        Ok(Subscription::detached(handle, queue))
+    }
+}
+
+impl FakeState {
+    /// The first expectation the call matches that still wants calls, otherwise the first it matches at all.
+    fn expectation_for(
+        &mut self,
+        operation: Operation,
+        name: &str,
+        data: &[u8],
+    ) -> Option<&mut ExpectationState> {
+        let matches = |expectation: &ExpectationState| {
+            expectation.operation == operation
+                && expectation.name == name
+                && expectation
+                    .data
+                    .as_ref()
+                    .is_none_or(|expected| expected == data)
+        };
+
+        let index = self
+            .expectations
+            .iter()
+            .position(|expectation| {
+                matches(expectation)
+                    && expectation
+                        .times
+                        .is_none_or(|times| expectation.calls < times)
+            })
+            .or_else(|| self.expectations.iter().position(matches))?;
+
+        Some(&mut self.expectations[index])
+    }
+
+    fn disconnect(&mut self) {
+        self.connected = false;
+
+        for (_, queue) in self.subscriptions.drain(..) {
+            queue.close();
+        }
+    }
+}
+
+impl ExpectationState {
+    fn is_met(&self) -> bool {
+        match self.times {
+            Some(times) => self.calls == times,
+            None => self.calls > 0,
+        }
+    }
+}
+
+impl fmt::Display for ExpectationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operation = match self.operation {
+            Operation::Read => "read of",
+            Operation::Write => "write to",
+            Operation::Rpc => "call of",
+        };
+
+        write!(f, "{operation} {}", self.name)?;
+
+        if let Some(data) = &self.data {
+            write!(f, " with {data:?}")?;
+        }
+
+        match self.times {
+            Some(times) => write!(f, ": expected {times} calls, got {}", self.calls),
+            None => write!(f, ": expected at least one call, got none"),
+        }
+    }
+}
+
+/// Sets up an expectation, see [`FakePlc::expect_read`], [`FakePlc::expect_write`] and [`FakePlc::expect_rpc`].
+pub struct Expectation<'p> {
+    plc: &'p FakePlc,
+    index: usize,
+}
+
+impl Expectation<'_> {
+    /// Only matches calls writing this value, or passing these RPC parameters.
+    pub fn with<T: PlcDataType>(self, value: T) -> Self {
+        self.with_bytes(value.as_bytes())
+    }
+
+    /// Only matches calls writing these bytes, or passing these RPC parameters laid out one after the other.
+    pub fn with_bytes(self, data: impl Into<Vec<u8>>) -> Self {
+        self.update(|expectation| expectation.data = Some(data.into()))
+    }
+
+    /// Expects exactly this many matching calls.
+    pub fn times(self, times: usize) -> Self {
+        self.update(|expectation| expectation.times = Some(times))
+    }
+
+    /// Expects no matching calls at all.
+    pub fn never(self) -> Self {
+        self.times(0)
+    }
+
+    /// Replies to matching reads and RPC calls with this value.
+    pub fn returning<T: PlcDataType>(self, value: T) -> Self {
+        self.update(|expectation| expectation.result = Some(value.as_bytes().to_vec()))
+    }
+
+    /// Fails matching calls with this ADS return code, disconnecting if a real connection would drop.
+    pub fn failing_with(self, code: u32) -> Self {
+        self.update(|expectation| expectation.error = Some(code))
+    }
+
+    fn update(self, update: impl FnOnce(&mut ExpectationState)) -> Self {
+        update(&mut self.plc.state.lock().unwrap().expectations[self.index]);
+
+        self
+    }
+}
+
+fn device_error(name: &str, code: u32) -> PlcError {
+    let description =
+        ReturnCode::lookup(code).map_or("Unknown error", |return_code| return_code.description);
+
+    PlcError::for_symbol(name, ads::Error::Ads("Fake PLC", description, code))
+}
+
+fn size_mismatch(name: &str, expected: usize) -> PlcError {
+    let description = ReturnCode::lookup(ERROR_INVALID_SIZE)
+        .map_or("Invalid size", |return_code| return_code.description);
+
+    PlcError::for_symbol_data(
+        name,
+        expected,
+        ads::Error::Ads("Fake PLC", description, ERROR_INVALID_SIZE),
+    )
+}
+
+fn decode<T: PlcDataType>(name: &str, data: &[u8]) -> PlcResult<T> {
+    if data.len() != T::size() {
+        return Err(size_mismatch(name, T::size()));
+    }
+
+    T::from_bytes(data).ok_or_else(|| PlcError::InvalidValue {
+        reason: format!("{name} read {} bytes", data.len()),
+    })
+}
@@ -0,0 +1,1044 @@
+//! An in-process ADS server, for testing applications built on PlcConnection without a PLC.
+//!
+//! Speaks AMS/TCP on a local port and serves symbols from an in-memory table: device info and state, symbol handles,
+//! symbol info and upload, reads, writes, sum commands, RPC methods and notifications. [`PlcSimulator`] runs scripted
+//! behaviours on top of it, and [`FaultProxy`] sits in front of either to break the connection in controlled ways.
+//! [`FakePlc`] skips the network altogether, for unit testing code written against
+//! [`PlcBackend`](crate::plc_backend::PlcBackend).
+//!
+//! ```
+//! use ads_client::{data_types::primitives::int::PlcInt, testing::MockAdsServer};
+//!
+//! let server = MockAdsServer::start();
+//! server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+//!
+//! let connection = server.connection();
+//! connection.run_connection_loop();
+//!
+//! let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+//! assert_eq!(i16::from(value), 7);
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use ads::{AdsState, AmsAddr, AmsNetId};
+
+use crate::plc_connection::{PlcConnection, PlcConnectionBuilder};
+
+pub use self::{
+    fake::{Call, Expectation, FakePlc},
+    proxy::FaultProxy,
+    replay::ReplayServer,
+    simulator::{Cycle, OnlineChange, PlcSimulator},
+};
+
+mod fake;
+mod proxy;
+mod replay;
+mod simulator;
+
+const AMS_HEADER_SIZE: usize = 38;
+
+const ERROR_SYMBOL_NOT_FOUND: u32 = 0x710;
+const ERROR_INVALID_SIZE: u32 = 0x705;
+const ERROR_INVALID_GROUP: u32 = 0x702;
+const ERROR_INVALID_DATA: u32 = 0x706;
+const ERROR_SYMBOL_VERSION_INVALID: u32 = 0x711;
+const ERROR_NO_MORE_HANDLES: u32 = 0x712;
+const ERROR_NOTIFICATION_HANDLE_INVALID: u32 = 0x714;
+const ERROR_TARGET_PORT_NOT_FOUND: u32 = 0x006;
+
+const MAX_SUM_COMMANDS: usize = 500;
+
+// Far beyond any symbol, but keeps a client's read length from allocating gigabytes of padding
+const MAX_READ_LENGTH: usize = 16 * 1024 * 1024;
+
+// The base type of symbols whose data type is not one of the ADS base types
+const ADST_BIGTYPE: u32 = 65;
+
+/// Called with an RPC method's parameter bytes, returning its result bytes or an ADS return code.
+type RpcHandler = Box<dyn FnMut(&[u8]) -> Result<Vec<u8>, u32> + Send>;
+
+/// A structure's size, and each field's name, data type, offset and size.
+type DataType = (usize, Vec<(String, String, usize, usize)>);
+
+/// A local ADS server, which stops when dropped.
+pub struct MockAdsServer {
+    address: SocketAddr,
+    state: Arc<Mutex<ServerState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct ServerState {
+    symbols: HashMap<String, Vec<u8>>,
+    symbol_types: HashMap<String, String>,
+    data_types: BTreeMap<String, DataType>,
+    rpc_methods: HashMap<String, RpcHandler>,
+    handles: HashMap<u32, String>,
+    // Handles to symbols changed by an online change, which must be fetched again
+    invalidated_handles: HashSet<u32>,
+    symbol_version: u8,
+    notifications: HashMap<u32, NotificationRoute>,
+    next_handle: u32,
+    ads_state: u16,
+    reply_delay: Duration,
+    sum_commands_supported: bool,
+    notifications_supported: bool,
+    sum_command_count: usize,
+    streams: Vec<TcpStream>,
+}
+
+struct NotificationRoute {
+    symbol: String,
+    writer: Arc<Mutex<TcpStream>>,
+    client: [u8; 8],
+    server: [u8; 8],
+}
+
+impl MockAdsServer {
+    /// Starts a server listening on a free local port, in run mode.
+    ///
+    /// Panics if no local port can be bound.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("binding a local port");
+        let address = listener.local_addr().expect("a bound address");
+
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let state = Arc::new(Mutex::new(ServerState {
+            ads_state: AdsState::Run as u16,
+            next_handle: 1,
+            sum_commands_supported: true,
+            notifications_supported: true,
+            ..Default::default()
+        }));
+
+        {
+            let state = state.clone();
+            let stopped = stopped.clone();
+
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else {
+                        return;
+                    };
+
+                    if stopped.load(Ordering::SeqCst) {
+                        return;
+                    }
+
+                    stream.set_nodelay(true).ok();
+
+                    let Ok(clone) = stream.try_clone() else {
+                        continue;
+                    };
+
+                    state.lock().unwrap().streams.push(clone);
+
+                    let state = state.clone();
+
+                    std::thread::spawn(move || serve(stream, state));
+                }
+            });
+        }
+
+        Self {
+            address,
+            state,
+            stopped,
+        }
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// A builder for a connection to this server, to set further options on.
+    pub fn builder(&self) -> PlcConnectionBuilder {
+        connection_builder(self.address)
+    }
+
+    /// A connection to this server, not yet connected.
+    pub fn connection(&self) -> PlcConnection {
+        self.builder().build()
+    }
+
+    pub fn add_symbol(&self, name: &str, value: impl Into<Vec<u8>>) {
+        self.state
+            .lock()
+            .unwrap()
+            .symbols
+            .insert(name.to_string(), value.into());
+    }
+
+    /// Adds a symbol with its PLC data type, e.g: `"INT"` or `"STRING(80)"`, which is what symbol info and the
+    /// uploaded symbol table report. Symbols added without a type are reported as arrays of bytes.
+    pub fn add_symbol_with_type(&self, name: &str, data_type: &str, value: impl Into<Vec<u8>>) {
+        let mut state = self.state.lock().unwrap();
+
+        state.symbols.insert(name.to_string(), value.into());
+        state
+            .symbol_types
+            .insert(name.to_string(), data_type.to_string());
+    }
+
+    /// Adds a structure to the uploaded data type table, with each field's name, PLC data type, offset and size in
+    /// bytes. The bounds of array fields come from their type, e.g: `ARRAY [0..2] OF INT`.
+    pub fn add_data_type(&self, name: &str, size: usize, fields: &[(&str, &str, usize, usize)]) {
+        let fields = fields
+            .iter()
+            .map(|(name, data_type, offset, size)| {
+                (name.to_string(), data_type.to_string(), *offset, *size)
+            })
+            .collect();
+
+        self.state
+            .lock()
+            .unwrap()
+            .data_types
+            .insert(name.to_string(), (size, fields));
+    }
+
+    /// Adds an RPC method, called with the parameter bytes and returning the result bytes.
+    pub fn add_rpc_method(
+        &self,
+        name: &str,
+        handler: impl FnMut(&[u8]) -> Vec<u8> + Send + 'static,
+    ) {
+        let mut handler = handler;
+
+        self.add_rpc_handler(name, Box::new(move |params| Ok(handler(params))));
+    }
+
+    fn add_rpc_handler(&self, name: &str, handler: RpcHandler) {
+        self.state
+            .lock()
+            .unwrap()
+            .rpc_methods
+            .insert(name.to_string(), handler);
+    }
+
+    pub fn value(&self, name: &str) -> Option<Vec<u8>> {
+        self.state.lock().unwrap().symbols.get(name).cloned()
+    }
+
+    /// Changes a symbol as if the PLC program wrote it, notifying subscribers.
+    pub fn set_value(&self, name: &str, value: impl Into<Vec<u8>>) {
+        let mut state = self.state.lock().unwrap();
+
+        state.symbols.insert(name.to_string(), value.into());
+
+        send(state.notifications_for(name));
+    }
+
+    /// Delays every reply, e.g: to exercise timeouts and cancellation. Requests are still handled as they arrive, so
+    /// several can be in flight at once.
+    pub fn set_reply_delay(&self, reply_delay: Duration) {
+        self.state.lock().unwrap().reply_delay = reply_delay;
+    }
+
+    /// Symbol handles the client has not released yet.
+    pub fn handle_count(&self) -> usize {
+        self.state.lock().unwrap().handles.len()
+    }
+
+    /// Rejects sum commands, like older runtimes that do not support them.
+    pub fn set_sum_commands_supported(&self, sum_commands_supported: bool) {
+        self.state.lock().unwrap().sum_commands_supported = sum_commands_supported;
+    }
+
+    /// Rejects new notifications, like a PLC that has run out of notification handles.
+    pub fn set_notifications_supported(&self, notifications_supported: bool) {
+        self.state.lock().unwrap().notifications_supported = notifications_supported;
+    }
+
+    /// Sum read and write commands received, whether or not they were supported.
+    pub fn sum_command_count(&self) -> usize {
+        self.state.lock().unwrap().sum_command_count
+    }
+
+    pub fn notification_count(&self) -> usize {
+        self.state.lock().unwrap().notifications.len()
+    }
+
+    pub fn ads_state(&self) -> AdsState {
+        AdsState::try_from(self.state.lock().unwrap().ads_state).unwrap_or(AdsState::Invalid)
+    }
+
+    /// Changes the ADS state, e.g: to stop the PLC. Clients can change it back with a write control request.
+    pub fn set_ads_state(&self, ads_state: AdsState) {
+        self.state.lock().unwrap().ads_state = ads_state as u16;
+    }
+
+    /// Removes a symbol, as if new code without it was downloaded. Handles to it stop working.
+    pub fn remove_symbol(&self, name: &str) {
+        let mut state = self.state.lock().unwrap();
+
+        state.symbols.remove(name);
+        state.symbol_types.remove(name);
+        state.handles.retain(|_, symbol| symbol != name);
+    }
+
+    /// Closes all client connections, as if the PLC went away.
+    pub fn drop_connections(&self) {
+        for stream in self.state.lock().unwrap().streams.drain(..) {
+            stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+impl Drop for MockAdsServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        // Wake the listener so it sees it was stopped
+        TcpStream::connect(self.address).ok();
+
+        self.drop_connections();
+    }
+}
+
+/// A builder for a connection to a mock PLC reached at the given address, directly or through a proxy.
+fn connection_builder(address: SocketAddr) -> PlcConnectionBuilder {
+    PlcConnectionBuilder::new(address, AmsAddr::new(AmsNetId::new(10, 0, 0, 1, 1, 1), 851))
+        .with_local_ams_address(Some(AmsAddr::new(AmsNetId::new(10, 0, 0, 2, 1, 1), 30000)))
+}
+
+impl ServerState {
+    fn notifications_for(&self, name: &str) -> Vec<Message> {
+        let Some(value) = self.symbols.get(name) else {
+            return Vec::new();
+        };
+
+        self.notifications
+            .iter()
+            .filter(|(_, route)| route.symbol == name)
+            .map(|(handle, route)| notification_message(route, *handle, value))
+            .collect()
+    }
+
+    /// A symbol entry as in the uploaded symbol table, starting with its own length.
+    fn symbol_entry(&self, name: &str) -> Option<Vec<u8>> {
+        let value = self.symbols.get(name)?;
+
+        let data_type = self
+            .symbol_types
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| format!("ARRAY [0..{}] OF BYTE", value.len().max(1) - 1));
+
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&0x4040u32.to_le_bytes());
+        entry.extend_from_slice(&0u32.to_le_bytes());
+        entry.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        entry.extend_from_slice(&ADST_BIGTYPE.to_le_bytes());
+        // Flags and legacy array dimensions
+        entry.extend_from_slice(&[0; 4]);
+        entry.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(data_type.len() as u16).to_le_bytes());
+        // No comment
+        entry.extend_from_slice(&0u16.to_le_bytes());
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry.extend_from_slice(data_type.as_bytes());
+        entry.push(0);
+        entry.push(0);
+
+        let length = (entry.len() + 4) as u32;
+
+        Some([&length.to_le_bytes()[..], &entry].concat())
+    }
+
+    /// Every symbol's entry, sorted by name.
+    fn symbol_table(&self) -> Vec<u8> {
+        let mut names: Vec<_> = self.symbols.keys().collect();
+        names.sort();
+
+        names
+            .into_iter()
+            .filter_map(|name| self.symbol_entry(name))
+            .flatten()
+            .collect()
+    }
+
+    /// Every data type's entry, sorted by name.
+    fn type_table(&self) -> Vec<u8> {
+        self.data_types
+            .iter()
+            .flat_map(|(name, (size, fields))| {
+                let sub_items = fields
+                    .iter()
+                    .map(|(name, data_type, offset, size)| {
+                        type_entry(name, data_type, *offset, *size, &[])
+                    })
+                    .collect::<Vec<_>>();
+
+                type_entry(name, "", 0, *size, &sub_items)
+            })
+            .collect()
+    }
+
+    fn symbol_for_handle(&self, handle: u32) -> Result<&String, u32> {
+        match self.handles.get(&handle) {
+            Some(name) => Ok(name),
+            None if self.invalidated_handles.contains(&handle) => Err(ERROR_SYMBOL_VERSION_INVALID),
+            None => Err(ERROR_SYMBOL_NOT_FOUND),
+        }
+    }
+
+    fn read_value(&self, handle: u32, length: usize) -> Result<Vec<u8>, u32> {
+        let name = self.symbol_for_handle(handle)?;
+        let value = self.symbols.get(name).ok_or(ERROR_SYMBOL_NOT_FOUND)?;
+
+        if length > value.len() {
+            return Err(ERROR_INVALID_SIZE);
+        }
+
+        Ok(value[..length].to_vec())
+    }
+
+    fn read_write(
+        &mut self,
+        index_group: u32,
+        index_offset: u32,
+        read_length: usize,
+        write_data: &[u8],
+    ) -> Result<Vec<u8>, u32> {
+        match index_group {
+            ads::index::GET_SYMHANDLE_BYNAME => {
+                let name = String::from_utf8_lossy(write_data).to_string();
+
+                if !self.symbols.contains_key(&name) && !self.rpc_methods.contains_key(&name) {
+                    return Err(ERROR_SYMBOL_NOT_FOUND);
+                }
+
+                let handle = self.next_handle;
+                self.next_handle += 1;
+                self.handles.insert(handle, name);
+
+                Ok(handle.to_le_bytes().to_vec())
+            }
+            ads::index::GET_SYMINFO_BYNAME => {
+                let name = String::from_utf8_lossy(write_data).to_string();
+
+                let value = self.symbols.get(&name).ok_or(ERROR_SYMBOL_NOT_FOUND)?;
+
+                let mut symbol_info = Vec::new();
+                symbol_info.extend_from_slice(&0x4040u32.to_le_bytes());
+                symbol_info.extend_from_slice(&0u32.to_le_bytes());
+                symbol_info.extend_from_slice(&(value.len() as u32).to_le_bytes());
+
+                Ok(symbol_info)
+            }
+            ads::index::GET_SYMINFO_BYNAME_EX => {
+                let name = String::from_utf8_lossy(write_data).to_string();
+
+                let entry = self.symbol_entry(&name).ok_or(ERROR_SYMBOL_NOT_FOUND)?;
+
+                if entry.len() > read_length {
+                    return Err(ERROR_INVALID_SIZE);
+                }
+
+                Ok(entry)
+            }
+            ads::index::RW_SYMVAL_BYHANDLE => {
+                let name = self.symbol_for_handle(index_offset)?.clone();
+
+                if let Some(handler) = self.rpc_methods.get_mut(&name) {
+                    if read_length > MAX_READ_LENGTH {
+                        return Err(ERROR_INVALID_SIZE);
+                    }
+
+                    let mut read_data = handler(write_data)?;
+                    read_data.resize(read_length, 0);
+
+                    return Ok(read_data);
+                }
+
+                self.read_value(index_offset, read_length)
+            }
+            _ => Err(ERROR_INVALID_GROUP),
+        }
+    }
+
+    /// Writes a symbol's value, returning the symbol's name.
+    fn write_value(&mut self, handle: u32, value: &[u8]) -> Result<String, u32> {
+        let name = self.symbol_for_handle(handle)?.clone();
+
+        let current = self.symbols.get_mut(&name).ok_or(ERROR_SYMBOL_NOT_FOUND)?;
+
+        if current.len() != value.len() {
+            return Err(ERROR_INVALID_SIZE);
+        }
+
+        *current = value.to_vec();
+
+        Ok(name)
+    }
+}
+
+fn serve(stream: TcpStream, state: Arc<Mutex<ServerState>>) {
+    let Ok(writer) = stream.try_clone() else {
+        return;
+    };
+
+    let writer = Arc::new(Mutex::new(writer));
+    let mut reader = stream;
+
+    loop {
+        let mut tcp_header = [0; 6];
+
+        if reader.read_exact(&mut tcp_header).is_err() {
+            return;
+        }
+
+        let ams_cmd = u16::from_le_bytes([tcp_header[0], tcp_header[1]]);
+        let length = read_u32(&tcp_header, 2) as usize;
+
+        let mut packet = vec![0; length];
+
+        if reader.read_exact(&mut packet).is_err() {
+            return;
+        }
+
+        match ams_cmd {
+            0 => {}
+            // Port open request, answered with the address assigned to the client
+            0x1000 => {
+                let mut reply = vec![0, 16, 8, 0, 0, 0];
+                reply.extend_from_slice(&[10, 0, 0, 2, 1, 1]);
+                reply.extend_from_slice(&30000u16.to_le_bytes());
+
+                writer.lock().unwrap().write_all(&reply).ok();
+
+                continue;
+            }
+            _ => continue,
+        }
+
+        // NB: a frame too short for an AMS header means the stream is out of step, so the connection is closed
+        if packet.len() < AMS_HEADER_SIZE - 6 {
+            reader.shutdown(Shutdown::Both).ok();
+
+            return;
+        }
+
+        let header = &packet[..32];
+        let data = &packet[32..];
+
+        let client: [u8; 8] = header[8..16].try_into().unwrap();
+        let server: [u8; 8] = header[0..8].try_into().unwrap();
+        let command = u16::from_le_bytes([header[16], header[17]]);
+        let invoke_id = read_u32(header, 28);
+
+        let mut state = state.lock().unwrap();
+
+        let (payload, notify) = if has_parameters(command, data) {
+            handle_command(&mut state, command, data, &writer, client, server)
+        } else {
+            (result(ERROR_INVALID_SIZE), None)
+        };
+
+        let mut reply = Vec::with_capacity(AMS_HEADER_SIZE + payload.len());
+        reply.extend_from_slice(&[0, 0]);
+        reply.extend_from_slice(&((32 + payload.len()) as u32).to_le_bytes());
+        reply.extend_from_slice(&client);
+        reply.extend_from_slice(&server);
+        reply.extend_from_slice(&command.to_le_bytes());
+        reply.extend_from_slice(&5u16.to_le_bytes());
+        reply.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        reply.extend_from_slice(&0u32.to_le_bytes());
+        reply.extend_from_slice(&invoke_id.to_le_bytes());
+        reply.extend_from_slice(&payload);
+
+        let mut messages = vec![(writer.clone(), reply)];
+
+        match notify {
+            Some(Notify::Symbols(names)) => {
+                for name in names {
+                    messages.extend(state.notifications_for(&name));
+                }
+            }
+            Some(Notify::Handle(handle)) => {
+                if let Some(route) = state.notifications.get(&handle) {
+                    if let Some(value) = state.symbols.get(&route.symbol) {
+                        messages.push(notification_message(route, handle, value));
+                    }
+                }
+            }
+            None => {}
+        }
+
+        let reply_delay = state.reply_delay;
+
+        drop(state);
+
+        if reply_delay.is_zero() {
+            send(messages);
+        } else {
+            std::thread::spawn(move || {
+                std::thread::sleep(reply_delay);
+
+                send(messages);
+            });
+        }
+    }
+}
+
+type Message = (Arc<Mutex<TcpStream>>, Vec<u8>);
+
+fn send(messages: Vec<Message>) {
+    for (writer, message) in messages {
+        writer.lock().unwrap().write_all(&message).ok();
+    }
+}
+
+enum Notify {
+    Symbols(Vec<String>),
+    Handle(u32),
+}
+
+fn handle_command(
+    state: &mut ServerState,
+    command: u16,
+    data: &[u8],
+    writer: &Arc<Mutex<TcpStream>>,
+    client: [u8; 8],
+    server: [u8; 8],
+) -> (Vec<u8>, Option<Notify>) {
+    // NB: in config mode the PLC runtime is not running, so the router has nothing to forward requests to
+    if state.ads_state == AdsState::Config as u16 {
+        return (result(ERROR_TARGET_PORT_NOT_FOUND), None);
+    }
+
+    match command {
+        // Read device info
+        1 => {
+            let mut payload = result(0);
+            payload.extend_from_slice(&[3, 1]);
+            payload.extend_from_slice(&4024u16.to_le_bytes());
+
+            let mut name = [0; 16];
+            name[..8].copy_from_slice(b"Mock PLC");
+            payload.extend_from_slice(&name);
+
+            (payload, None)
+        }
+        // Read
+        2 => {
+            let (index_group, index_offset, length) = (
+                read_u32(data, 0),
+                read_u32(data, 4),
+                read_u32(data, 8) as usize,
+            );
+
+            match index_group {
+                ads::index::GET_SYMVERSION => (with_data(&[state.symbol_version]), None),
+                ads::index::SYM_UPLOAD_INFO2 => {
+                    let symbol_table = state.symbol_table();
+                    let type_table = state.type_table();
+
+                    // Symbol count and length, data type count and length, extension count and length
+                    let upload_info: Vec<u8> = [
+                        state.symbols.len(),
+                        symbol_table.len(),
+                        state.data_types.len(),
+                        type_table.len(),
+                        0,
+                        0,
+                    ]
+                    .into_iter()
+                    .flat_map(|field| (field as u32).to_le_bytes())
+                    .collect();
+
+                    (with_data(&upload_info), None)
+                }
+                ads::index::SYM_UPLOAD => (with_data(&state.symbol_table()), None),
+                ads::index::SYM_DT_UPLOAD => (with_data(&state.type_table()), None),
+                ads::index::RW_SYMVAL_BYHANDLE => match state.read_value(index_offset, length) {
+                    Ok(value) => (with_data(&value), None),
+                    Err(code) => (result(code), None),
+                },
+                _ => (result(ERROR_INVALID_GROUP), None),
+            }
+        }
+        // Write
+        3 => {
+            let (index_group, index_offset, length) = (
+                read_u32(data, 0),
+                read_u32(data, 4),
+                read_u32(data, 8) as usize,
+            );
+            let value = &data[12..12 + length];
+
+            match index_group {
+                ads::index::RW_SYMVAL_BYHANDLE => match state.write_value(index_offset, value) {
+                    Ok(name) => (result(0), Some(Notify::Symbols(vec![name]))),
+                    Err(code) => (result(code), None),
+                },
+                ads::index::RELEASE_SYMHANDLE => {
+                    let Some(handle) = value.get(..4) else {
+                        return (result(ERROR_INVALID_SIZE), None);
+                    };
+
+                    state.handles.remove(&read_u32(handle, 0));
+
+                    (result(0), None)
+                }
+                _ => (result(ERROR_INVALID_GROUP), None),
+            }
+        }
+        // Read state
+        4 => {
+            let mut payload = result(0);
+            payload.extend_from_slice(&state.ads_state.to_le_bytes());
+            payload.extend_from_slice(&0u16.to_le_bytes());
+
+            (payload, None)
+        }
+        // Write control
+        5 => {
+            let ads_state = u16::from_le_bytes([data[0], data[1]]);
+
+            if AdsState::try_from(ads_state).is_err() {
+                return (result(ERROR_INVALID_DATA), None);
+            }
+
+            state.ads_state = ads_state;
+
+            (result(0), None)
+        }
+        // Add notification
+        6 => {
+            let (index_group, index_offset) = (read_u32(data, 0), read_u32(data, 4));
+
+            if index_group != ads::index::RW_SYMVAL_BYHANDLE {
+                return (result(ERROR_INVALID_GROUP), None);
+            }
+
+            if !state.notifications_supported {
+                return (result(ERROR_NO_MORE_HANDLES), None);
+            }
+
+            let symbol = match state.symbol_for_handle(index_offset) {
+                Ok(symbol) => symbol.clone(),
+                Err(code) => return (result(code), None),
+            };
+
+            let handle = state.next_handle;
+            state.next_handle += 1;
+
+            state.notifications.insert(
+                handle,
+                NotificationRoute {
+                    symbol,
+                    writer: writer.clone(),
+                    client,
+                    server,
+                },
+            );
+
+            let mut payload = result(0);
+            payload.extend_from_slice(&handle.to_le_bytes());
+
+            // The initial value is sent straight after the reply
+            (payload, Some(Notify::Handle(handle)))
+        }
+        // Delete notification
+        7 => match state.notifications.remove(&read_u32(data, 0)) {
+            Some(_) => (result(0), None),
+            None => (result(ERROR_NOTIFICATION_HANDLE_INVALID), None),
+        },
+        // Read/write
+        9 => {
+            let (index_group, index_offset) = (read_u32(data, 0), read_u32(data, 4));
+            let (read_length, write_length) =
+                (read_u32(data, 8) as usize, read_u32(data, 12) as usize);
+            let write_data = &data[16..16 + write_length];
+
+            match index_group {
+                ads::index::SUMUP_READ | ads::index::SUMUP_WRITE | ads::index::SUMUP_READWRITE => {
+                    state.sum_command_count += 1;
+
+                    if !state.sum_commands_supported {
+                        return (result(ERROR_INVALID_GROUP), None);
+                    }
+
+                    let count = index_offset as usize;
+
+                    // Like TwinCAT, which takes no more sub-commands than this in one sum command
+                    if count > MAX_SUM_COMMANDS {
+                        return (result(ERROR_INVALID_SIZE), None);
+                    }
+
+                    match index_group {
+                        ads::index::SUMUP_READ => sum_read(state, count, write_data),
+                        ads::index::SUMUP_WRITE => sum_write(state, count, write_data),
+                        _ => sum_read_write(state, count, write_data),
+                    }
+                }
+                _ => match state.read_write(index_group, index_offset, read_length, write_data) {
+                    Ok(read_data) => (with_data(&read_data), None),
+                    Err(code) => (result(code), None),
+                },
+            }
+        }
+        _ => (result(0x701), None),
+    }
+}
+
+/// Whether a request's data holds the parameters its command reads, e.g: a read's index group, offset and length.
+fn has_parameters(command: u16, data: &[u8]) -> bool {
+    let length_at = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|_| read_u32(data, offset) as usize)
+    };
+
+    match command {
+        2 | 6 => data.len() >= 12,
+        3 => length_at(8).is_some_and(|length| data.len() >= 12 + length),
+        5 | 7 => data.len() >= 4,
+        9 => length_at(12).is_some_and(|length| data.len() >= 16 + length),
+        _ => true,
+    }
+}
+
+/// Reads each sub-command's value, replying with all the result codes followed by all the data.
+fn sum_read(state: &ServerState, count: usize, requests: &[u8]) -> (Vec<u8>, Option<Notify>) {
+    let Some(sub_commands) = requests.get(..count * 12) else {
+        return (result(ERROR_INVALID_SIZE), None);
+    };
+
+    let mut codes = Vec::new();
+    let mut values = Vec::new();
+
+    for sub_command in sub_commands.chunks_exact(12) {
+        let (handle, length) = (read_u32(sub_command, 4), read_u32(sub_command, 8) as usize);
+
+        match state.read_value(handle, length) {
+            Ok(value) => {
+                codes.extend_from_slice(&0u32.to_le_bytes());
+                values.extend_from_slice(&value);
+            }
+            Err(_) if values.len() + length > MAX_READ_LENGTH => {
+                return (result(ERROR_INVALID_SIZE), None);
+            }
+            Err(code) => {
+                codes.extend_from_slice(&code.to_le_bytes());
+                values.resize(values.len() + length, 0);
+            }
+        }
+    }
+
+    codes.extend_from_slice(&values);
+
+    (with_data(&codes), None)
+}
+
+/// Writes each sub-command's value, taken from the data after all the sub-commands, replying with the result codes.
+fn sum_write(state: &mut ServerState, count: usize, requests: &[u8]) -> (Vec<u8>, Option<Notify>) {
+    let Some(sub_commands) = requests.get(..count * 12) else {
+        return (result(ERROR_INVALID_SIZE), None);
+    };
+
+    let mut writes = Vec::with_capacity(count);
+    let mut offset = sub_commands.len();
+
+    // NB: every sub-command is checked before any is written, so a malformed request changes nothing
+    for sub_command in sub_commands.chunks_exact(12) {
+        let length = read_u32(sub_command, 8) as usize;
+
+        let Some(value) = requests.get(offset..offset + length) else {
+            return (result(ERROR_INVALID_SIZE), None);
+        };
+
+        writes.push((read_u32(sub_command, 4), value));
+        offset += length;
+    }
+
+    let mut codes = Vec::new();
+    let mut names = Vec::new();
+
+    for (handle, value) in writes {
+        match state.write_value(handle, value) {
+            Ok(name) => {
+                codes.extend_from_slice(&0u32.to_le_bytes());
+                names.push(name);
+            }
+            Err(code) => codes.extend_from_slice(&code.to_le_bytes()),
+        }
+    }
+
+    (with_data(&codes), Some(Notify::Symbols(names)))
+}
+
+/// Runs each read/write sub-command, replying with each result code and read length followed by all the read data.
+fn sum_read_write(
+    state: &mut ServerState,
+    count: usize,
+    requests: &[u8],
+) -> (Vec<u8>, Option<Notify>) {
+    let Some(sub_commands) = requests.get(..count * 16) else {
+        return (result(ERROR_INVALID_SIZE), None);
+    };
+
+    let mut read_writes = Vec::with_capacity(count);
+    let mut offset = sub_commands.len();
+
+    for sub_command in sub_commands.chunks_exact(16) {
+        let write_length = read_u32(sub_command, 12) as usize;
+
+        let Some(write_data) = requests.get(offset..offset + write_length) else {
+            return (result(ERROR_INVALID_SIZE), None);
+        };
+
+        read_writes.push((sub_command, write_data));
+        offset += write_length;
+    }
+
+    let mut headers = Vec::new();
+    let mut values = Vec::new();
+
+    for (sub_command, write_data) in read_writes {
+        let (index_group, index_offset, read_length) = (
+            read_u32(sub_command, 0),
+            read_u32(sub_command, 4),
+            read_u32(sub_command, 8) as usize,
+        );
+
+        match state.read_write(index_group, index_offset, read_length, write_data) {
+            Ok(read_data) => {
+                headers.extend_from_slice(&0u32.to_le_bytes());
+                headers.extend_from_slice(&(read_data.len() as u32).to_le_bytes());
+                values.extend_from_slice(&read_data);
+            }
+            Err(code) => {
+                headers.extend_from_slice(&code.to_le_bytes());
+                headers.extend_from_slice(&0u32.to_le_bytes());
+            }
+        }
+    }
+
+    headers.extend_from_slice(&values);
+
+    (with_data(&headers), None)
+}
+
+/// A data type or field entry as in the uploaded data type table, starting with its own length.
+fn type_entry(
+    name: &str,
+    data_type: &str,
+    offset: usize,
+    size: usize,
+    sub_items: &[Vec<u8>],
+) -> Vec<u8> {
+    // The lower bound and element count of each dimension of e.g: `ARRAY [0..2, 1..4] OF INT`
+    let bounds: Vec<(i32, i32)> = data_type
+        .split_once('[')
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(bounds, _)| {
+            bounds
+                .split(',')
+                .filter_map(|dimension| {
+                    let (lower, upper) = dimension.split_once("..")?;
+                    let lower: i32 = lower.trim().parse().ok()?;
+
+                    Some((lower, upper.trim().parse::<i32>().ok()? - lower + 1))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // A data type, or a data item for a field
+    let flags: u32 = if sub_items.is_empty() && !data_type.is_empty() {
+        2
+    } else {
+        1
+    };
+
+    let mut entry = Vec::new();
+    entry.extend_from_slice(&1u32.to_le_bytes());
+    // Sub-item index, PLC interface ID and reserved
+    entry.extend_from_slice(&[0; 8]);
+    entry.extend_from_slice(&(size as u32).to_le_bytes());
+    entry.extend_from_slice(&(offset as u32).to_le_bytes());
+    entry.extend_from_slice(&ADST_BIGTYPE.to_le_bytes());
+    entry.extend_from_slice(&flags.to_le_bytes());
+    entry.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    entry.extend_from_slice(&(data_type.len() as u16).to_le_bytes());
+    // No comment
+    entry.extend_from_slice(&0u16.to_le_bytes());
+    entry.extend_from_slice(&(bounds.len() as u16).to_le_bytes());
+    entry.extend_from_slice(&(sub_items.len() as u16).to_le_bytes());
+    entry.extend_from_slice(name.as_bytes());
+    entry.push(0);
+    entry.extend_from_slice(data_type.as_bytes());
+    entry.push(0);
+    entry.push(0);
+
+    for (lower, count) in bounds {
+        entry.extend_from_slice(&lower.to_le_bytes());
+        entry.extend_from_slice(&count.to_le_bytes());
+    }
+
+    for sub_item in sub_items {
+        entry.extend_from_slice(sub_item);
+    }
+
+    let length = (entry.len() + 4) as u32;
+
+    [&length.to_le_bytes()[..], &entry].concat()
+}
+
+fn notification_message(route: &NotificationRoute, handle: u32, value: &[u8]) -> Message {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&1u32.to_le_bytes());
+    payload.extend_from_slice(&0u64.to_le_bytes());
+    payload.extend_from_slice(&1u32.to_le_bytes());
+    payload.extend_from_slice(&handle.to_le_bytes());
+    payload.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    payload.extend_from_slice(value);
+
+    let data_length = payload.len() as u32 + 4;
+
+    let mut message = Vec::new();
+    message.extend_from_slice(&[0, 0]);
+    message.extend_from_slice(&(32 + data_length).to_le_bytes());
+    message.extend_from_slice(&route.client);
+    message.extend_from_slice(&route.server);
+    message.extend_from_slice(&8u16.to_le_bytes());
+    message.extend_from_slice(&4u16.to_le_bytes());
+    message.extend_from_slice(&data_length.to_le_bytes());
+    message.extend_from_slice(&0u32.to_le_bytes());
+    message.extend_from_slice(&0u32.to_le_bytes());
+    message.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    message.extend_from_slice(&payload);
+
+    (route.writer.clone(), message)
+}
+
+fn result(code: u32) -> Vec<u8> {
+    code.to_le_bytes().to_vec()
+}
+
+fn with_data(data: &[u8]) -> Vec<u8> {
+    let mut payload = result(0);
+    payload.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    payload.extend_from_slice(data);
+
+    payload
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
@@ -0,0 +1,253 @@
+//! A TCP proxy between a connection and an ADS server, injecting faults into the AMS traffic it forwards.
+
+use std::{
+    io::{self, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use super::{connection_builder, AMS_HEADER_SIZE};
+use crate::{
+    plc_connection::{PlcConnection, PlcConnectionBuilder},
+    transport::{read_frame, TCP_HEADER_SIZE},
+};
+
+const COMMAND_NOTIFICATION: u16 = 8;
+const STATE_FLAG_RESPONSE: u16 = 0x0001;
+
+/// Forwards AMS/TCP between clients and an ADS server, breaking the connection in controlled ways, e.g: to exercise
+/// reconnection logic against the mock ADS server or the PLC simulator.
+///
+/// Faults apply to every connection through the proxy, including ones made after the fault was set. One-shot faults
+/// apply to the next reply from the server, whichever connection and request it belongs to; notifications are only
+/// ever delayed.
+pub struct FaultProxy {
+    address: SocketAddr,
+    faults: Arc<Mutex<Faults>>,
+    streams: Arc<Mutex<Vec<TcpStream>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Faults {
+    latency: Duration,
+    half_open: bool,
+    corrupt_next_reply: bool,
+    mismatch_next_invoke_id: bool,
+    next_error_code: Option<u32>,
+}
+
+impl FaultProxy {
+    /// Starts a proxy on a free local port, forwarding to the server at the given address.
+    ///
+    /// Panics if no local port can be bound.
+    pub fn start(server_address: SocketAddr) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("binding a local port");
+        let address = listener.local_addr().expect("a bound address");
+
+        let faults: Arc<Mutex<Faults>> = Default::default();
+        let streams: Arc<Mutex<Vec<TcpStream>>> = Default::default();
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        {
+            let faults = faults.clone();
+            let streams = streams.clone();
+            let stopped = stopped.clone();
+
+            std::thread::spawn(move || {
+                for client in listener.incoming() {
+                    let Ok(client) = client else {
+                        return;
+                    };
+
+                    if stopped.load(Ordering::SeqCst) {
+                        return;
+                    }
+
+                    // NB: a server that is not reachable shows up as a connection closed right after opening it
+                    if let Err(error) = link(client, server_address, &faults, &streams) {
+                        tracing::debug!(%error, "fault proxy could not reach the server");
+                    }
+                }
+            });
+        }
+
+        Self {
+            address,
+            faults,
+            streams,
+            stopped,
+        }
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// A builder for a connection through this proxy to the mock ADS server, to set further options on.
+    pub fn builder(&self) -> PlcConnectionBuilder {
+        connection_builder(self.address)
+    }
+
+    /// A connection through this proxy to the mock ADS server, not yet connected.
+    pub fn connection(&self) -> PlcConnection {
+        self.builder().build()
+    }
+
+    /// Delays every reply and notification from the server, e.g: to exceed the reply timeout.
+    pub fn set_latency(&self, latency: Duration) {
+        self.faults.lock().unwrap().latency = latency;
+    }
+
+    /// Stops forwarding in either direction while keeping the sockets open, like a peer that went away without
+    /// closing the connection. Requests are then left without a reply.
+    pub fn set_half_open(&self, half_open: bool) {
+        self.faults.lock().unwrap().half_open = half_open;
+    }
+
+    /// Closes every connection through the proxy, as if the network went away. New connections still get through.
+    pub fn drop_connections(&self) {
+        for stream in self.streams.lock().unwrap().drain(..) {
+            stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+
+    /// Corrupts the AMS header of the next reply, so its length no longer matches its data.
+    pub fn corrupt_next_reply(&self) {
+        self.faults.lock().unwrap().corrupt_next_reply = true;
+    }
+
+    /// Changes the invoke ID of the next reply, so it does not match any request in flight.
+    pub fn mismatch_next_invoke_id(&self) {
+        self.faults.lock().unwrap().mismatch_next_invoke_id = true;
+    }
+
+    /// Replaces the next reply with an error carrying the given ADS return code in its AMS header.
+    pub fn fail_next_reply(&self, code: u32) {
+        self.faults.lock().unwrap().next_error_code = Some(code);
+    }
+
+    /// Clears every fault, forwarding traffic untouched again.
+    pub fn heal(&self) {
+        *self.faults.lock().unwrap() = Faults::default();
+    }
+}
+
+impl Drop for FaultProxy {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        // Wake the listener so it sees it was stopped
+        TcpStream::connect(self.address).ok();
+
+        self.drop_connections();
+    }
+}
+
+impl Faults {
+    /// Applies the pending one-shot faults to a reply from the server.
+    fn apply(&mut self, frame: &mut Vec<u8>) {
+        if let Some(code) = self.next_error_code.take() {
+            // Error replies carry no data
+            frame.truncate(AMS_HEADER_SIZE);
+            frame[2..6]
+                .copy_from_slice(&((AMS_HEADER_SIZE - TCP_HEADER_SIZE) as u32).to_le_bytes());
+            frame[26..30].copy_from_slice(&0u32.to_le_bytes());
+            frame[30..34].copy_from_slice(&code.to_le_bytes());
+        }
+
+        if std::mem::take(&mut self.corrupt_next_reply) {
+            let length = u32::from_le_bytes(frame[26..30].try_into().unwrap());
+
+            frame[26..30].copy_from_slice(&length.wrapping_add(1).to_le_bytes());
+        }
+
+        if std::mem::take(&mut self.mismatch_next_invoke_id) {
+            let invoke_id = u32::from_le_bytes(frame[34..38].try_into().unwrap());
+
+            frame[34..38].copy_from_slice(&invoke_id.wrapping_add(1 << 31).to_le_bytes());
+        }
+    }
+}
+
+/// Connects a client to the server, forwarding each direction on its own thread.
+fn link(
+    client: TcpStream,
+    server_address: SocketAddr,
+    faults: &Arc<Mutex<Faults>>,
+    streams: &Arc<Mutex<Vec<TcpStream>>>,
+) -> io::Result<()> {
+    let server = TcpStream::connect(server_address)?;
+
+    client.set_nodelay(true).ok();
+    server.set_nodelay(true).ok();
+
+    {
+        let mut streams = streams.lock().unwrap();
+
+        streams.push(client.try_clone()?);
+        streams.push(server.try_clone()?);
+    }
+
+    {
+        let client = client.try_clone()?;
+        let server = server.try_clone()?;
+        let faults = faults.clone();
+
+        std::thread::spawn(move || forward(client, server, &faults, false));
+    }
+
+    let faults = faults.clone();
+
+    std::thread::spawn(move || forward(server, client, &faults, true));
+
+    Ok(())
+}
+
+/// Forwards whole AMS/TCP frames until either side closes, then closes both.
+fn forward(mut from: TcpStream, mut to: TcpStream, faults: &Mutex<Faults>, from_server: bool) {
+    while let Ok(mut frame) = read_frame(&mut from) {
+        let latency = {
+            let mut faults = faults.lock().unwrap();
+
+            if faults.half_open {
+                continue;
+            }
+
+            if from_server && is_reply(&frame) {
+                faults.apply(&mut frame);
+            }
+
+            if from_server {
+                faults.latency
+            } else {
+                Duration::ZERO
+            }
+        };
+
+        std::thread::sleep(latency);
+
+        if to.write_all(&frame).is_err() {
+            break;
+        }
+    }
+
+    from.shutdown(Shutdown::Both).ok();
+    to.shutdown(Shutdown::Both).ok();
+}
+
+/// Whether a frame is an AMS reply to a request, rather than a router message or a notification.
+fn is_reply(frame: &[u8]) -> bool {
+    if frame.len() < AMS_HEADER_SIZE || frame[0..2] != [0, 0] {
+        return false;
+    }
+
+    let command = u16::from_le_bytes([frame[22], frame[23]]);
+    let state_flags = u16::from_le_bytes([frame[24], frame[25]]);
+
+    command != COMMAND_NOTIFICATION && state_flags & STATE_FLAG_RESPONSE != 0
+}
@@ -0,0 +1,374 @@
+//! Serves a recorded session back to a connection, so a field issue can be reproduced without the machine.
+
+use std::{
+    collections::VecDeque,
+    io::Write,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use ads::{AmsAddr, AmsNetId};
+
+use crate::{
+    plc_connection::{PlcConnection, PlcConnectionBuilder},
+    recording::{Direction, Event, Recording},
+    transport::{read_frame, TCP_HEADER_SIZE},
+};
+
+use super::AMS_HEADER_SIZE;
+
+const COMMAND_NOTIFICATION: u16 = 8;
+
+// Returned for requests that are not in the recording
+const ERROR_SERVICE_NOT_SUPPORTED: u32 = 0x701;
+
+/// A local server replaying a recorded session, one recorded connection per connection made to it.
+///
+/// Each request is answered with the reply recorded for the same request, regardless of the invoke ID, followed by the
+/// notifications recorded before the next request. Requests missing from the recording fail with
+/// ADSERR_DEVICE_SRVNOTSUPP and are kept for inspection. Connections beyond the recorded ones are closed straight away,
+/// like a PLC that went away.
+///
+/// Connections made with [`ReplayServer::builder`] use the AMS addresses the session was recorded with.
+pub struct ReplayServer {
+    address: SocketAddr,
+    plc_ams_address: AmsAddr,
+    local_ams_address: Option<AmsAddr>,
+    state: Arc<Mutex<ReplayState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct ReplayState {
+    connections: VecDeque<Vec<Event>>,
+    unmatched: Vec<Vec<u8>>,
+    unanswered: usize,
+    realtime: bool,
+    streams: Vec<TcpStream>,
+}
+
+impl ReplayServer {
+    /// Starts replaying a recording on a free local port.
+    ///
+    /// Panics if no local port can be bound, or the recording has no AMS requests to take the addresses from.
+    pub fn start(recording: Recording) -> Self {
+        let (plc_ams_address, local_ams_address) =
+            recorded_addresses(&recording).expect("a recording with AMS requests");
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("binding a local port");
+        let address = listener.local_addr().expect("a bound address");
+
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let state = Arc::new(Mutex::new(ReplayState {
+            connections: recording.connections.into(),
+            ..Default::default()
+        }));
+
+        {
+            let state = state.clone();
+            let stopped = stopped.clone();
+
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else {
+                        return;
+                    };
+
+                    if stopped.load(Ordering::SeqCst) {
+                        return;
+                    }
+
+                    let events = {
+                        let mut state = state.lock().unwrap();
+
+                        let Some(events) = state.connections.pop_front() else {
+                            continue;
+                        };
+
+                        if let Ok(clone) = stream.try_clone() {
+                            state.streams.push(clone);
+                        }
+
+                        events
+                    };
+
+                    stream.set_nodelay(true).ok();
+
+                    let state = state.clone();
+
+                    std::thread::spawn(move || replay(stream, Session::new(events), state));
+                }
+            });
+        }
+
+        Self {
+            address,
+            plc_ams_address,
+            local_ams_address,
+            state,
+            stopped,
+        }
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// A builder for a connection to this server with the recorded AMS addresses, to set further options on.
+    pub fn builder(&self) -> PlcConnectionBuilder {
+        PlcConnectionBuilder::new(self.address, self.plc_ams_address)
+            .with_local_ams_address(self.local_ams_address)
+    }
+
+    /// A connection to this server, not yet connected.
+    pub fn connection(&self) -> PlcConnection {
+        self.builder().build()
+    }
+
+    /// Waits between frames as long as the recording did, instead of replying straight away.
+    pub fn set_realtime(&self, realtime: bool) {
+        self.state.lock().unwrap().realtime = realtime;
+    }
+
+    /// Requests that were not in the recording, in the order they arrived.
+    pub fn unmatched_requests(&self) -> Vec<Vec<u8>> {
+        self.state.lock().unwrap().unmatched.clone()
+    }
+
+    /// Whether every recorded connection was made and every recorded request was replayed.
+    pub fn is_complete(&self) -> bool {
+        let state = self.state.lock().unwrap();
+
+        state.connections.is_empty() && state.unanswered == 0
+    }
+
+    /// Closes all client connections, as if the PLC went away.
+    pub fn drop_connections(&self) {
+        for stream in self.state.lock().unwrap().streams.drain(..) {
+            stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+impl Drop for ReplayServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        // Wake the listener so it sees it was stopped
+        TcpStream::connect(self.address).ok();
+
+        self.drop_connections();
+    }
+}
+
+/// One recorded connection, tracking which of its frames were replayed.
+struct Session {
+    events: Vec<Event>,
+    replayed: Vec<bool>,
+}
+
+impl Session {
+    fn new(events: Vec<Event>) -> Self {
+        let replayed = vec![false; events.len()];
+
+        Self { events, replayed }
+    }
+
+    fn unanswered(&self) -> usize {
+        self.events
+            .iter()
+            .zip(&self.replayed)
+            .filter(|(event, replayed)| event.direction == Direction::Sent && !**replayed)
+            .count()
+    }
+
+    /// The recorded frames answering a request, each with how long after the request it was received.
+    fn answer(&mut self, request: &[u8]) -> Option<Vec<(Duration, Vec<u8>)>> {
+        let index = (0..self.events.len()).find(|&index| {
+            let event = &self.events[index];
+
+            !self.replayed[index]
+                && event.direction == Direction::Sent
+                && without_invoke_id(&event.frame) == without_invoke_id(request)
+        })?;
+
+        self.replayed[index] = true;
+
+        let requested = &self.events[index];
+        let mut frames = Vec::new();
+
+        let reply = (index + 1..self.events.len()).find(|&reply| {
+            let event = &self.events[reply];
+
+            !self.replayed[reply]
+                && event.direction == Direction::Received
+                && answers(&requested.frame, &event.frame)
+        });
+
+        if let Some(reply) = reply {
+            self.replayed[reply] = true;
+
+            let mut frame = self.events[reply].frame.clone();
+
+            if is_ams(&frame) {
+                frame[34..38].copy_from_slice(&request[34..38]);
+            }
+
+            frames.push((self.events[reply].elapsed, frame));
+        }
+
+        // Notifications recorded before the next request follow the reply
+        for notification in index + 1..self.events.len() {
+            let event = &self.events[notification];
+
+            if event.direction == Direction::Sent {
+                break;
+            }
+
+            if !self.replayed[notification] && is_notification(&event.frame) {
+                self.replayed[notification] = true;
+
+                frames.push((event.elapsed, event.frame.clone()));
+            }
+        }
+
+        Some(
+            frames
+                .into_iter()
+                .map(|(elapsed, frame)| (elapsed.saturating_sub(requested.elapsed), frame))
+                .collect(),
+        )
+    }
+}
+
+fn replay(stream: TcpStream, mut session: Session, state: Arc<Mutex<ReplayState>>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    let mut reader = stream;
+
+    state.lock().unwrap().unanswered += session.unanswered();
+
+    while let Ok(request) = read_frame(&mut reader) {
+        let (frames, realtime) = {
+            let mut state = state.lock().unwrap();
+
+            let frames = match session.answer(&request) {
+                Some(frames) => {
+                    state.unanswered -= 1;
+
+                    frames
+                }
+                None => {
+                    tracing::warn!(length = request.len(), "Request not found in the recording");
+
+                    state.unmatched.push(request.clone());
+
+                    error_reply(&request)
+                        .map(|reply| vec![(Duration::ZERO, reply)])
+                        .unwrap_or_default()
+                }
+            };
+
+            (frames, state.realtime)
+        };
+
+        let mut waited = Duration::ZERO;
+
+        for (after, frame) in frames {
+            if realtime {
+                std::thread::sleep(after.saturating_sub(waited));
+
+                waited = waited.max(after);
+            }
+
+            if writer.write_all(&frame).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// The PLC's address, and the client's own unless the router assigned it, from the first recorded connection.
+fn recorded_addresses(recording: &Recording) -> Option<(AmsAddr, Option<AmsAddr>)> {
+    let events = recording.connections.first()?;
+
+    let address = |bytes: &[u8]| {
+        AmsAddr::new(
+            AmsNetId::from_slice(&bytes[0..6]).expect("six bytes"),
+            u16::from_le_bytes([bytes[6], bytes[7]]),
+        )
+    };
+
+    let mut requests = events
+        .iter()
+        .filter(|event| event.direction == Direction::Sent);
+
+    let first = requests.next()?;
+
+    if is_ams(&first.frame) {
+        return Some((
+            address(&first.frame[6..14]),
+            Some(address(&first.frame[14..22])),
+        ));
+    }
+
+    // The router assigned the client's address, which the replayed port request gets again
+    requests
+        .find(|event| is_ams(&event.frame))
+        .map(|event| (address(&event.frame[6..14]), None))
+}
+
+/// Whether a frame carries an AMS packet, rather than a router message such as a port request.
+fn is_ams(frame: &[u8]) -> bool {
+    frame.len() >= AMS_HEADER_SIZE && frame[0..2] == [0, 0]
+}
+
+fn is_notification(frame: &[u8]) -> bool {
+    is_ams(frame) && u16::from_le_bytes([frame[22], frame[23]]) == COMMAND_NOTIFICATION
+}
+
+/// The frame with its invoke ID cleared, which differs between sessions for the same request.
+fn without_invoke_id(frame: &[u8]) -> Vec<u8> {
+    let mut frame = frame.to_vec();
+
+    if is_ams(&frame) {
+        frame[34..38].fill(0);
+    }
+
+    frame
+}
+
+/// Whether a received frame is the reply to a sent one.
+fn answers(request: &[u8], reply: &[u8]) -> bool {
+    match (is_ams(request), is_ams(reply)) {
+        (true, true) => !is_notification(reply) && request[34..38] == reply[34..38],
+        // Router messages carry no invoke ID, so a reply is the next router message
+        (false, false) => true,
+        _ => false,
+    }
+}
+
+/// An AMS error reply to a request, with the request's command and invoke ID.
+fn error_reply(request: &[u8]) -> Option<Vec<u8>> {
+    if !is_ams(request) {
+        return None;
+    }
+
+    let mut reply = request[..AMS_HEADER_SIZE].to_vec();
+
+    reply[2..6].copy_from_slice(&((AMS_HEADER_SIZE - TCP_HEADER_SIZE) as u32).to_le_bytes());
+    // Swaps the target and source addresses
+    reply[6..22].copy_from_slice(&[&request[14..22], &request[6..14]].concat());
+    reply[24..26].copy_from_slice(&5u16.to_le_bytes());
+    reply[26..30].copy_from_slice(&0u32.to_le_bytes());
+    reply[30..34].copy_from_slice(&ERROR_SERVICE_NOT_SUPPORTED.to_le_bytes());
+
+    Some(reply)
+}
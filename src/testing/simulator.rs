@@ -0,0 +1,277 @@
+//! A PLC simulator built on the mock ADS server, running scripted behaviours every cycle.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use ads::AdsState;
+
+use super::{send, Message, MockAdsServer, ServerState, ERROR_INVALID_SIZE};
+use crate::{data_types::PlcDataType, plc_connection::PlcConnection};
+
+type Behaviour = Box<dyn FnMut(&mut Cycle) + Send>;
+
+/// A simulated PLC, serving symbols like the mock ADS server while scripted behaviours change them every cycle.
+///
+/// Behaviours only run in run mode, like the PLC program would. In config mode the PLC does not answer at all.
+pub struct PlcSimulator {
+    server: MockAdsServer,
+    behaviours: Arc<Mutex<Vec<Behaviour>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl PlcSimulator {
+    /// Starts a simulator in run mode, running its behaviours every cycle time.
+    pub fn start(cycle_time: Duration) -> Self {
+        let server = MockAdsServer::start();
+        let behaviours: Arc<Mutex<Vec<Behaviour>>> = Default::default();
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        {
+            let state = server.state.clone();
+            let behaviours = behaviours.clone();
+            let stopped = stopped.clone();
+
+            std::thread::spawn(move || {
+                let mut number = 0;
+
+                while !stopped.load(Ordering::SeqCst) {
+                    std::thread::sleep(cycle_time);
+
+                    let mut behaviours = behaviours.lock().unwrap();
+                    let mut state = state.lock().unwrap();
+
+                    if state.ads_state != AdsState::Run as u16 {
+                        continue;
+                    }
+
+                    number += 1;
+
+                    let mut cycle = Cycle {
+                        state: &mut state,
+                        number,
+                        cycle_time,
+                        notifications: Vec::new(),
+                    };
+
+                    for behaviour in behaviours.iter_mut() {
+                        behaviour(&mut cycle);
+                    }
+
+                    let notifications = cycle.notifications;
+
+                    drop(state);
+
+                    send(notifications);
+                }
+            });
+        }
+
+        Self {
+            server,
+            behaviours,
+            stopped,
+        }
+    }
+
+    /// The underlying server, e.g: to check handles or drop connections.
+    pub fn server(&self) -> &MockAdsServer {
+        &self.server
+    }
+
+    /// A connection to the simulator, not yet connected.
+    pub fn connection(&self) -> PlcConnection {
+        self.server.connection()
+    }
+
+    pub fn add_symbol<T: PlcDataType>(&self, name: &str, value: T) {
+        self.server.add_symbol(name, value.as_bytes());
+    }
+
+    pub fn value<T: PlcDataType>(&self, name: &str) -> Option<T> {
+        self.server
+            .value(name)
+            .and_then(|value| T::from_bytes(&value))
+    }
+
+    /// Changes a symbol as if the PLC program wrote it, notifying subscribers.
+    pub fn set_value<T: PlcDataType>(&self, name: &str, value: T) {
+        self.server.set_value(name, value.as_bytes());
+    }
+
+    /// Adds a symbol whose next value is computed from its current one every cycle.
+    pub fn drive<T: PlcDataType>(
+        &self,
+        name: &str,
+        initial: T,
+        mut behaviour: impl FnMut(&Cycle, T) -> T + Send + 'static,
+    ) {
+        self.add_symbol(name, initial);
+
+        let name = name.to_string();
+
+        self.on_cycle(move |cycle| {
+            if let Some(value) = cycle.read::<T>(&name) {
+                let value = behaviour(cycle, value);
+
+                cycle.write(&name, value);
+            }
+        });
+    }
+
+    /// Runs a behaviour every cycle, e.g: one that reads some symbols and writes others.
+    pub fn on_cycle(&self, behaviour: impl FnMut(&mut Cycle) + Send + 'static) {
+        self.behaviours.lock().unwrap().push(Box::new(behaviour));
+    }
+
+    /// Adds an RPC method with typed parameters and result. Parameters of the wrong size fail the call.
+    pub fn add_rpc_handler<P: PlcDataType, R: PlcDataType>(
+        &self,
+        name: &str,
+        mut handler: impl FnMut(P) -> R + Send + 'static,
+    ) {
+        self.server.add_rpc_handler(
+            name,
+            Box::new(move |params| {
+                if params.len() != P::size() {
+                    return Err(ERROR_INVALID_SIZE);
+                }
+
+                let params = P::from_bytes(params).ok_or(ERROR_INVALID_SIZE)?;
+
+                Ok(handler(params).as_bytes().to_vec())
+            }),
+        );
+    }
+
+    pub fn ads_state(&self) -> AdsState {
+        self.server.ads_state()
+    }
+
+    /// Puts the PLC in run mode, resuming behaviours.
+    pub fn run(&self) {
+        self.server.set_ads_state(AdsState::Run);
+    }
+
+    /// Puts the PLC in stop mode, pausing behaviours. Symbols can still be read and written.
+    pub fn stop(&self) {
+        self.server.set_ads_state(AdsState::Stop);
+    }
+
+    /// Puts the PLC in config mode, in which every request fails as if the runtime was not started.
+    pub fn config(&self) {
+        self.server.set_ads_state(AdsState::Config);
+    }
+
+    /// Changes the symbol table like downloading new code with an online change.
+    ///
+    /// Handles to changed or removed symbols become invalid, failing with ADSERR_DEVICE_SYMBOLVERSIONINVALID, and
+    /// notifications on them stop.
+    pub fn online_change(&self, change: impl FnOnce(&mut OnlineChange)) {
+        let mut state = self.server.state.lock().unwrap();
+
+        let mut online_change = OnlineChange {
+            state: &mut state,
+            changed: Vec::new(),
+        };
+
+        change(&mut online_change);
+
+        let changed = online_change.changed;
+
+        let invalidated: Vec<u32> = state
+            .handles
+            .iter()
+            .filter(|(_, symbol)| changed.contains(symbol))
+            .map(|(handle, _)| *handle)
+            .collect();
+
+        for handle in invalidated {
+            state.handles.remove(&handle);
+            state.invalidated_handles.insert(handle);
+        }
+
+        state
+            .notifications
+            .retain(|_, route| !changed.contains(&route.symbol));
+
+        state.symbol_version = state.symbol_version.wrapping_add(1);
+    }
+}
+
+impl Drop for PlcSimulator {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+/// One cycle of the simulated PLC program, giving behaviours access to the symbol table.
+pub struct Cycle<'s> {
+    state: &'s mut ServerState,
+    number: u64,
+    cycle_time: Duration,
+    notifications: Vec<Message>,
+}
+
+impl Cycle<'_> {
+    /// Counts the cycles run so far, starting at 1.
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Simulated time since the PLC first ran, not counting time spent stopped.
+    pub fn elapsed(&self) -> Duration {
+        self.cycle_time * self.number as u32
+    }
+
+    pub fn read<T: PlcDataType>(&self, name: &str) -> Option<T> {
+        self.state
+            .symbols
+            .get(name)
+            .and_then(|value| T::from_bytes(value))
+    }
+
+    /// Writes a symbol, notifying subscribers at the end of the cycle if it changed.
+    pub fn write<T: PlcDataType>(&mut self, name: &str, value: T) {
+        let value = value.as_bytes();
+
+        if self.state.symbols.get(name).map(Vec::as_slice) == Some(value) {
+            return;
+        }
+
+        self.state.symbols.insert(name.to_string(), value.to_vec());
+
+        self.notifications
+            .extend(self.state.notifications_for(name));
+    }
+}
+
+/// Changes to the symbol table made by an online change.
+pub struct OnlineChange<'s> {
+    state: &'s mut ServerState,
+    changed: Vec<String>,
+}
+
+impl OnlineChange<'_> {
+    /// Adds a symbol, or replaces one with a new type and value.
+    pub fn add_symbol<T: PlcDataType>(&mut self, name: &str, value: T) {
+        let previous = self
+            .state
+            .symbols
+            .insert(name.to_string(), value.as_bytes().to_vec());
+
+        if previous.is_some_and(|previous| previous.len() != T::size()) {
+            self.changed.push(name.to_string());
+        }
+    }
+
+    pub fn remove_symbol(&mut self, name: &str) {
+        if self.state.symbols.remove(name).is_some() {
+            self.changed.push(name.to_string());
+        }
+    }
+}
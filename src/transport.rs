@@ -0,0 +1,702 @@
+//! AMS/TCP transport that keeps several requests in flight on one connection.
+//!
+//! The ads crate's Client waits for each reply before the next request can be sent. Here replies are matched to their
+//! requests by invoke ID instead, so any number of threads can share a connection.
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use ads::{client::Command, notif, AdsState, AmsAddr, AmsNetId, Error, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+use crate::{recording::Direction, return_code::ReturnCode};
+
+pub(crate) const TCP_HEADER_SIZE: usize = 6;
+const AMS_HEADER_SIZE: usize = 38;
+
+// Far beyond any frame a PLC sends, e.g: a large symbol upload, but keeps a corrupted length from allocating gigabytes
+const MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+// Ports opened by the router for clients that do not bring their own AMS address
+const AMS_PORT_OPEN: u16 = 0x1000;
+const AMS_PORT_CLOSE: u16 = 0x0001;
+
+type ReplySender = Sender<Result<Vec<u8>>>;
+
+pub(crate) struct AmsTransport {
+    writer: Mutex<TcpStream>,
+    source: AmsAddr,
+    source_port_opened: bool,
+    invoke_id: AtomicU32,
+    reply_timeout: Duration,
+    pending: Arc<PendingReplies>,
+    notification_receiver: Receiver<notif::Notification>,
+    taps: Taps,
+}
+
+/// Sees every AMS/TCP frame sent and received on a connection, e.g: to record or capture the traffic.
+pub(crate) trait FrameTap: Send + Sync {
+    /// Called once the TCP connection is made, before any of its frames.
+    fn connected(&self, local: SocketAddr, peer: SocketAddr);
+
+    fn frame(&self, direction: Direction, frame: &[u8]);
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Taps(Vec<Arc<dyn FrameTap>>);
+
+impl Taps {
+    pub(crate) fn new(taps: Vec<Arc<dyn FrameTap>>) -> Self {
+        Self(taps)
+    }
+
+    fn connected(&self, local: SocketAddr, peer: SocketAddr) {
+        for tap in &self.0 {
+            tap.connected(local, peer);
+        }
+    }
+
+    fn frame(&self, direction: Direction, frame: &[u8]) {
+        for tap in &self.0 {
+            tap.frame(direction, frame);
+        }
+    }
+}
+
+#[derive(Default)]
+struct PendingReplies {
+    replies: Mutex<HashMap<u32, ReplySender>>,
+    closed: AtomicBool,
+}
+
+impl PendingReplies {
+    /// Fails every outstanding request, as no more replies will arrive.
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+
+        for (_, reply_sender) in self.replies.lock().unwrap().drain() {
+            reply_sender
+                .send(Err(Error::Io(
+                    "receiving reply",
+                    io::ErrorKind::UnexpectedEof.into(),
+                )))
+                .ok();
+        }
+    }
+}
+
+impl AmsTransport {
+    /// Connects to an AMS router, asking it for a source address unless one is given.
+    pub(crate) fn connect(
+        address: SocketAddr,
+        source: Option<AmsAddr>,
+        connect_timeout: Duration,
+        reply_timeout: Duration,
+        taps: Taps,
+    ) -> Result<Self> {
+        let mut socket = TcpStream::connect_timeout(&address, connect_timeout)
+            .map_err(|error| Error::Io("connecting TCP socket with timeout", error))?;
+
+        socket
+            .set_nodelay(true)
+            .map_err(|error| Error::Io("setting NODELAY", error))?;
+        socket
+            .set_write_timeout(Some(reply_timeout))
+            .map_err(|error| Error::Io("setting write timeout", error))?;
+
+        let local_address = socket
+            .local_addr()
+            .map_err(|error| Error::Io("getting local address", error))?;
+
+        taps.connected(local_address, address);
+
+        let (source, source_port_opened) = match source {
+            Some(source) => (source, false),
+            None => (request_port(&mut socket, reply_timeout, &taps)?, true),
+        };
+
+        let reader_socket = socket
+            .try_clone()
+            .map_err(|error| Error::Io("cloning TCP socket", error))?;
+
+        let pending = Arc::new(PendingReplies::default());
+        let (notification_sender, notification_receiver) = crossbeam_channel::unbounded();
+
+        let reader = Reader {
+            socket: reader_socket,
+            source,
+            pending: pending.clone(),
+            notification_sender,
+            taps: taps.clone(),
+        };
+
+        std::thread::spawn(move || reader.run());
+
+        Ok(Self {
+            writer: Mutex::new(socket),
+            source,
+            source_port_opened,
+            invoke_id: AtomicU32::new(0),
+            reply_timeout,
+            pending,
+            notification_receiver,
+            taps,
+        })
+    }
+
+    pub(crate) fn device(&self, mut address: AmsAddr) -> Device<'_> {
+        // Same as the ads crate, the local NetID addresses the router we are connected to
+        if address.netid() == AmsNetId::local() {
+            address = AmsAddr::new(self.source.netid(), address.port());
+        }
+
+        Device {
+            transport: self,
+            address,
+        }
+    }
+
+    pub(crate) fn notification_receiver(&self) -> Receiver<notif::Notification> {
+        self.notification_receiver.clone()
+    }
+
+    /// Whether the connection was closed, after which every request fails straight away.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.pending.closed.load(Ordering::SeqCst)
+    }
+
+    /// Sends a request and waits for its reply, returning the reply data following the result field.
+    fn communicate(&self, command: Command, target: AmsAddr, data: &[&[u8]]) -> Result<Vec<u8>> {
+        let action = action(command);
+
+        if self.is_closed() {
+            return Err(Error::Io(
+                "sending request",
+                io::ErrorKind::NotConnected.into(),
+            ));
+        }
+
+        let invoke_id = self
+            .invoke_id
+            .fetch_add(1, Ordering::SeqCst)
+            .wrapping_add(1);
+        let data_length: usize = data.iter().map(|data| data.len()).sum();
+
+        let mut request = Vec::with_capacity(AMS_HEADER_SIZE + data_length);
+        request.extend_from_slice(&[0, 0]);
+        request.extend_from_slice(
+            &u32::try_from(AMS_HEADER_SIZE - TCP_HEADER_SIZE + data_length)?.to_le_bytes(),
+        );
+        push_address(&mut request, target);
+        push_address(&mut request, self.source);
+        request.extend_from_slice(&(command as u16).to_le_bytes());
+        request.extend_from_slice(&4u16.to_le_bytes());
+        request.extend_from_slice(&(data_length as u32).to_le_bytes());
+        request.extend_from_slice(&0u32.to_le_bytes());
+        request.extend_from_slice(&invoke_id.to_le_bytes());
+
+        for data in data {
+            request.extend_from_slice(data);
+        }
+
+        let (reply_sender, reply_receiver) = crossbeam_channel::bounded(1);
+
+        // Registered before sending, so a fast reply cannot arrive before anyone waits for it
+        self.pending
+            .replies
+            .lock()
+            .unwrap()
+            .insert(invoke_id, reply_sender);
+
+        {
+            let mut writer = self.writer.lock().unwrap();
+
+            // NB: tapped before it is sent, so the reply cannot be tapped first
+            self.taps.frame(Direction::Sent, &request);
+
+            if let Err(error) = writer.write_all(&request) {
+                self.pending.replies.lock().unwrap().remove(&invoke_id);
+
+                return Err(Error::Io("sending request", error));
+            }
+        }
+
+        let reply = match reply_receiver.recv_timeout(self.reply_timeout) {
+            Ok(reply) => reply?,
+            Err(RecvTimeoutError::Timeout) => {
+                self.pending.replies.lock().unwrap().remove(&invoke_id);
+
+                return Err(Error::Io(
+                    "receiving reply (route set?)",
+                    io::ErrorKind::TimedOut.into(),
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(Error::Io(
+                    "receiving reply",
+                    io::ErrorKind::UnexpectedEof.into(),
+                ));
+            }
+        };
+
+        let reply_command = read_u16(&reply, 22);
+        let state_flags = read_u16(&reply, 24);
+        let error_code = read_u32(&reply, 30);
+
+        if reply_command != command as u16 {
+            return Err(Error::Reply(
+                action,
+                "unexpected command",
+                reply_command.into(),
+            ));
+        }
+
+        if state_flags != 5 {
+            return Err(Error::Reply(
+                action,
+                "unexpected state flags",
+                state_flags.into(),
+            ));
+        }
+
+        if error_code != 0 {
+            return Err(ads_error(action, error_code));
+        }
+
+        if reply.len() < AMS_HEADER_SIZE + 4 {
+            return Err(Error::Reply(
+                action,
+                "got less data than expected",
+                reply.len() as u32,
+            ));
+        }
+
+        let result = read_u32(&reply, AMS_HEADER_SIZE);
+
+        if result != 0 {
+            return Err(ads_error(action, result));
+        }
+
+        Ok(reply[AMS_HEADER_SIZE + 4..].to_vec())
+    }
+}
+
+impl Drop for AmsTransport {
+    fn drop(&mut self) {
+        let mut writer = self.writer.lock().unwrap();
+
+        if self.source_port_opened {
+            let mut close_port = AMS_PORT_CLOSE.to_le_bytes().to_vec();
+            close_port.extend_from_slice(&2u32.to_le_bytes());
+            close_port.extend_from_slice(&self.source.port().to_le_bytes());
+
+            writer.write_all(&close_port).ok();
+
+            self.taps.frame(Direction::Sent, &close_port);
+        }
+
+        // Also stops the reader thread, which shares the socket
+        writer.shutdown(Shutdown::Both).ok();
+    }
+}
+
+/// Operations on one ADS device, mirroring the ads crate's Device.
+#[derive(Clone, Copy)]
+pub(crate) struct Device<'t> {
+    transport: &'t AmsTransport,
+    address: AmsAddr,
+}
+
+impl Device<'_> {
+    pub(crate) fn get_info(&self) -> Result<ads::client::DeviceInfo> {
+        let reply = self.exact(Command::DevInfo, &[], 20)?;
+
+        let name = reply[4..]
+            .iter()
+            .take_while(|&&character| character > 0)
+            .map(|&character| character as char)
+            .collect();
+
+        Ok(ads::client::DeviceInfo {
+            name,
+            major: reply[0],
+            minor: reply[1],
+            version: read_u16(&reply, 2),
+        })
+    }
+
+    pub(crate) fn get_state(&self) -> Result<(AdsState, u16)> {
+        let reply = self.exact(Command::ReadState, &[], 4)?;
+
+        let ads_state = AdsState::try_from(read_u16(&reply, 0))
+            .map_err(|error| Error::Reply("read state", error, read_u16(&reply, 0).into()))?;
+
+        Ok((ads_state, read_u16(&reply, 2)))
+    }
+
+    pub(crate) fn write_control(&self, ads_state: AdsState, dev_state: u16) -> Result<()> {
+        let mut data = Vec::with_capacity(8);
+        data.extend_from_slice(&(ads_state as u16).to_le_bytes());
+        data.extend_from_slice(&dev_state.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        self.transport
+            .communicate(Command::WriteControl, self.address, &[&data])?;
+
+        Ok(())
+    }
+
+    /// Reads exactly as much data as fits the buffer.
+    pub(crate) fn read_exact(
+        &self,
+        index_group: u32,
+        index_offset: u32,
+        data: &mut [u8],
+    ) -> Result<()> {
+        let request = index_request(index_group, index_offset, &[u32::try_from(data.len())?]);
+
+        let reply = self
+            .transport
+            .communicate(Command::Read, self.address, &[&request])?;
+
+        copy_data(Command::Read, &reply, data)
+    }
+
+    pub(crate) fn write(&self, index_group: u32, index_offset: u32, data: &[u8]) -> Result<()> {
+        let request = index_request(index_group, index_offset, &[u32::try_from(data.len())?]);
+
+        self.transport
+            .communicate(Command::Write, self.address, &[&request, data])?;
+
+        Ok(())
+    }
+
+    /// Writes the data then reads back however much data the device returns, up to the maximum length.
+    pub(crate) fn write_read(
+        &self,
+        index_group: u32,
+        index_offset: u32,
+        write_data: &[u8],
+        max_length: usize,
+    ) -> Result<Vec<u8>> {
+        let request = index_request(
+            index_group,
+            index_offset,
+            &[u32::try_from(max_length)?, u32::try_from(write_data.len())?],
+        );
+
+        let reply = self.transport.communicate(
+            Command::ReadWrite,
+            self.address,
+            &[&request, write_data],
+        )?;
+
+        if reply.len() < 4 {
+            return Err(Error::Reply(
+                action(Command::ReadWrite),
+                "got less data than expected",
+                reply.len() as u32,
+            ));
+        }
+
+        let length = (read_u32(&reply, 0) as usize).min(reply.len() - 4);
+
+        Ok(reply[4..4 + length].to_vec())
+    }
+
+    /// Writes the data then reads back exactly as much data as fits the read buffer.
+    pub(crate) fn write_read_exact(
+        &self,
+        index_group: u32,
+        index_offset: u32,
+        write_data: &[u8],
+        read_data: &mut [u8],
+    ) -> Result<()> {
+        let request = index_request(
+            index_group,
+            index_offset,
+            &[
+                u32::try_from(read_data.len())?,
+                u32::try_from(write_data.len())?,
+            ],
+        );
+
+        let reply = self.transport.communicate(
+            Command::ReadWrite,
+            self.address,
+            &[&request, write_data],
+        )?;
+
+        copy_data(Command::ReadWrite, &reply, read_data)
+    }
+
+    pub(crate) fn add_notification(
+        &self,
+        index_group: u32,
+        index_offset: u32,
+        attributes: &notif::Attributes,
+    ) -> Result<notif::Handle> {
+        let request = index_request(
+            index_group,
+            index_offset,
+            &[
+                u32::try_from(attributes.length)?,
+                attributes.trans_mode as u32,
+                u32::try_from(attributes.max_delay.as_millis())?,
+                u32::try_from(attributes.cycle_time.as_millis())?,
+                0,
+                0,
+                0,
+                0,
+            ],
+        );
+
+        let reply =
+            self.transport
+                .communicate(Command::AddNotification, self.address, &[&request])?;
+
+        if reply.len() < 4 {
+            return Err(Error::Reply(
+                action(Command::AddNotification),
+                "got less data than expected",
+                reply.len() as u32,
+            ));
+        }
+
+        Ok(read_u32(&reply, 0))
+    }
+
+    pub(crate) fn delete_notification(&self, handle: notif::Handle) -> Result<()> {
+        self.transport.communicate(
+            Command::DeleteNotification,
+            self.address,
+            &[&handle.to_le_bytes()],
+        )?;
+
+        Ok(())
+    }
+
+    fn exact(&self, command: Command, data: &[&[u8]], length: usize) -> Result<Vec<u8>> {
+        let reply = self.transport.communicate(command, self.address, data)?;
+
+        if reply.len() < length {
+            return Err(Error::Reply(
+                action(command),
+                "got less data than expected",
+                reply.len() as u32,
+            ));
+        }
+
+        Ok(reply)
+    }
+}
+
+// Takes replies and notifications off the socket and hands them out
+struct Reader {
+    socket: TcpStream,
+    source: AmsAddr,
+    pending: Arc<PendingReplies>,
+    notification_sender: Sender<notif::Notification>,
+    taps: Taps,
+}
+
+impl Reader {
+    fn run(mut self) {
+        if let Err(error) = self.run_inner() {
+            tracing::debug!(%error, "AMS connection closed");
+        }
+
+        self.pending.close();
+
+        self.socket.shutdown(Shutdown::Both).ok();
+    }
+
+    fn run_inner(&mut self) -> io::Result<()> {
+        let mut source = Vec::with_capacity(8);
+        push_address(&mut source, self.source);
+
+        loop {
+            let packet = read_frame(&mut self.socket)?;
+
+            self.taps.frame(Direction::Received, &packet);
+
+            if read_u16(&packet, 0) != 0 {
+                // Router messages, e.g: port status
+                continue;
+            }
+
+            if packet.len() < AMS_HEADER_SIZE
+                || read_u32(&packet, 26) as usize != packet.len() - AMS_HEADER_SIZE
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "inconsistent AMS packet",
+                ));
+            }
+
+            if packet[6..14] != source[..] {
+                continue;
+            }
+
+            if read_u16(&packet, 22) == Command::Notification as u16 {
+                if let Ok(notification) = notif::Notification::new(packet) {
+                    self.notification_sender.send(notification).ok();
+                }
+
+                continue;
+            }
+
+            let invoke_id = read_u32(&packet, 34);
+
+            // Replies to requests that already timed out are dropped
+            if let Some(reply_sender) = self.pending.replies.lock().unwrap().remove(&invoke_id) {
+                reply_sender.send(Ok(packet)).ok();
+            }
+        }
+    }
+}
+
+fn request_port(socket: &mut TcpStream, timeout: Duration, taps: &Taps) -> Result<AmsAddr> {
+    let mut request = AMS_PORT_OPEN.to_le_bytes().to_vec();
+    request.extend_from_slice(&2u32.to_le_bytes());
+    request.extend_from_slice(&[0, 0]);
+
+    let mut reply = [0; 14];
+
+    socket
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| socket.write_all(&request))
+        .and_then(|_| socket.read_exact(&mut reply))
+        .and_then(|_| socket.set_read_timeout(None))
+        .map_err(|error| Error::Io("requesting port from router", error))?;
+
+    taps.frame(Direction::Sent, &request);
+    taps.frame(Direction::Received, &reply);
+
+    if reply[..6] != [0, 16, 8, 0, 0, 0] {
+        return Err(Error::Reply(
+            "requesting port",
+            "unexpected reply header",
+            0,
+        ));
+    }
+
+    let netid = AmsNetId::from_slice(&reply[6..12]).expect("six bytes");
+
+    Ok(AmsAddr::new(netid, read_u16(&reply, 12)))
+}
+
+fn index_request(index_group: u32, index_offset: u32, rest: &[u32]) -> Vec<u8> {
+    [index_group, index_offset]
+        .iter()
+        .chain(rest)
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Copies the length prefixed data of a read reply into the buffer, which it must fill.
+fn copy_data(command: Command, reply: &[u8], data: &mut [u8]) -> Result<()> {
+    let length = if reply.len() >= 4 {
+        (read_u32(reply, 0) as usize).min(reply.len() - 4)
+    } else {
+        0
+    };
+
+    if length < data.len() {
+        return Err(Error::Reply(
+            action(command),
+            "got less data than expected",
+            length as u32,
+        ));
+    }
+
+    data.copy_from_slice(&reply[4..4 + data.len()]);
+
+    Ok(())
+}
+
+/// Reads an AMS/TCP frame, including its TCP header, rejecting lengths no PLC sends as invalid data.
+pub(crate) fn read_frame(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut frame = vec![0; TCP_HEADER_SIZE];
+
+    reader.read_exact(&mut frame)?;
+
+    let length = read_u32(&frame, 2) as usize;
+
+    if length > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("AMS/TCP frame of {length} bytes is too long"),
+        ));
+    }
+
+    frame.resize(TCP_HEADER_SIZE + length, 0);
+
+    reader.read_exact(&mut frame[TCP_HEADER_SIZE..])?;
+
+    Ok(frame)
+}
+
+pub(crate) fn ads_error(action: &'static str, code: u32) -> Error {
+    let description = ReturnCode::lookup(code)
+        .map_or("Unknown error code", |return_code| return_code.description);
+
+    Error::Ads(action, description, code)
+}
+
+fn action(command: Command) -> &'static str {
+    match command {
+        Command::DevInfo => "get device info",
+        Command::Read => "read data",
+        Command::Write => "write data",
+        Command::ReadWrite => "write and read data",
+        Command::ReadState => "read state",
+        Command::WriteControl => "write control",
+        Command::AddNotification => "add notification",
+        Command::DeleteNotification => "delete notification",
+        Command::Notification => "notification",
+    }
+}
+
+fn push_address(buffer: &mut Vec<u8>, address: AmsAddr) {
+    buffer.extend_from_slice(&address.netid().0);
+    buffer.extend_from_slice(&address.port().to_le_bytes());
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().expect("four bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_read_up_to_their_length() {
+        let mut stream = &[0, 0, 2, 0, 0, 0, 1, 2, 3][..];
+
+        assert_eq!(read_frame(&mut stream).unwrap(), [0, 0, 2, 0, 0, 0, 1, 2]);
+        assert_eq!(stream, [3]);
+    }
+
+    #[test]
+    fn corrupted_lengths_are_invalid_data() {
+        let mut stream = &[0, 0, 0xff, 0xff, 0xff, 0xff, 1, 2, 3][..];
+
+        let error = read_frame(&mut stream).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,157 @@
+#![cfg(feature = "tokio")]
+
+use std::time::Duration;
+
+use ads_client::{
+    async_connection::AsyncPlcConnection,
+    data_types::primitives::{int::PlcInt, udint::PlcUDInt},
+    error::PlcError,
+    testing::MockAdsServer,
+};
+use futures_util::StreamExt;
+
+async fn connect(server: &MockAdsServer) -> AsyncPlcConnection {
+    let connection = AsyncPlcConnection::new(server.connection());
+
+    connection.run_connection_loop().await;
+
+    connection
+}
+
+#[tokio::test]
+async fn reads_and_writes_symbols() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = connect(&server).await;
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").await.unwrap();
+    assert_eq!(i16::from(value), 7);
+
+    connection
+        .write_symbol("MAIN.nCounter", PlcInt::from(42))
+        .await
+        .unwrap();
+    assert_eq!(
+        server.value("MAIN.nCounter"),
+        Some(42i16.to_le_bytes().to_vec())
+    );
+}
+
+#[tokio::test]
+async fn fetches_from_rpc_methods() {
+    let server = MockAdsServer::start();
+    server.add_rpc_method("MAIN.fbAxis#GetPosition", |_| {
+        1234u32.to_le_bytes().to_vec()
+    });
+
+    let connection = connect(&server).await;
+
+    let position: PlcUDInt = connection
+        .fetch_from_rpc_method("MAIN.fbAxis#GetPosition")
+        .await
+        .unwrap();
+
+    assert_eq!(u32::from(position), 1234);
+}
+
+#[tokio::test]
+async fn requests_fail_when_not_connected() {
+    let server = MockAdsServer::start();
+
+    let connection = AsyncPlcConnection::new(server.connection());
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nCounter").await;
+
+    assert!(matches!(result, Err(PlcError::NotConnected)));
+}
+
+#[tokio::test]
+async fn subscriptions_stream_changes() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 1i16.to_le_bytes());
+
+    let connection = connect(&server).await;
+
+    let mut stream = connection
+        .subscribe::<PlcInt>("MAIN.nCounter")
+        .await
+        .unwrap();
+
+    let initial = stream.next().await.unwrap();
+    assert_eq!(i16::from(initial), 1);
+
+    server.set_value("MAIN.nCounter", 2i16.to_le_bytes());
+
+    let changed = stream.next().await.unwrap();
+    assert_eq!(i16::from(changed), 2);
+}
+
+#[tokio::test]
+async fn dropping_a_stream_unsubscribes() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 1i16.to_le_bytes());
+
+    let connection = connect(&server).await;
+
+    let stream = connection
+        .subscribe::<PlcInt>("MAIN.nCounter")
+        .await
+        .unwrap();
+
+    assert_eq!(server.notification_count(), 1);
+
+    drop(stream);
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while server.notification_count() > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn cancelled_requests_leave_the_connection_usable() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = connect(&server).await;
+
+    server.set_reply_delay(Duration::from_millis(200));
+
+    let cancelled = tokio::time::timeout(
+        Duration::from_millis(10),
+        connection.read_symbol::<PlcInt>("MAIN.nCounter"),
+    )
+    .await;
+
+    assert!(cancelled.is_err());
+
+    server.set_reply_delay(Duration::ZERO);
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").await.unwrap();
+    assert_eq!(i16::from(value), 7);
+}
+
+#[tokio::test]
+async fn streams_end_when_the_connection_drops() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 1i16.to_le_bytes());
+
+    let connection = connect(&server).await;
+
+    let mut stream = connection
+        .subscribe::<PlcInt>("MAIN.nCounter")
+        .await
+        .unwrap();
+
+    stream.next().await.unwrap();
+
+    connection.disconnect().await;
+
+    let end = tokio::time::timeout(Duration::from_secs(2), stream.next()).await;
+
+    assert!(matches!(end, Ok(None)));
+}
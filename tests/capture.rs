@@ -0,0 +1,69 @@
+use ads_client::{
+    capture::{PacketCapture, ADS_TCP_PORT},
+    data_types::primitives::int::PlcInt,
+    testing::MockAdsServer,
+};
+
+/// The TCP payloads in a pcap capture of IPv4 packets, with their source and destination ports.
+fn segments(capture: &[u8]) -> Vec<(u16, u16, &[u8])> {
+    let mut segments = Vec::new();
+    let mut offset = 24;
+
+    while offset < capture.len() {
+        let length =
+            u32::from_le_bytes(capture[offset + 8..offset + 12].try_into().unwrap()) as usize;
+        let packet = &capture[offset + 16..offset + 16 + length];
+
+        let tcp = &packet[20..];
+        let ports = (
+            u16::from_be_bytes([tcp[0], tcp[1]]),
+            u16::from_be_bytes([tcp[2], tcp[3]]),
+        );
+
+        segments.push((ports.0, ports.1, &tcp[20..]));
+        offset += 16 + length;
+    }
+
+    segments
+}
+
+#[test]
+fn frames_are_captured_for_wireshark() {
+    let path = std::env::temp_dir().join(format!("ads-client-{}.pcap", std::process::id()));
+
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server
+        .builder()
+        .with_capture(Some(PacketCapture::create(&path).unwrap()))
+        .build();
+    connection.run_connection_loop();
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+    assert_eq!(i16::from(value), 7);
+
+    connection.disconnect();
+    drop(connection);
+
+    let capture = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let segments = segments(&capture);
+
+    // A handshake, then every frame in its own segment
+    assert!(segments.len() > 3);
+    assert!(segments[..3]
+        .iter()
+        .all(|(_, _, payload)| payload.is_empty()));
+    assert!(segments[3..].iter().all(|(source, destination, payload)| {
+        (*source == ADS_TCP_PORT) != (*destination == ADS_TCP_PORT)
+            && payload.len() == 6 + u32::from_le_bytes(payload[2..6].try_into().unwrap()) as usize
+    }));
+
+    // The reply to the read carries the value
+    assert!(segments
+        .iter()
+        .any(|(source, _, payload)| *source == ADS_TCP_PORT
+            && payload.ends_with(&[2, 0, 0, 0, 7, 0])));
+}
@@ -0,0 +1,270 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    process::{Child, Command, Output, Stdio},
+    time::{Duration, Instant},
+};
+
+use ads_client::testing::MockAdsServer;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The CLI, configured to reach the mock PLC through its router port request.
+fn cli(server: &MockAdsServer, arguments: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ads-cli"));
+
+    command
+        .args(arguments)
+        .env("ADS_ROUTER_IP", server.address().ip().to_string())
+        .env("ADS_ROUTER_PORT", server.address().port().to_string())
+        .env("PLC_AMS_NET_ID", "10.0.0.1.1.1")
+        .env("PLC_AMS_PORT", "851")
+        .env_remove("LOCAL_AMS_NET_ID")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    command
+}
+
+fn wait(mut child: Child) -> Output {
+    let deadline = Instant::now() + TIMEOUT;
+
+    while child.try_wait().unwrap().is_none() {
+        if Instant::now() > deadline {
+            child.kill().ok();
+
+            panic!("ads-cli did not finish in time");
+        }
+
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    child.wait_with_output().unwrap()
+}
+
+fn run(server: &MockAdsServer, arguments: &[&str]) -> (bool, String, String) {
+    let output = wait(cli(server, arguments).spawn().unwrap());
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn info_and_state_are_shown() {
+    let server = MockAdsServer::start();
+
+    let (success, stdout, _) = run(&server, &["info"]);
+    assert!(success);
+    assert_eq!(stdout, "Mock PLC 3.1.4024\nState: Run\n");
+
+    let (success, stdout, _) = run(&server, &["state", "--json"]);
+    assert!(success);
+    assert_eq!(stdout, "{\"state\":\"Run\"}\n");
+}
+
+#[test]
+fn symbols_are_read_by_their_type_on_the_plc() {
+    let server = MockAdsServer::start();
+    server.add_symbol_with_type("MAIN.nCounter", "INT", (-7i16).to_le_bytes());
+    server.add_symbol_with_type("MAIN.sStatus", "STRING(10)", *b"Running\0\0\0\0");
+    server.add_symbol("MAIN.stData", [1, 2, 0xab]);
+
+    assert_eq!(run(&server, &["read", "MAIN.nCounter"]).1, "-7\n");
+    assert_eq!(run(&server, &["read", "MAIN.sStatus"]).1, "'Running'\n");
+    assert_eq!(run(&server, &["read", "MAIN.stData"]).1, "0102ab\n");
+
+    // A type given on the command line is used as is
+    assert_eq!(
+        run(&server, &["read", "MAIN.stData", "--type", "UINT"]).1,
+        "513\n"
+    );
+
+    assert_eq!(
+        run(&server, &["--json", "read", "MAIN.nCounter"]).1,
+        "{\"symbol\":\"MAIN.nCounter\",\"type\":\"INT\",\"value\":-7}\n"
+    );
+}
+
+#[test]
+fn symbols_are_written() {
+    let server = MockAdsServer::start();
+    server.add_symbol_with_type("MAIN.fSpeed", "REAL", 0f32.to_le_bytes());
+    server.add_symbol_with_type("MAIN.bStop", "BOOL", [0]);
+    server.add_symbol_with_type("MAIN.tDelay", "TIME", [0; 4]);
+    server.add_symbol_with_type("MAIN.sStatus", "STRING(10)", [0; 11]);
+
+    let (success, stdout, _) = run(&server, &["write", "MAIN.fSpeed", "-1.5"]);
+    assert!(success);
+    assert_eq!(stdout, "MAIN.fSpeed = -1.5\n");
+    assert_eq!(
+        server.value("MAIN.fSpeed").unwrap(),
+        (-1.5f32).to_le_bytes()
+    );
+
+    run(&server, &["write", "MAIN.bStop", "TRUE"]);
+    assert_eq!(server.value("MAIN.bStop").unwrap(), [1]);
+
+    let (success, _, stderr) = run(&server, &["write", "MAIN.bStop", "maybe"]);
+    assert!(!success);
+    assert!(stderr.contains("Invalid BOOL literal \"maybe\": expected TRUE or FALSE"));
+
+    let (_, stdout, _) = run(&server, &["write", "MAIN.tDelay", "T#1m30s"]);
+    assert_eq!(stdout, "MAIN.tDelay = T#1m30s\n");
+    assert_eq!(
+        server.value("MAIN.tDelay").unwrap(),
+        90_000u32.to_le_bytes()
+    );
+
+    // Text is taken as is without quotes, or as a literal with them
+    run(&server, &["write", "MAIN.sStatus", "Idle"]);
+    assert_eq!(
+        server.value("MAIN.sStatus").unwrap(),
+        *b"Idle\0\0\0\0\0\0\0"
+    );
+
+    let (_, stdout, _) = run(&server, &["write", "MAIN.sStatus", "'It$'s'"]);
+    assert_eq!(stdout, "MAIN.sStatus = 'It$'s'\n");
+}
+
+#[test]
+fn symbols_are_browsed_by_prefix() {
+    let server = MockAdsServer::start();
+    server.add_symbol_with_type("MAIN.nCounter", "INT", [0, 0]);
+    server.add_symbol_with_type("MAIN.fSpeed", "REAL", [0; 4]);
+    server.add_symbol_with_type("GVL.bReady", "BOOL", [0]);
+
+    let (success, stdout, _) = run(&server, &["browse", "main."]);
+    assert!(success);
+    assert_eq!(
+        stdout,
+        "MAIN.fSpeed    REAL  4 bytes\nMAIN.nCounter  INT   2 bytes\n"
+    );
+
+    let (_, stdout, _) = run(&server, &["browse", "--json"]);
+    let symbols: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(symbols.as_array().unwrap().len(), 3);
+    assert_eq!(symbols[0]["name"], "GVL.bReady");
+    assert_eq!(symbols[0]["type"], "BOOL");
+    assert_eq!(symbols[0]["size"], 1);
+}
+
+#[test]
+fn symbols_are_watched_until_the_count() {
+    let server = MockAdsServer::start();
+    server.add_symbol_with_type("MAIN.nCounter", "INT", 7i16.to_le_bytes());
+
+    let child = cli(
+        &server,
+        &["--json", "watch", "MAIN.nCounter", "--count", "2"],
+    )
+    .spawn()
+    .unwrap();
+
+    let deadline = Instant::now() + TIMEOUT;
+
+    while server.notification_count() == 0 {
+        assert!(Instant::now() < deadline, "ads-cli did not subscribe");
+
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    server.set_value("MAIN.nCounter", 8i16.to_le_bytes());
+
+    let output = wait(child);
+    assert!(output.status.success());
+
+    let values: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    // The current value comes first, then every change
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0]["symbol"], "MAIN.nCounter");
+    assert_eq!(values[0]["value"], 7);
+    assert_eq!(values[1]["value"], 8);
+}
+
+#[test]
+fn rpc_methods_are_called_with_typed_parameters() {
+    let server = MockAdsServer::start();
+    server.add_rpc_method("MAIN.fbMath#Add", |params| {
+        let a = i16::from_le_bytes([params[0], params[1]]);
+        let b = i16::from_le_bytes([params[2], params[3]]);
+
+        (a + b).to_le_bytes().to_vec()
+    });
+
+    let (success, stdout, _) = run(
+        &server,
+        &[
+            "call",
+            "MAIN.fbMath#Add",
+            "INT#2",
+            "INT#-5",
+            "--returns",
+            "INT",
+        ],
+    );
+    assert!(success);
+    assert_eq!(stdout, "-3\n");
+
+    let (success, _, stderr) = run(&server, &["call", "MAIN.fbMath#Add", "INT#70000"]);
+    assert!(!success);
+    assert!(stderr.contains("Invalid INT literal \"INT#70000\": out of range -32768 to 32767"));
+}
+
+#[test]
+fn bindings_are_generated_for_the_symbols_under_a_prefix() {
+    let server = MockAdsServer::start();
+    server.add_data_type("ST_Axis", 8, &[("fPosition", "REAL", 0, 4)]);
+    server.add_data_type("ST_Unused", 2, &[("nValue", "INT", 0, 2)]);
+    server.add_symbol_with_type("MAIN.stAxis", "ST_Axis", [0; 8]);
+    server.add_symbol_with_type("GVL.stUnused", "ST_Unused", [0; 2]);
+
+    let (success, stdout, _) = run(&server, &["codegen", "main."]);
+    assert!(success);
+    assert!(stdout
+        .starts_with("// Generated by ads-client from the PLC at 10.0.0.1.1.1:851. Do not edit."));
+    assert!(stdout.contains("pub struct StAxis {"));
+    assert!(stdout.contains("pub const MAIN_ST_AXIS: &str = \"MAIN.stAxis\";"));
+    assert!(!stdout.contains("Unused"));
+
+    let path = std::env::temp_dir().join(format!("ads-cli-codegen-{}.rs", std::process::id()));
+
+    let (success, stdout, _) = run(
+        &server,
+        &["--json", "codegen", "--output", path.to_str().unwrap()],
+    );
+    assert!(success);
+
+    let summary: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(summary["data_types"], 2);
+    assert_eq!(summary["symbols"], 2);
+
+    let source = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert!(source.contains("pub struct StUnused {"));
+}
+
+#[test]
+fn missing_configuration_is_reported() {
+    let server = MockAdsServer::start();
+
+    let output = wait(
+        cli(&server, &["state"])
+            .env_remove("PLC_AMS_NET_ID")
+            .spawn()
+            .unwrap(),
+    );
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Environment variable PLC_AMS_NET_ID not found."));
+}
@@ -0,0 +1,171 @@
+use std::{sync::Barrier, time::Duration};
+
+use ads_client::{
+    coalescing::CoalescingStats, data_types::primitives::int::PlcInt, error::PlcError,
+    plc_connection::PlcConnection, testing::MockAdsServer,
+};
+
+const SYMBOLS: [&str; 4] = ["MAIN.nA", "MAIN.nB", "MAIN.nC", "MAIN.nD"];
+
+fn coalescing_connection(server: &MockAdsServer) -> PlcConnection {
+    for (index, symbol) in SYMBOLS.iter().enumerate() {
+        server.add_symbol(symbol, (index as i16).to_le_bytes());
+    }
+
+    let connection = server
+        .builder()
+        .with_coalescing_window(Some(Duration::from_millis(100)))
+        .build();
+    connection.run_connection_loop();
+
+    // Fetch the handles up front, so only the reads and writes themselves are batched
+    for symbol in SYMBOLS {
+        connection.read_symbol::<PlcInt>(symbol).unwrap();
+    }
+
+    connection
+}
+
+/// Runs the request for every symbol at once, each on its own thread.
+fn concurrently<R: Send>(request: impl Fn(&str) -> R + Sync) -> Vec<R> {
+    let barrier = Barrier::new(SYMBOLS.len());
+
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = SYMBOLS
+            .iter()
+            .map(|symbol| {
+                let (barrier, request) = (&barrier, &request);
+
+                scope.spawn(move || {
+                    barrier.wait();
+
+                    request(symbol)
+                })
+            })
+            .collect();
+
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect()
+    })
+}
+
+#[test]
+fn concurrent_reads_share_a_sum_command() {
+    let server = MockAdsServer::start();
+    let connection = coalescing_connection(&server);
+
+    let values = concurrently(|symbol| connection.read_symbol::<PlcInt>(symbol).unwrap());
+
+    assert_eq!(
+        values.into_iter().map(i16::from).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    assert_eq!(server.sum_command_count(), 1);
+
+    let stats = connection.coalescing_stats().unwrap();
+
+    assert_eq!(stats.coalesced_requests, 4);
+    assert_eq!(stats.largest_batch, 4);
+}
+
+#[test]
+fn concurrent_writes_share_a_sum_command() {
+    let server = MockAdsServer::start();
+    let connection = coalescing_connection(&server);
+
+    concurrently(|symbol| connection.write_symbol(symbol, PlcInt::from(42)).unwrap());
+
+    for symbol in SYMBOLS {
+        assert_eq!(server.value(symbol), Some(42i16.to_le_bytes().to_vec()));
+    }
+
+    assert_eq!(server.sum_command_count(), 1);
+}
+
+#[test]
+fn bursts_beyond_one_sum_command_are_all_sent() {
+    let server = MockAdsServer::start();
+    let connection = coalescing_connection(&server);
+
+    let count = 501;
+    let barrier = Barrier::new(count);
+
+    // NB: no further requests follow the burst to send what did not fit in the first sum command
+    let values: Vec<i16> = std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..count)
+            .map(|index| {
+                let (barrier, connection) = (&barrier, &connection);
+
+                scope.spawn(move || {
+                    barrier.wait();
+
+                    connection
+                        .read_symbol::<PlcInt>(SYMBOLS[index % SYMBOLS.len()])
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        threads
+            .into_iter()
+            .map(|thread| i16::from(thread.join().unwrap()))
+            .collect()
+    });
+
+    assert!(values
+        .iter()
+        .enumerate()
+        .all(|(index, value)| *value == (index % SYMBOLS.len()) as i16));
+
+    let stats = connection.coalescing_stats().unwrap();
+
+    assert_eq!(stats.largest_batch, 500);
+    assert_eq!(stats.requests, count as u64 + SYMBOLS.len() as u64);
+}
+
+#[test]
+fn failed_sub_commands_only_fail_their_caller() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.bFlag", [1]);
+
+    let connection = coalescing_connection(&server);
+
+    let results = std::thread::scope(|scope| {
+        let flag = scope.spawn(|| connection.read_symbol::<PlcInt>("MAIN.bFlag"));
+        let value = scope.spawn(|| connection.read_symbol::<PlcInt>("MAIN.nB"));
+
+        (flag.join().unwrap(), value.join().unwrap())
+    });
+
+    assert!(matches!(results.0, Err(PlcError::SizeMismatch { .. })));
+    assert_eq!(i16::from(results.1.unwrap()), 1);
+    assert!(connection.is_connected());
+}
+
+#[test]
+fn unsupported_sum_commands_fall_back_to_single_requests() {
+    let server = MockAdsServer::start();
+    let connection = coalescing_connection(&server);
+
+    server.set_sum_commands_supported(false);
+
+    let values = concurrently(|symbol| connection.read_symbol::<PlcInt>(symbol).unwrap());
+
+    assert_eq!(
+        values.into_iter().map(i16::from).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    assert_eq!(connection.coalescing_stats().unwrap().fallbacks, 1);
+}
+
+#[test]
+fn coalescing_is_off_by_default() {
+    let server = MockAdsServer::start();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    assert_eq!(connection.coalescing_stats(), None::<CoalescingStats>);
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "codegen")]
+
+use std::mem::{offset_of, size_of};
+
+use ads_client::{
+    codegen::TypeTable,
+    data_types::primitives::{dint::PlcDInt, int::PlcInt},
+    testing::MockAdsServer,
+};
+use zerocopy::AsBytes;
+
+#[allow(dead_code)]
+mod plc {
+    include!("codegen/plc.rs");
+}
+
+use plc::{symbols, EColor, FbConveyorRpc, StPacked, StStatus};
+
+const TMC: &str = include_str!("codegen/Plc.tmc");
+
+#[test]
+fn bindings_match_the_checked_in_source() {
+    let source = TypeTable::from_tmc(TMC).unwrap().to_rust("Plc.tmc");
+
+    // NB: set UPDATE_CODEGEN=1 to regenerate tests/codegen/plc.rs after changing the generator
+    if std::env::var_os("UPDATE_CODEGEN").is_some() {
+        std::fs::write("tests/codegen/plc.rs", &source).unwrap();
+    }
+
+    assert_eq!(source, include_str!("codegen/plc.rs"));
+}
+
+#[test]
+fn structs_have_the_plc_layout() {
+    assert_eq!(size_of::<StStatus>(), 48);
+    assert_eq!(offset_of!(StStatus, f_speed), 4);
+    assert_eq!(offset_of!(StStatus, a_values), 10);
+    assert_eq!(offset_of!(StStatus, s_name), 16);
+    assert_eq!(offset_of!(StStatus, e_color), 34);
+    assert_eq!(offset_of!(StStatus, f_total), 40);
+
+    // A REAL the pack mode does not align is kept as bytes
+    assert_eq!(size_of::<StPacked>(), 5);
+    assert_eq!(offset_of!(StPacked, f_value), 1);
+}
+
+#[test]
+fn symbols_are_read_as_generated_types() {
+    let server = MockAdsServer::start();
+
+    let mut status = StStatus::default();
+    status.n_count = PlcInt::from(7);
+    status.e_color = EColor::DARK_BLUE;
+
+    server.add_symbol(symbols::MAIN_ST_STATUS, status.as_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let status: StStatus = connection.read_symbol(symbols::MAIN_ST_STATUS).unwrap();
+    assert_eq!(i16::from(status.n_count), 7);
+    assert_eq!(status.e_color, EColor::DARK_BLUE);
+}
+
+#[test]
+fn rpc_methods_are_called_through_the_wrappers() {
+    let server = MockAdsServer::start();
+    server.add_rpc_method("MAIN.fbConveyor#Start", |params| {
+        let speed = i32::from_le_bytes(params.try_into().unwrap());
+
+        vec![u8::from(speed > 0)]
+    });
+    server.add_rpc_method("MAIN.fbConveyor#Stop", |_| Vec::new());
+    server.add_rpc_method("MAIN.fbConveyor#GetStatus", |_| {
+        let mut status = StStatus::default();
+        status.e_color = EColor::GREEN;
+
+        status.as_bytes().to_vec()
+    });
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let conveyor = FbConveyorRpc::new(&connection, symbols::MAIN_FB_CONVEYOR);
+
+    assert!(bool::from(conveyor.start(PlcDInt::from(2)).unwrap()));
+    assert!(!bool::from(conveyor.start(PlcDInt::from(0)).unwrap()));
+    conveyor.stop().unwrap();
+    assert_eq!(conveyor.get_status().unwrap().e_color, EColor::GREEN);
+}
+
+#[test]
+fn unused_types_are_dropped_with_their_symbols() {
+    let mut type_table = TypeTable::from_tmc(TMC).unwrap();
+
+    type_table.retain_symbols(|symbol| symbol.name.starts_with("MAIN.st"));
+
+    let names: Vec<_> = type_table
+        .data_types
+        .iter()
+        .map(|data_type| data_type.name.as_str())
+        .collect();
+
+    assert_eq!(names, ["E_Color", "T_Speed", "ST_Status"]);
+    assert_eq!(type_table.symbols.len(), 1);
+}
+
+#[test]
+fn bindings_are_generated_from_a_running_plc() {
+    let server = MockAdsServer::start();
+    server.add_data_type(
+        "ST_Axis",
+        16,
+        &[
+            ("bEnabled", "BOOL", 0, 1),
+            ("fPosition", "REAL", 4, 4),
+            ("aLimits", "ARRAY [0..1] OF INT", 8, 4),
+        ],
+    );
+    server.add_symbol_with_type("MAIN.stAxis", "ST_Axis", [0; 16]);
+    server.add_symbol_with_type("MAIN.nCounter", "INT", [0; 2]);
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let source = TypeTable::from_plc(&connection).unwrap().to_rust("the PLC");
+
+    assert!(source.starts_with("// Generated by ads-client from the PLC. Do not edit."));
+    assert!(source.contains(
+        "#[repr(C)]\npub struct StAxis {\n    /// `bEnabled`: BOOL\n    pub b_enabled: PlcBool,\n    _padding_1: [u8; 3],\n"
+    ));
+    assert!(source.contains("    pub a_limits: [PlcInt; 2],\n    _padding_12: [u8; 4],\n}"));
+    assert!(source.contains("    pub const MAIN_ST_AXIS: &str = \"MAIN.stAxis\";"));
+    assert!(source.contains("    pub const MAIN_N_COUNTER: &str = \"MAIN.nCounter\";"));
+}
@@ -0,0 +1,156 @@
+// Generated by ads-client from Plc.tmc. Do not edit.
+
+use ads_client::data_types::primitives::{
+    bool::PlcBool,
+    dint::PlcDInt,
+    int::PlcInt,
+    lreal::PlcLReal,
+    real::PlcReal,
+    string16::PlcString16,
+};
+
+/// `E_Color`, an enum of INT.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, zerocopy::AsBytes, zerocopy::FromBytes,
+    zerocopy::FromZeroes,
+)]
+#[repr(transparent)]
+pub struct EColor(pub i16);
+
+impl EColor {
+    pub const RED: Self = Self(0);
+    pub const GREEN: Self = Self(1);
+    pub const DARK_BLUE: Self = Self(10);
+}
+
+impl ads_client::data_types::PlcDataType for EColor {}
+
+/// `T_Speed`, a REAL.
+pub type TSpeed = PlcReal;
+
+/// `ST_Status`, 48 bytes.
+#[derive(Clone, Debug, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
+#[repr(C)]
+pub struct StStatus {
+    /// `bReady`: BOOL
+    pub b_ready: PlcBool,
+    _padding_1: [u8; 3],
+    /// `fSpeed`: T_Speed
+    pub f_speed: TSpeed,
+    /// `nCount`: INT
+    pub n_count: PlcInt,
+    /// `aValues`: ARRAY OF INT, 3 elements
+    pub a_values: [PlcInt; 3],
+    /// `sName`: STRING(16)
+    pub s_name: PlcString16,
+    _padding_33: [u8; 1],
+    /// `eColor`: E_Color
+    pub e_color: EColor,
+    _padding_36: [u8; 4],
+    /// `fTotal`: LREAL
+    pub f_total: PlcLReal,
+}
+
+impl Default for StStatus {
+    fn default() -> Self {
+        zerocopy::FromZeroes::new_zeroed()
+    }
+}
+
+impl ads_client::data_types::PlcDataType for StStatus {}
+
+/// `ST_Packed`, 5 bytes.
+#[derive(Clone, Debug, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
+#[repr(C)]
+pub struct StPacked {
+    /// `bFlag`: BOOL
+    pub b_flag: PlcBool,
+    /// `fValue`: REAL
+    pub f_value: [u8; 4],
+}
+
+impl Default for StPacked {
+    fn default() -> Self {
+        zerocopy::FromZeroes::new_zeroed()
+    }
+}
+
+impl ads_client::data_types::PlcDataType for StPacked {}
+
+/// `FB_Conveyor`, 16 bytes.
+#[derive(Clone, Debug, zerocopy::AsBytes, zerocopy::FromBytes, zerocopy::FromZeroes)]
+#[repr(C)]
+pub struct FbConveyor {
+    _padding_0: [u8; 8],
+    /// `fSpeed`: REAL
+    pub f_speed: PlcReal,
+    /// `bRunning`: BOOL
+    pub b_running: PlcBool,
+    _padding_13: [u8; 3],
+}
+
+impl Default for FbConveyor {
+    fn default() -> Self {
+        zerocopy::FromZeroes::new_zeroed()
+    }
+}
+
+impl ads_client::data_types::PlcDataType for FbConveyor {}
+
+/// The RPC methods of a `FB_Conveyor`, called on the instance a scope points at.
+#[derive(Clone)]
+pub struct FbConveyorRpc {
+    scope: ads_client::scope::Scope,
+}
+
+impl FbConveyorRpc {
+    /// The methods of the instance at the path, e.g: one of the `symbols`.
+    pub fn new(connection: &ads_client::plc_connection::PlcConnection, path: &str) -> Self {
+        Self::in_scope(connection.scope(path))
+    }
+
+    pub fn in_scope(scope: ads_client::scope::Scope) -> Self {
+        Self { scope }
+    }
+
+    /// `Start(speed: DINT): BOOL`
+    pub fn start(&self, speed: PlcDInt) -> ads_client::error::PlcResult<PlcBool> {
+        ads_client::plc_interface::__private::call_rpc_method(
+            &self.scope,
+            "#Start",
+            &[zerocopy::AsBytes::as_bytes(&speed)],
+        )
+    }
+
+    /// `Stop()`
+    pub fn stop(&self) -> ads_client::error::PlcResult<()> {
+        self.scope.invoke_rpc_method_with_params(
+            "#Stop",
+            &[],
+        )
+    }
+
+    /// `GetStatus(): ST_Status`
+    pub fn get_status(&self) -> ads_client::error::PlcResult<StStatus> {
+        ads_client::plc_interface::__private::call_rpc_method(
+            &self.scope,
+            "#GetStatus",
+            &[],
+        )
+    }
+}
+
+/// The paths of the PLC's symbols.
+pub mod symbols {
+    /// INT
+    pub const MAIN_N_COUNTER: &str = "MAIN.nCounter";
+
+    /// ST_Status
+    pub const MAIN_ST_STATUS: &str = "MAIN.stStatus";
+
+    /// FB_Conveyor
+    pub const MAIN_FB_CONVEYOR: &str = "MAIN.fbConveyor";
+
+    /// E_Color
+    pub const GVL_E_COLOR: &str = "GVL.eColor";
+}
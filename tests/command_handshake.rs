@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use ads_client::{
+    command_handshake::{CommandHandshake, CommandOutcome},
+    data_types::primitives::{bool::PlcBool, dint::PlcDInt, udint::PlcUDInt},
+    error::PlcError,
+    testing::PlcSimulator,
+};
+
+const CYCLE_TIME: Duration = Duration::from_millis(5);
+
+const FB: &str = "MAIN.fbMove";
+
+/// A PLC moving to the target on the rising edge of bExecute, failing with error 42 for negative targets.
+fn move_simulator() -> PlcSimulator {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.add_symbol("MAIN.fbMove.nTarget", PlcDInt::from(0));
+    simulator.add_symbol("MAIN.fbMove.bExecute", PlcBool::from(false));
+    simulator.add_symbol("MAIN.fbMove.bDone", PlcBool::from(false));
+    simulator.add_symbol("MAIN.fbMove.bError", PlcBool::from(false));
+    simulator.add_symbol("MAIN.fbMove.nErrorId", PlcUDInt::from(0));
+
+    simulator.on_cycle(|cycle| {
+        let execute: PlcBool = cycle.read("MAIN.fbMove.bExecute").unwrap();
+
+        if !bool::from(execute) {
+            cycle.write("MAIN.fbMove.bDone", PlcBool::from(false));
+            cycle.write("MAIN.fbMove.bError", PlcBool::from(false));
+
+            return;
+        }
+
+        let target: PlcDInt = cycle.read("MAIN.fbMove.nTarget").unwrap();
+
+        if i32::from(target) < 0 {
+            cycle.write("MAIN.fbMove.nErrorId", PlcUDInt::from(42));
+            cycle.write("MAIN.fbMove.bError", PlcBool::from(true));
+        } else {
+            cycle.write("MAIN.fbMove.bDone", PlcBool::from(true));
+        }
+    });
+
+    simulator
+}
+
+fn run_move(simulator: &PlcSimulator, target: i32) -> Result<CommandOutcome, PlcError> {
+    let connection = simulator.connection();
+    connection.run_connection_loop();
+
+    connection.run_command(&CommandHandshake::for_function_block(FB), |connection| {
+        connection.write_symbol("MAIN.fbMove.nTarget", PlcDInt::from(target))
+    })
+}
+
+fn execute_flag(simulator: &PlcSimulator) -> bool {
+    simulator
+        .value::<PlcBool>("MAIN.fbMove.bExecute")
+        .unwrap()
+        .into()
+}
+
+#[test]
+fn commands_finish_when_the_plc_is_done() {
+    let simulator = move_simulator();
+
+    assert_eq!(run_move(&simulator, 100).unwrap(), CommandOutcome::Done);
+    assert_eq!(
+        i32::from(simulator.value::<PlcDInt>("MAIN.fbMove.nTarget").unwrap()),
+        100
+    );
+    assert!(!execute_flag(&simulator));
+
+    // The flags of the finished command clear before the next one starts
+    assert_eq!(run_move(&simulator, 200).unwrap(), CommandOutcome::Done);
+}
+
+#[test]
+fn command_errors_carry_the_error_id() {
+    let simulator = move_simulator();
+
+    assert_eq!(
+        run_move(&simulator, -1).unwrap(),
+        CommandOutcome::Error { error_id: 42 }
+    );
+    assert!(!execute_flag(&simulator));
+}
+
+#[test]
+fn commands_time_out_and_still_reset_the_execute_flag() {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.add_symbol("MAIN.fbMove.bExecute", PlcBool::from(false));
+    simulator.add_symbol("MAIN.fbMove.bDone", PlcBool::from(false));
+    simulator.add_symbol("MAIN.fbMove.bError", PlcBool::from(false));
+    simulator.add_symbol("MAIN.fbMove.nErrorId", PlcUDInt::from(0));
+
+    let connection = simulator.connection();
+    connection.run_connection_loop();
+
+    let handshake =
+        CommandHandshake::for_function_block(FB).with_timeout(Duration::from_millis(100));
+
+    let result = connection.run_command(&handshake, |_| Ok(()));
+
+    assert!(matches!(result, Err(PlcError::Timeout)));
+    assert!(!execute_flag(&simulator));
+}
+
+#[test]
+fn flags_are_polled_when_subscribing_fails() {
+    let simulator = move_simulator();
+    simulator.server().set_notifications_supported(false);
+
+    assert_eq!(run_move(&simulator, 100).unwrap(), CommandOutcome::Done);
+    assert_eq!(
+        run_move(&simulator, -1).unwrap(),
+        CommandOutcome::Error { error_id: 42 }
+    );
+    assert_eq!(simulator.server().notification_count(), 0);
+    assert!(!execute_flag(&simulator));
+}
@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use ads_client::{
+    data_types::primitives::int::PlcInt,
+    error::PlcError,
+    plc_connection::PlcConnection,
+    return_code::{ReturnCode, ReturnCodeClass},
+    testing::{FaultProxy, MockAdsServer, PlcSimulator},
+};
+
+fn connected_through_proxy() -> (MockAdsServer, FaultProxy, PlcConnection) {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let proxy = FaultProxy::start(server.address());
+
+    let connection = proxy.connection();
+    connection.run_connection_loop();
+
+    assert_eq!(read_counter(&connection).unwrap(), 7);
+
+    (server, proxy, connection)
+}
+
+fn read_counter(connection: &PlcConnection) -> Result<i16, PlcError> {
+    connection
+        .read_symbol::<PlcInt>("MAIN.nCounter")
+        .map(i16::from)
+}
+
+/// Checks a fault lost the connection, and that it recovers once the fault is gone.
+fn assert_reconnects(proxy: &FaultProxy, connection: &PlcConnection) {
+    assert!(!connection.is_connected());
+
+    proxy.heal();
+    connection.run_connection_loop();
+
+    assert_eq!(read_counter(connection).unwrap(), 7);
+}
+
+#[test]
+fn dropped_connections_lose_the_transport() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    proxy.drop_connections();
+
+    let result = read_counter(&connection);
+
+    assert!(matches!(result, Err(PlcError::TransportLost { .. })));
+    assert_reconnects(&proxy, &connection);
+}
+
+#[test]
+fn half_open_connections_time_out() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    proxy.set_half_open(true);
+
+    let result = read_counter(&connection);
+
+    assert!(matches!(result, Err(PlcError::TransportLost { .. })));
+    assert_reconnects(&proxy, &connection);
+}
+
+#[test]
+fn corrupted_headers_lose_the_transport() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    proxy.corrupt_next_reply();
+
+    let result = read_counter(&connection);
+
+    assert!(matches!(result, Err(PlcError::TransportLost { .. })));
+    assert_reconnects(&proxy, &connection);
+}
+
+#[test]
+fn mismatched_invoke_ids_lose_the_transport() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    proxy.mismatch_next_invoke_id();
+
+    let result = read_counter(&connection);
+
+    assert!(matches!(result, Err(PlcError::TransportLost { .. })));
+    assert_reconnects(&proxy, &connection);
+}
+
+#[test]
+fn replies_within_the_timeout_keep_the_connection() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    proxy.set_latency(Duration::from_millis(200));
+
+    assert_eq!(read_counter(&connection).unwrap(), 7);
+    assert!(connection.is_connected());
+}
+
+#[test]
+fn replies_after_the_timeout_lose_the_transport() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    proxy.set_latency(Duration::from_millis(2500));
+
+    let result = read_counter(&connection);
+
+    assert!(matches!(result, Err(PlcError::TransportLost { .. })));
+    assert_reconnects(&proxy, &connection);
+}
+
+#[test]
+fn disconnect_codes_drop_the_connection() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    for code in [0x006, 0x012, 0x01a, 0x711] {
+        assert_eq!(ReturnCode::classify(code), ReturnCodeClass::Disconnect);
+
+        proxy.fail_next_reply(code);
+
+        let result = read_counter(&connection);
+
+        assert!(
+            matches!(result, Err(PlcError::AdsDeviceError { code: error_code, .. }) if error_code == code),
+            "{code:#x}: {result:?}"
+        );
+        assert_reconnects(&proxy, &connection);
+    }
+}
+
+#[test]
+fn other_codes_keep_the_connection() {
+    let (_server, proxy, connection) = connected_through_proxy();
+
+    for (code, class) in [
+        (0x708, ReturnCodeClass::Transient),
+        (0x700, ReturnCodeClass::Fatal),
+        (0x00f, ReturnCodeClass::Configuration),
+    ] {
+        assert_eq!(ReturnCode::classify(code), class);
+
+        proxy.fail_next_reply(code);
+
+        let result = read_counter(&connection);
+
+        assert!(
+            matches!(result, Err(PlcError::AdsDeviceError { code: error_code, .. }) if error_code == code),
+            "{code:#x}: {result:?}"
+        );
+        assert!(connection.is_connected());
+        assert_eq!(read_counter(&connection).unwrap(), 7);
+    }
+}
+
+#[test]
+fn simulated_plcs_reconnect_through_the_proxy() {
+    let simulator = PlcSimulator::start(Duration::from_millis(5));
+    simulator.add_symbol("MAIN.nCounter", PlcInt::from(7));
+
+    let proxy = FaultProxy::start(simulator.server().address());
+
+    let connection = proxy.connection();
+    connection.run_connection_loop();
+
+    simulator.config();
+
+    let result = read_counter(&connection);
+
+    assert!(matches!(
+        result,
+        Err(PlcError::AdsDeviceError { code: 0x006, .. })
+    ));
+
+    simulator.run();
+
+    assert_reconnects(&proxy, &connection);
+}
@@ -0,0 +1,246 @@
+use std::{sync::Barrier, time::Duration};
+
+use ads::AdsState;
+use ads_client::{
+    data_types::primitives::{bool::PlcBool, dint::PlcDInt, int::PlcInt, real::PlcReal},
+    error::{PlcError, PlcResult},
+    plc_backend::PlcBackend,
+    subscription::{DeliveryPolicy, SubscriptionOptions},
+    testing::{Call, FakePlc, MockAdsServer},
+};
+
+/// Business logic under test, written against any backend.
+fn stop_if_too_fast(plc: &impl PlcBackend) -> PlcResult<bool> {
+    let speed: PlcReal = plc.read_symbol("MAIN.fSpeed")?;
+
+    if f32::from(speed) <= 2.0 {
+        return Ok(false);
+    }
+
+    plc.write_symbol("MAIN.bStop", PlcBool::from(true))?;
+    plc.invoke_rpc_method("MAIN.fbAxis#Halt")?;
+
+    Ok(true)
+}
+
+#[test]
+fn connections_are_backends() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.fSpeed", 2.5f32.to_le_bytes());
+    server.add_symbol("MAIN.bStop", [0]);
+    server.add_rpc_method("MAIN.fbAxis#Halt", |_| Vec::new());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    assert_eq!(PlcBackend::ads_state(&connection).unwrap(), AdsState::Run);
+    assert!(stop_if_too_fast(&connection).unwrap());
+    assert_eq!(server.value("MAIN.bStop").unwrap(), [1]);
+}
+
+#[test]
+fn fakes_record_calls() {
+    let plc = FakePlc::new();
+    plc.add_symbol("MAIN.fSpeed", PlcReal::from(2.5));
+    plc.add_symbol("MAIN.bStop", PlcBool::from(false));
+    plc.expect_rpc("MAIN.fbAxis#Halt").times(1);
+
+    assert!(stop_if_too_fast(&plc).unwrap());
+
+    assert_eq!(
+        plc.calls(),
+        [
+            Call::Read {
+                name: "MAIN.fSpeed".to_string()
+            },
+            Call::Write {
+                name: "MAIN.bStop".to_string(),
+                data: vec![1]
+            },
+            Call::Rpc {
+                name: "MAIN.fbAxis#Halt".to_string(),
+                params: Vec::new()
+            },
+        ]
+    );
+    assert!(bool::from(plc.value::<PlcBool>("MAIN.bStop").unwrap()));
+}
+
+#[test]
+fn fakes_script_replies() {
+    let plc = FakePlc::new();
+    plc.expect_read("MAIN.fSpeed")
+        .returning(PlcReal::from(1.0))
+        .times(1);
+    plc.expect_rpc("MAIN.fbAxis#Double")
+        .with(PlcDInt::from(21))
+        .returning(PlcDInt::from(42));
+
+    assert!(!stop_if_too_fast(&plc).unwrap());
+
+    let doubled: PlcDInt = plc
+        .call_rpc_method("MAIN.fbAxis#Double", &[&21i32.to_le_bytes()])
+        .unwrap();
+    assert_eq!(i32::from(doubled), 42);
+
+    assert!(plc.writes::<PlcBool>("MAIN.bStop").is_empty());
+}
+
+#[test]
+#[should_panic(expected = "write to MAIN.bStop")]
+fn unmet_expectations_panic() {
+    let plc = FakePlc::new();
+    plc.add_symbol("MAIN.fSpeed", PlcReal::from(1.0));
+    plc.expect_write("MAIN.bStop").with(PlcBool::from(true));
+
+    stop_if_too_fast(&plc).unwrap();
+}
+
+#[test]
+fn expectations_count_calls() {
+    let plc = FakePlc::new();
+    plc.add_symbol("MAIN.nCounter", PlcInt::from(0));
+    plc.expect_write("MAIN.nCounter").times(2);
+    plc.expect_write("MAIN.nCounter")
+        .with(PlcInt::from(-1))
+        .never();
+
+    plc.write_symbol("MAIN.nCounter", PlcInt::from(1)).unwrap();
+    plc.write_symbol("MAIN.nCounter", PlcInt::from(2)).unwrap();
+
+    plc.checkpoint();
+
+    assert_eq!(
+        plc.writes::<PlcInt>("MAIN.nCounter")
+            .into_iter()
+            .map(i16::from)
+            .collect::<Vec<_>>(),
+        [1, 2]
+    );
+}
+
+#[test]
+fn fakes_check_symbols_like_a_plc() {
+    let plc = FakePlc::new();
+    plc.add_symbol("MAIN.nCounter", PlcInt::from(0));
+
+    assert!(matches!(
+        plc.read_symbol::<PlcInt>("MAIN.nMissing"),
+        Err(PlcError::SymbolNotFound { .. })
+    ));
+    assert!(matches!(
+        plc.read_symbol::<PlcDInt>("MAIN.nCounter"),
+        Err(PlcError::SizeMismatch { expected: 4, .. })
+    ));
+    assert!(matches!(
+        plc.write_symbol("MAIN.nCounter", PlcDInt::from(1)),
+        Err(PlcError::SizeMismatch { expected: 4, .. })
+    ));
+    assert!(matches!(
+        plc.invoke_rpc_method("MAIN.fbAxis#Halt"),
+        Err(PlcError::SymbolNotFound { .. })
+    ));
+}
+
+#[test]
+fn disconnect_errors_disconnect_fakes() {
+    let plc = FakePlc::new();
+    plc.add_symbol("MAIN.nCounter", PlcInt::from(7));
+    plc.expect_read("MAIN.nCounter")
+        .failing_with(0x708)
+        .times(1);
+    plc.expect_write("MAIN.nCounter")
+        .failing_with(0x711)
+        .times(1);
+
+    let subscription = plc.subscribe_with_options::<PlcInt>("MAIN.nCounter", Default::default());
+
+    assert!(matches!(
+        plc.read_symbol::<PlcInt>("MAIN.nCounter"),
+        Err(PlcError::AdsDeviceError { code: 0x708, .. })
+    ));
+    assert!(plc.is_connected());
+
+    assert!(matches!(
+        plc.write_symbol("MAIN.nCounter", PlcInt::from(1)),
+        Err(PlcError::AdsDeviceError { code: 0x711, .. })
+    ));
+    assert!(!plc.is_connected());
+    assert!(matches!(plc.ads_state(), Err(PlcError::NotConnected)));
+
+    let subscription = subscription.unwrap();
+    assert_eq!(i16::from(subscription.recv().unwrap()), 7);
+    assert!(subscription.recv().is_err());
+
+    plc.checkpoint();
+    plc.set_connected(true);
+    assert_eq!(
+        i16::from(plc.read_symbol::<PlcInt>("MAIN.nCounter").unwrap()),
+        7
+    );
+}
+
+#[test]
+fn fake_subscriptions_get_changes() {
+    let plc = FakePlc::new();
+    plc.add_symbol("MAIN.nCounter", PlcInt::from(0));
+
+    let subscription = plc
+        .subscribe_with_options::<PlcInt>("MAIN.nCounter", Default::default())
+        .unwrap();
+
+    plc.set_value("MAIN.nCounter", PlcInt::from(1));
+    plc.set_value("MAIN.nCounter", PlcInt::from(1));
+    plc.write_symbol("MAIN.nCounter", PlcInt::from(2)).unwrap();
+
+    let values: Vec<i16> = (0..3)
+        .map(|_| i16::from(subscription.recv_timeout(Duration::from_secs(1)).unwrap()))
+        .collect();
+
+    assert_eq!(values, [0, 1, 2]);
+    assert!(subscription.try_recv().is_err());
+}
+
+#[test]
+fn fake_subscribers_end_on_the_stored_value() {
+    let plc = FakePlc::new();
+    plc.add_symbol("MAIN.nCounter", PlcInt::from(0));
+
+    let subscription = plc
+        .subscribe_with_options::<PlcInt>(
+            "MAIN.nCounter",
+            SubscriptionOptions::new().with_delivery_policy(DeliveryPolicy::CoalesceLatest),
+        )
+        .unwrap();
+
+    let barrier = Barrier::new(3);
+    let mut mismatches = 0;
+
+    // Two writers race each round, so which of their values is stored last differs from round to round
+    std::thread::scope(|scope| {
+        for writer in [1, -1] {
+            let (plc, barrier) = (&plc, &barrier);
+
+            scope.spawn(move || {
+                for round in 1..=5000 {
+                    barrier.wait();
+                    plc.set_value("MAIN.nCounter", PlcInt::from(writer * round));
+                    barrier.wait();
+                }
+            });
+        }
+
+        for _ in 1..=5000 {
+            barrier.wait();
+            barrier.wait();
+
+            let last = subscription.try_recv().ok().map(i16::from);
+            let stored = plc.value::<PlcInt>("MAIN.nCounter").map(i16::from);
+
+            // NB: counted rather than asserted, as a panic here would leave the writers waiting at the barrier
+            mismatches += usize::from(last != stored);
+        }
+    });
+
+    assert_eq!(mismatches, 0);
+}
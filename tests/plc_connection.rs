@@ -0,0 +1,545 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use ads::AdsState;
+
+use ads_client::{
+    command_handshake::CommandHandshake,
+    data_types::primitives::{int::PlcInt, udint::PlcUDInt},
+    error::PlcError,
+    plc_client::PlcClient,
+    plc_connection::PlcConnection,
+    subscription::{DeliveryPolicy, Subscription},
+    symbol::Symbol,
+    symbol_mirror::{Quality, SymbolMirror, SymbolMirrorBuilder},
+    testing::MockAdsServer,
+};
+
+#[test]
+fn reads_and_writes_symbols() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+    assert_eq!(i16::from(value), 7);
+
+    connection
+        .write_symbol("MAIN.nCounter", PlcInt::from(42))
+        .unwrap();
+    assert_eq!(
+        server.value("MAIN.nCounter"),
+        Some(42i16.to_le_bytes().to_vec())
+    );
+}
+
+#[test]
+fn unknown_symbols_are_not_found() {
+    let server = MockAdsServer::start();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nMissing");
+
+    assert!(matches!(result, Err(PlcError::SymbolNotFound { .. })));
+    assert!(connection.is_connected());
+}
+
+#[test]
+fn requests_fail_when_not_connected() {
+    let server = MockAdsServer::start();
+
+    let connection = server.connection();
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nCounter");
+
+    assert!(matches!(result, Err(PlcError::NotConnected)));
+}
+
+#[test]
+fn rpc_methods_receive_parameters() {
+    let server = MockAdsServer::start();
+    server.add_rpc_method("MAIN.fbAxis#Double", |param| {
+        let value = u32::from_le_bytes(param.try_into().unwrap());
+
+        (value * 2).to_le_bytes().to_vec()
+    });
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    connection
+        .invoke_rpc_method_with_param("MAIN.fbAxis#Double", PlcUDInt::from(21))
+        .unwrap();
+}
+
+#[test]
+fn subscriptions_receive_changes() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 1i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let subscription = connection
+        .subscribe_with_policy::<PlcInt>("MAIN.nCounter", DeliveryPolicy::Unbounded)
+        .unwrap();
+
+    let initial = subscription.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(i16::from(initial), 1);
+
+    server.set_value("MAIN.nCounter", 2i16.to_le_bytes());
+
+    let changed = subscription.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(i16::from(changed), 2);
+
+    drop(subscription);
+
+    assert_eq!(server.notification_count(), 0);
+}
+
+#[test]
+fn handles_are_released_on_disconnect() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+    assert_eq!(server.handle_count(), 1);
+
+    connection.disconnect();
+    assert_eq!(server.handle_count(), 0);
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn connection_types_are_thread_safe() {
+    assert_send_sync::<PlcClient>();
+    assert_send_sync::<PlcConnection>();
+    assert_send_sync::<Subscription<PlcInt>>();
+    assert_send_sync::<Symbol<PlcInt>>();
+    assert_send_sync::<SymbolMirror>();
+    assert_send_sync::<CommandHandshake>();
+}
+
+#[test]
+fn concurrent_requests_are_in_flight_together() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    // Fetch the handle up front, so only the reads themselves are timed
+    connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+
+    server.set_reply_delay(Duration::from_millis(200));
+
+    let started = Instant::now();
+
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap());
+        }
+    });
+
+    // One after the other, the reads would take 1.6 seconds
+    assert!(started.elapsed() < Duration::from_millis(800));
+}
+
+#[test]
+fn concurrent_reads_share_one_symbol_handle() {
+    let server = MockAdsServer::start();
+
+    for index in 0..10 {
+        server.add_symbol(&format!("MAIN.aCounters[{index}]"), 7i16.to_le_bytes());
+    }
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    server.set_reply_delay(Duration::from_millis(20));
+
+    // Every thread fetches the handle at once, and reads with whichever handle is cached
+    for index in 0..10 {
+        let name = format!("MAIN.aCounters[{index}]");
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let value: PlcInt = connection.read_symbol(&name).unwrap();
+                    assert_eq!(i16::from(value), 7);
+                });
+            }
+        });
+    }
+
+    assert_eq!(server.handle_count(), 10);
+}
+
+#[test]
+fn dropped_connections_are_noticed() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    server.drop_connections();
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nCounter");
+
+    assert!(matches!(result, Err(PlcError::TransportLost { .. })));
+    assert!(!connection.is_connected());
+
+    connection.run_connection_loop();
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+    assert_eq!(i16::from(value), 7);
+}
+
+#[test]
+fn stopped_plcs_are_not_connected() {
+    let server = MockAdsServer::start();
+    server.set_ads_state(AdsState::Stop);
+
+    let connection = server.connection();
+
+    assert!(matches!(
+        connection.try_connect(),
+        Err(PlcError::NotInRunMode)
+    ));
+    assert!(!connection.is_connected());
+}
+
+#[test]
+fn stopped_plcs_can_be_set_to_run_mode() {
+    let server = MockAdsServer::start();
+    server.set_ads_state(AdsState::Stop);
+
+    let connection = server.builder().set_to_run_mode(true).build();
+    connection.try_connect().unwrap();
+
+    assert_eq!(server.ads_state(), AdsState::Run);
+}
+
+#[test]
+fn removed_symbols_are_not_found() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+
+    server.remove_symbol("MAIN.nCounter");
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nCounter");
+
+    assert!(matches!(result, Err(PlcError::SymbolNotFound { .. })));
+}
+
+#[test]
+fn stopped_servers_drop_their_connections() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    drop(server);
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nCounter");
+
+    assert!(matches!(result, Err(PlcError::TransportLost { .. })));
+    assert!(connection.try_connect().is_err());
+}
+
+#[test]
+fn symbols_are_looked_up_and_accessed_by_their_bytes() {
+    let server = MockAdsServer::start();
+    server.add_symbol_with_type("MAIN.nCounter", "INT", 7i16.to_le_bytes());
+    server.add_symbol("MAIN.stData", [1, 2, 3]);
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let info = connection.device_info().unwrap();
+    assert_eq!(info.name, "Mock PLC");
+    assert_eq!((info.major, info.minor, info.version), (3, 1, 4024));
+
+    let symbol = connection.symbol_info("MAIN.nCounter").unwrap();
+    assert_eq!(symbol.name, "MAIN.nCounter");
+    assert_eq!(symbol.typ, "INT");
+    assert_eq!(symbol.size, 2);
+
+    let (symbols, _) = connection.upload_symbol_info().unwrap();
+    let names: Vec<_> = symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+    assert_eq!(names, ["MAIN.nCounter", "MAIN.stData"]);
+    assert_eq!(symbols[1].size, 3);
+
+    connection
+        .write_symbol_raw("MAIN.stData", &[4, 5, 6])
+        .unwrap();
+    assert_eq!(
+        connection.read_symbol_raw("MAIN.stData", 3).unwrap(),
+        [4, 5, 6]
+    );
+
+    assert!(matches!(
+        connection.symbol_info("MAIN.nMissing"),
+        Err(PlcError::SymbolNotFound { .. })
+    ));
+}
+
+#[test]
+fn symbols_beyond_one_sum_command_are_read_in_several() {
+    let server = MockAdsServer::start();
+
+    let names: Vec<_> = (0..600)
+        .map(|index| format!("MAIN.aValues[{index}]"))
+        .collect();
+
+    for (index, name) in names.iter().enumerate() {
+        server.add_symbol(name, (index as u16).to_le_bytes());
+    }
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let symbols: Vec<_> = names.iter().map(|name| (name.as_str(), 2)).collect();
+
+    let results = connection.read_symbols_raw(&symbols).unwrap();
+
+    assert_eq!(results.len(), 600);
+    assert_eq!(results[599].as_ref().unwrap(), &599u16.to_le_bytes());
+    assert_eq!(server.sum_command_count(), 2);
+}
+
+#[test]
+fn waiting_polls_when_subscribing_fails() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 0i16.to_le_bytes());
+    server.set_notifications_supported(false);
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let value = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            std::thread::sleep(Duration::from_millis(50));
+
+            server.set_value("MAIN.nCounter", 3i16.to_le_bytes());
+        });
+
+        connection
+            .wait_until::<PlcInt>(
+                "MAIN.nCounter",
+                |value| i16::from(value.clone()) == 3,
+                Duration::from_secs(2),
+            )
+            .unwrap()
+    });
+
+    assert_eq!(i16::from(value), 3);
+    assert_eq!(server.notification_count(), 0);
+}
+
+#[test]
+fn waiting_times_out() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 0i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let never = |value: &PlcInt| i16::from(value.clone()) == 1;
+
+    let started = Instant::now();
+
+    let result = connection.wait_until("MAIN.nCounter", never, Duration::from_millis(100));
+
+    assert!(matches!(result, Err(PlcError::Timeout)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    server.set_notifications_supported(false);
+
+    let result = connection.wait_until("MAIN.nCounter", never, Duration::from_millis(100));
+
+    assert!(matches!(result, Err(PlcError::Timeout)));
+}
+
+#[test]
+fn waiting_fails_distinctly_when_the_connection_drops() {
+    for notifications_supported in [true, false] {
+        let server = MockAdsServer::start();
+        server.add_symbol("MAIN.nCounter", 0i16.to_le_bytes());
+        server.set_notifications_supported(notifications_supported);
+
+        let connection = server.connection();
+        connection.run_connection_loop();
+
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+
+                server.drop_connections();
+            });
+
+            connection.wait_until::<PlcInt>(
+                "MAIN.nCounter",
+                |value| i16::from(value.clone()) == 1,
+                Duration::from_secs(2),
+            )
+        });
+
+        assert!(
+            matches!(result, Err(PlcError::NotConnected)),
+            "notifications supported: {notifications_supported}, {result:?}"
+        );
+    }
+}
+
+#[test]
+fn malformed_frames_do_not_stop_the_mock_server() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    // A frame too short for an AMS header closes its connection
+    let mut stream = TcpStream::connect(server.address()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    stream.write_all(&[0, 0, 4, 0, 0, 0, 1, 2, 3, 4]).unwrap();
+
+    assert_eq!(stream.read(&mut [0; 64]).unwrap(), 0);
+
+    let mut stream = TcpStream::connect(server.address()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+
+    // A read without its index group, offset and length
+    assert_eq!(raw_request(&mut stream, 2, &[0; 4]), 0x705);
+
+    // A sum read of more sub-commands than its data holds
+    let mut sum_read = Vec::new();
+    sum_read.extend_from_slice(&ads::index::SUMUP_READ.to_le_bytes());
+    sum_read.extend_from_slice(&10u32.to_le_bytes());
+    sum_read.extend_from_slice(&0u32.to_le_bytes());
+    sum_read.extend_from_slice(&12u32.to_le_bytes());
+    sum_read.extend_from_slice(&[0; 12]);
+    assert_eq!(raw_request(&mut stream, 9, &sum_read), 0x705);
+
+    // A handle release without a handle
+    let mut release = Vec::new();
+    release.extend_from_slice(&ads::index::RELEASE_SYMHANDLE.to_le_bytes());
+    release.extend_from_slice(&0u32.to_le_bytes());
+    release.extend_from_slice(&2u32.to_le_bytes());
+    release.extend_from_slice(&[0; 2]);
+    assert_eq!(raw_request(&mut stream, 3, &release), 0x705);
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+    assert_eq!(i16::from(value), 7);
+}
+
+/// Sends a request straight over AMS/TCP, returning the result code of its reply.
+fn raw_request(stream: &mut TcpStream, command: u16, data: &[u8]) -> u32 {
+    let mut request = vec![0, 0];
+    request.extend_from_slice(&(32 + data.len() as u32).to_le_bytes());
+    request.extend_from_slice(&[10, 0, 0, 1, 1, 1, 0x53, 3]);
+    request.extend_from_slice(&[10, 0, 0, 2, 1, 1, 0x30, 0x75]);
+    request.extend_from_slice(&command.to_le_bytes());
+    request.extend_from_slice(&4u16.to_le_bytes());
+    request.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    request.extend_from_slice(&0u32.to_le_bytes());
+    request.extend_from_slice(&1u32.to_le_bytes());
+    request.extend_from_slice(data);
+
+    stream.write_all(&request).unwrap();
+
+    let mut reply = [0; 42];
+    stream.read_exact(&mut reply).unwrap();
+
+    u32::from_le_bytes(reply[38..42].try_into().unwrap())
+}
+
+#[test]
+fn mirrored_values_follow_the_connection() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+    server.add_symbol("MAIN.nPolled", 1i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let mirror = SymbolMirrorBuilder::new(connection.clone())
+        .with_poll_interval(Duration::from_millis(20))
+        .with_stale_after(Duration::from_millis(200))
+        .build();
+
+    let mirrored = |name: &str| {
+        mirror
+            .read::<PlcInt>(name)
+            .map(|mirrored| (i16::from(mirrored.value), mirrored.quality))
+    };
+
+    mirror.register::<PlcInt>("MAIN.nCounter");
+
+    eventually(|| mirrored("MAIN.nCounter") == Some((7, Quality::Good)));
+
+    server.set_value("MAIN.nCounter", 8i16.to_le_bytes());
+
+    eventually(|| mirrored("MAIN.nCounter") == Some((8, Quality::Good)));
+    assert_eq!(server.notification_count(), 1);
+
+    // Symbols that cannot be subscribed to are polled, and go stale once polling fails
+    server.set_notifications_supported(false);
+    mirror.register::<PlcInt>("MAIN.nPolled");
+
+    eventually(|| mirrored("MAIN.nPolled") == Some((1, Quality::Good)));
+
+    server.remove_symbol("MAIN.nPolled");
+
+    eventually(|| mirrored("MAIN.nPolled") == Some((1, Quality::Stale)));
+
+    // The drop is noticed without any polled symbol's read failing
+    mirror.unregister("MAIN.nPolled");
+
+    server.set_notifications_supported(true);
+    server.drop_connections();
+
+    eventually(|| mirrored("MAIN.nCounter") == Some((8, Quality::Disconnected)));
+
+    connection.run_connection_loop();
+
+    eventually(|| server.notification_count() == 2);
+
+    server.set_value("MAIN.nCounter", 9i16.to_le_bytes());
+
+    eventually(|| mirrored("MAIN.nCounter") == Some((9, Quality::Good)));
+}
+
+/// Waits for a condition the mirror reaches in the background.
+fn eventually(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while !condition() {
+        assert!(Instant::now() < deadline, "condition not reached in time");
+
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
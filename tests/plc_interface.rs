@@ -0,0 +1,106 @@
+use ads_client::{
+    data_types::primitives::{bool::PlcBool, dint::PlcDInt, real::PlcReal},
+    error::PlcError,
+    plc_interface,
+    testing::MockAdsServer,
+};
+
+plc_interface! {
+    /// The conveyor belt feeding the packing station.
+    pub struct Conveyor {
+        prefix = "MAIN.fbConveyor";
+        values = ConveyorValues;
+
+        /// Belt speed in m/s.
+        var speed: PlcReal = "fSpeed";
+        var running: PlcBool = "bRunning";
+
+        rpc start(speed: PlcDInt) -> PlcBool = "Start";
+        rpc stop() = "Stop";
+    }
+}
+
+fn conveyor_server() -> MockAdsServer {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.fbConveyor.fSpeed", 1.5f32.to_le_bytes());
+    server.add_symbol("MAIN.fbConveyor.bRunning", [1]);
+    server.add_rpc_method("MAIN.fbConveyor#Start", |param| {
+        let speed = i32::from_le_bytes(param.try_into().unwrap());
+
+        vec![u8::from(speed > 0)]
+    });
+    server.add_rpc_method("MAIN.fbConveyor#Stop", |_| Vec::new());
+
+    server
+}
+
+#[test]
+fn variables_are_found_under_the_prefix() {
+    let server = conveyor_server();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let conveyor = Conveyor::new(connection);
+
+    assert_eq!(Conveyor::PREFIX, "MAIN.fbConveyor");
+    assert_eq!(conveyor.speed().name(), "MAIN.fbConveyor.fSpeed");
+    assert_eq!(f32::from(conveyor.speed().read().unwrap()), 1.5);
+
+    conveyor.running().write(PlcBool::from(false)).unwrap();
+    assert_eq!(server.value("MAIN.fbConveyor.bRunning"), Some(vec![0]));
+}
+
+#[test]
+fn rpc_methods_take_typed_parameters() {
+    let server = conveyor_server();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let conveyor = Conveyor::new(connection);
+
+    assert!(bool::from(conveyor.start(PlcDInt::from(2)).unwrap()));
+    assert!(!bool::from(conveyor.start(PlcDInt::from(0)).unwrap()));
+
+    conveyor.stop().unwrap();
+}
+
+#[test]
+fn all_variables_are_read_in_one_sum_command() {
+    let server = conveyor_server();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let conveyor = Conveyor::new(connection);
+
+    let values = conveyor.read_all().unwrap();
+
+    assert_eq!(f32::from(values.speed), 1.5);
+    assert!(bool::from(values.running));
+    assert_eq!(server.sum_command_count(), 1);
+}
+
+#[test]
+fn validation_checks_every_member() {
+    let server = conveyor_server();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let conveyor = Conveyor::new(connection);
+
+    conveyor.validate().unwrap();
+
+    server.add_symbol("MAIN.fbConveyor.fSpeed", 1.5f64.to_le_bytes());
+
+    // A new connection, as if new code was downloaded with the speed as an LREAL
+    conveyor.connection().disconnect();
+    conveyor.connection().run_connection_loop();
+
+    assert!(matches!(
+        conveyor.validate(),
+        Err(PlcError::SizeMismatch { .. })
+    ));
+}
@@ -0,0 +1,191 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use ads_client::{
+    data_types::primitives::{dint::PlcDInt, int::PlcInt},
+    error::PlcError,
+    plc_connection::PlcConnection,
+    recording::{Recording, SessionRecorder},
+    subscription::SubscriptionOptions,
+    testing::{MockAdsServer, ReplayServer},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+fn recording_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ads-client-{}-{name}.txt", std::process::id()))
+}
+
+/// Records a session against a mock server, returning the recording.
+fn record(name: &str, session: impl FnOnce(&MockAdsServer, &PlcConnection)) -> Recording {
+    let path = recording_path(name);
+
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+    server.add_rpc_method("MAIN.fbAxis#Double", |params| {
+        (i32::from_le_bytes(params.try_into().unwrap()) * 2)
+            .to_le_bytes()
+            .to_vec()
+    });
+
+    let connection = server
+        .builder()
+        .with_recorder(Some(SessionRecorder::create(&path).unwrap()))
+        .build();
+    connection.run_connection_loop();
+
+    session(&server, &connection);
+
+    connection.disconnect();
+    drop(connection);
+
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    recording
+}
+
+fn wait_until_complete(replay: &ReplayServer) {
+    let deadline = Instant::now() + Duration::from_secs(2);
+
+    while !replay.is_complete() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    assert!(replay.is_complete());
+}
+
+#[test]
+fn sessions_replay_deterministically() {
+    let recording = record("deterministic", |server, connection| {
+        let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+        assert_eq!(i16::from(value), 7);
+
+        connection
+            .write_symbol("MAIN.nCounter", PlcInt::from(8))
+            .unwrap();
+
+        let doubled: PlcDInt = connection
+            .call_rpc_method("MAIN.fbAxis#Double", &[&21i32.to_le_bytes()])
+            .unwrap();
+        assert_eq!(i32::from(doubled), 42);
+
+        let subscription = connection
+            .subscribe_with_options::<PlcInt>("MAIN.nCounter", SubscriptionOptions::new())
+            .unwrap();
+        assert_eq!(i16::from(subscription.recv_timeout(TIMEOUT).unwrap()), 8);
+
+        server.set_value("MAIN.nCounter", 9i16.to_le_bytes());
+        assert_eq!(i16::from(subscription.recv_timeout(TIMEOUT).unwrap()), 9);
+    });
+
+    assert_eq!(recording.connections.len(), 1);
+
+    // Replayed twice, to show the replay does not depend on anything but the recording
+    for _ in 0..2 {
+        let replay = ReplayServer::start(recording.clone());
+
+        let connection = replay.connection();
+        connection.run_connection_loop();
+
+        let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+        assert_eq!(i16::from(value), 7);
+
+        connection
+            .write_symbol("MAIN.nCounter", PlcInt::from(8))
+            .unwrap();
+
+        let doubled: PlcDInt = connection
+            .call_rpc_method("MAIN.fbAxis#Double", &[&21i32.to_le_bytes()])
+            .unwrap();
+        assert_eq!(i32::from(doubled), 42);
+
+        let subscription = connection
+            .subscribe_with_options::<PlcInt>("MAIN.nCounter", SubscriptionOptions::new())
+            .unwrap();
+        assert_eq!(i16::from(subscription.recv_timeout(TIMEOUT).unwrap()), 8);
+        assert_eq!(i16::from(subscription.recv_timeout(TIMEOUT).unwrap()), 9);
+
+        drop(subscription);
+        connection.disconnect();
+
+        wait_until_complete(&replay);
+        assert!(replay.unmatched_requests().is_empty());
+    }
+}
+
+#[test]
+fn reconnects_replay_as_new_connections() {
+    let recording = record("reconnects", |server, connection| {
+        connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+
+        server.drop_connections();
+        assert!(connection.read_symbol::<PlcInt>("MAIN.nCounter").is_err());
+
+        connection.run_connection_loop();
+        server.set_value("MAIN.nCounter", 9i16.to_le_bytes());
+
+        let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+        assert_eq!(i16::from(value), 9);
+    });
+
+    assert_eq!(recording.connections.len(), 2);
+
+    let replay = ReplayServer::start(recording);
+
+    let connection = replay.connection();
+    connection.run_connection_loop();
+
+    connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+
+    replay.drop_connections();
+    assert!(connection.read_symbol::<PlcInt>("MAIN.nCounter").is_err());
+
+    connection.run_connection_loop();
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+    assert_eq!(i16::from(value), 9);
+}
+
+#[test]
+fn requests_missing_from_the_recording_fail() {
+    let recording = record("missing", |_, connection| {
+        connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+    });
+
+    let replay = ReplayServer::start(recording);
+
+    let connection = replay.connection();
+    connection.run_connection_loop();
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nOther");
+
+    assert!(matches!(
+        result,
+        Err(PlcError::AdsDeviceError { code: 0x701, .. })
+    ));
+    assert_eq!(replay.unmatched_requests().len(), 1);
+    assert!(!replay.is_complete());
+}
+
+#[test]
+fn realtime_replays_keep_the_recorded_timing() {
+    let recording = record("realtime", |server, connection| {
+        server.set_reply_delay(Duration::from_millis(200));
+
+        connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+    });
+
+    let replay = ReplayServer::start(recording);
+    replay.set_realtime(true);
+
+    let connection = replay.connection();
+    connection.run_connection_loop();
+
+    let started = Instant::now();
+    connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
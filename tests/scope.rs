@@ -0,0 +1,77 @@
+use ads_client::{
+    data_types::primitives::{int::PlcInt, real::PlcReal},
+    plc_interface,
+    testing::MockAdsServer,
+};
+
+plc_interface! {
+    pub struct Drive {
+        prefix = "MAIN.fbDrive";
+        values = DriveValues;
+
+        var speed: PlcReal = "fSpeed";
+
+        rpc stop() = "Stop";
+    }
+}
+
+fn stations_server() -> MockAdsServer {
+    let server = MockAdsServer::start();
+
+    for station in 1..=2 {
+        server.add_symbol(
+            &format!("GVL.aStations[{station}].fbDrive.fSpeed"),
+            (station as f32).to_le_bytes(),
+        );
+        server.add_symbol(
+            &format!("GVL.aStations[{station}].nCount"),
+            0i16.to_le_bytes(),
+        );
+        server.add_rpc_method(&format!("GVL.aStations[{station}].fbDrive#Stop"), |_| {
+            Vec::new()
+        });
+    }
+
+    server
+}
+
+#[test]
+fn scopes_join_relative_paths() {
+    let server = stations_server();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let stations = connection.scope("GVL.aStations");
+
+    for station in 1..=2 {
+        let scope = stations.index(station);
+
+        assert_eq!(scope.prefix(), format!("GVL.aStations[{station}]"));
+
+        let speed: PlcReal = scope.scope("fbDrive").read_symbol("fSpeed").unwrap();
+        assert_eq!(f32::from(speed), station as f32);
+
+        scope.write_symbol("nCount", PlcInt::from(7)).unwrap();
+        assert_eq!(
+            server.value(&format!("GVL.aStations[{station}].nCount")),
+            Some(7i16.to_le_bytes().to_vec())
+        );
+    }
+}
+
+#[test]
+fn interfaces_can_point_at_any_instance() {
+    let server = stations_server();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let drive = Drive::in_scope(connection.scope("GVL.aStations").index(2).scope("fbDrive"));
+
+    drive.validate().unwrap();
+    drive.stop().unwrap();
+
+    assert_eq!(drive.speed().name(), "GVL.aStations[2].fbDrive.fSpeed");
+    assert_eq!(f32::from(drive.read_all().unwrap().speed), 2.0);
+}
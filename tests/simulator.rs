@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use ads::AdsState;
+use ads_client::{
+    data_types::primitives::{dint::PlcDInt, int::PlcInt, real::PlcReal},
+    error::PlcError,
+    testing::PlcSimulator,
+};
+
+const CYCLE_TIME: Duration = Duration::from_millis(5);
+
+#[test]
+fn driven_symbols_change_every_cycle() {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.drive("MAIN.nCounter", PlcInt::from(0), |_, value| {
+        PlcInt::from(i16::from(value) + 1)
+    });
+
+    let connection = simulator.connection();
+    connection.run_connection_loop();
+
+    let value = connection
+        .wait_until::<PlcInt>(
+            "MAIN.nCounter",
+            |value| i16::from(value.clone()) >= 5,
+            Duration::from_secs(2),
+        )
+        .unwrap();
+
+    assert!(i16::from(value) >= 5);
+}
+
+#[test]
+fn behaviours_can_follow_other_symbols() {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.add_symbol("MAIN.fSetpoint", PlcReal::from(0.0));
+    simulator.add_symbol("MAIN.fSpeed", PlcReal::from(0.0));
+    simulator.on_cycle(|cycle| {
+        let setpoint: PlcReal = cycle.read("MAIN.fSetpoint").unwrap();
+
+        cycle.write("MAIN.fSpeed", setpoint);
+    });
+
+    let connection = simulator.connection();
+    connection.run_connection_loop();
+
+    connection
+        .write_symbol("MAIN.fSetpoint", PlcReal::from(2.5))
+        .unwrap();
+
+    connection
+        .wait_until::<PlcReal>(
+            "MAIN.fSpeed",
+            |speed| f32::from(speed.clone()) == 2.5,
+            Duration::from_secs(2),
+        )
+        .unwrap();
+}
+
+#[test]
+fn behaviours_pause_while_stopped() {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.drive("MAIN.nCounter", PlcInt::from(0), |_, value| {
+        PlcInt::from(i16::from(value) + 1)
+    });
+
+    simulator.stop();
+
+    let stopped_at = simulator.value::<PlcInt>("MAIN.nCounter").unwrap();
+    std::thread::sleep(CYCLE_TIME * 10);
+
+    assert_eq!(
+        i16::from(simulator.value::<PlcInt>("MAIN.nCounter").unwrap()),
+        i16::from(stopped_at)
+    );
+
+    // Stopped PLCs are set back to run mode when connecting
+    let connection = simulator.server().builder().set_to_run_mode(true).build();
+    connection.try_connect().unwrap();
+
+    assert_eq!(simulator.ads_state(), AdsState::Run);
+}
+
+#[test]
+fn rpc_handlers_take_typed_parameters() {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.add_rpc_handler("MAIN.fbAxis#Double", |value: PlcDInt| {
+        PlcDInt::from(i32::from(value) * 2)
+    });
+
+    let connection = simulator.connection();
+    connection.run_connection_loop();
+
+    let doubled: PlcDInt = connection
+        .call_rpc_method("MAIN.fbAxis#Double", &[&21i32.to_le_bytes()])
+        .unwrap();
+    assert_eq!(i32::from(doubled), 42);
+
+    let result = connection.call_rpc_method::<PlcDInt>("MAIN.fbAxis#Double", &[&[1, 2]]);
+    assert!(matches!(result, Err(PlcError::SizeMismatch { .. })));
+}
+
+#[test]
+fn config_mode_drops_the_connection() {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.add_symbol("MAIN.nCounter", PlcInt::from(7));
+
+    let connection = simulator.connection();
+    connection.run_connection_loop();
+
+    simulator.config();
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nCounter");
+
+    assert!(matches!(
+        result,
+        Err(PlcError::AdsDeviceError { code: 0x006, .. })
+    ));
+    assert!(!connection.is_connected());
+    assert!(connection.try_connect().is_err());
+
+    simulator.run();
+    connection.try_connect().unwrap();
+
+    let value: PlcInt = connection.read_symbol("MAIN.nCounter").unwrap();
+    assert_eq!(i16::from(value), 7);
+}
+
+#[test]
+fn online_changes_invalidate_changed_symbols() {
+    let simulator = PlcSimulator::start(CYCLE_TIME);
+    simulator.add_symbol("MAIN.nCounter", PlcInt::from(7));
+
+    let connection = simulator.connection();
+    connection.run_connection_loop();
+
+    connection.read_symbol::<PlcInt>("MAIN.nCounter").unwrap();
+
+    simulator.online_change(|change| {
+        change.add_symbol("MAIN.nCounter", PlcDInt::from(70_000));
+        change.add_symbol("MAIN.bNew", PlcInt::from(1));
+    });
+
+    let result = connection.read_symbol::<PlcInt>("MAIN.nCounter");
+
+    assert!(matches!(
+        result,
+        Err(PlcError::AdsDeviceError { code: 0x711, .. })
+    ));
+    assert!(!connection.is_connected());
+
+    connection.run_connection_loop();
+
+    let value: PlcDInt = connection.read_symbol("MAIN.nCounter").unwrap();
+    assert_eq!(i32::from(value), 70_000);
+
+    let value: PlcInt = connection.read_symbol("MAIN.bNew").unwrap();
+    assert_eq!(i16::from(value), 1);
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use ads_client::{
+    data_types::primitives::{int::PlcInt, udint::PlcUDInt},
+    error::PlcError,
+    testing::MockAdsServer,
+};
+
+#[test]
+fn symbols_read_and_write_their_type() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let counter = connection.symbol::<PlcInt>("MAIN.nCounter");
+
+    assert_eq!(counter.name(), "MAIN.nCounter");
+    assert_eq!(i16::from(counter.read().unwrap()), 7);
+
+    counter.write(PlcInt::from(42)).unwrap();
+    assert_eq!(
+        server.value("MAIN.nCounter"),
+        Some(42i16.to_le_bytes().to_vec())
+    );
+}
+
+#[test]
+fn resolving_checks_the_symbol_exists() {
+    let server = MockAdsServer::start();
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let result = connection.symbol::<PlcInt>("MAIN.nCountr").resolve();
+
+    assert!(matches!(result, Err(PlcError::SymbolNotFound { .. })));
+}
+
+#[test]
+fn resolving_checks_the_symbol_size() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let counter = connection.symbol::<PlcUDInt>("MAIN.nCounter");
+
+    let error = counter.resolve().unwrap_err();
+
+    assert!(matches!(
+        error,
+        PlcError::SizeMismatch {
+            expected: 4,
+            actual: Some(2),
+            source: None,
+            ..
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Size of symbol MAIN.nCounter does not match the expected 4 bytes, it is 2 bytes on the PLC"
+    );
+    assert!(matches!(counter.read(), Err(PlcError::SizeMismatch { .. })));
+}
+
+#[test]
+fn symbols_resolve_again_after_reconnecting() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let counter = connection.symbol::<PlcInt>("MAIN.nCounter");
+    counter.resolve().unwrap();
+
+    assert!(counter.handle().is_some());
+
+    connection.disconnect();
+    connection.run_connection_loop();
+
+    assert_eq!(counter.handle(), None);
+    assert_eq!(i16::from(counter.read().unwrap()), 7);
+    assert!(counter.handle().is_some());
+}
+
+#[test]
+fn symbols_wait_for_values() {
+    let server = MockAdsServer::start();
+    server.add_symbol("MAIN.nCounter", 7i16.to_le_bytes());
+
+    let connection = server.connection();
+    connection.run_connection_loop();
+
+    let counter = connection.symbol::<PlcInt>("MAIN.nCounter");
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            std::thread::sleep(Duration::from_millis(50));
+
+            server.set_value("MAIN.nCounter", 9i16.to_le_bytes());
+        });
+
+        let value = counter
+            .wait_until(
+                |value| i16::from(value.clone()) == 9,
+                Duration::from_secs(2),
+            )
+            .unwrap();
+
+        assert_eq!(i16::from(value), 9);
+    });
+}